@@ -12,12 +12,20 @@ pub struct Atom {
     pub electrons: u8, // same as protons
 }
 
-#[allow(dead_code)]
 impl Atom {
     pub fn get_element_num(&self) -> u8 {
         self.element.number
     }
 
+    pub fn get_element(&self) -> &Element {
+        &self.element
+    }
+
+    /// Net charge, i.e. protons minus electrons
+    pub fn charge(&self) -> i8 {
+        self.element.number as i8 - self.electrons as i8
+    }
+
     pub fn get_config(&mut self) -> String {
         if self.configuration.is_none() {
             let cfg =
@@ -47,6 +55,7 @@ impl Atom {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input_str: &str) -> Option<Self> {
         let element = Element::from_str(input_str)?;
         Some(Atom {
@@ -73,6 +82,41 @@ impl Atom {
         iso
     }
 
+    /// Numbers of bonds the atom can form given its charge, lowest first.
+    ///
+    /// Charge shifts the standard valences like its isoelectronic
+    /// neighbour: N⁺ bonds like C, O⁻ like F, while C⁺ and C⁻ both form three
+    /// bonds. Empty when the element has no standard valence.
+    pub fn valences(&self) -> Vec<u8> {
+        let charge = self.charge() as i16;
+        let shift = match self.element.group() {
+            15..=17 => charge,
+            14 => -charge.abs(),
+            _ => -charge,
+        };
+        self.element
+            .standard_valences()
+            .iter()
+            .map(|&v| v as i16 + shift)
+            .filter(|&v| v >= 0)
+            .map(|v| v as u8)
+            .collect()
+    }
+
+    /// Returns a copy of the atom carrying the given charge.
+    pub fn ion(&self, charge: i8) -> Self {
+        let mut ion = self.clone();
+        ion.electrons = (self.element.number as i16 - charge as i16) as u8;
+        ion.configuration = None;
+        ion
+    }
+
+    /// Protium carrying no charge; the hydrogens that may be left implicit
+    /// or regenerated at will, unlike isotopes and hydrogen ions.
+    pub fn is_plain_hydrogen(&self) -> bool {
+        self.element.number == 1 && self.neutrons == 0 && self.charge() == 0
+    }
+
     pub fn bromine() -> Self {
         Atom::new_unchecked(35)
     }
@@ -134,4 +178,25 @@ mod tests {
         assert_eq!(Atom::new_unchecked(3).to_string(), "Li");
         assert_eq!(Atom::new_unchecked(118).to_string(), "Og");
     }
+
+    #[test]
+    fn atom_valences_follow_charge() {
+        let oxygen = Atom::new_unchecked(8);
+        assert_eq!(Atom::carbon().valences(), vec![4]);
+        assert_eq!(Atom::carbon().ion(1).valences(), vec![3]);
+        assert_eq!(Atom::carbon().ion(-1).valences(), vec![3]);
+        assert_eq!(Atom::nitrogen().ion(1).valences(), vec![4]);
+        assert_eq!(oxygen.valences(), vec![2]);
+        assert_eq!(oxygen.ion(-1).valences(), vec![1]);
+        assert_eq!(oxygen.ion(1).valences(), vec![3]);
+        assert_eq!(Atom::new_unchecked(5).ion(-1).valences(), vec![4]);
+    }
+
+    #[test]
+    fn atom_ion_charge() {
+        assert_eq!(Atom::carbon().charge(), 0);
+        assert_eq!(Atom::nitrogen().ion(1).charge(), 1);
+        assert_eq!(Atom::nitrogen().ion(1).electrons, 6);
+        assert_eq!(Atom::chlorine().ion(-1).charge(), -1);
+    }
 }
@@ -0,0 +1,249 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::matter::compound::{
+    bond::{bond_key, BondOrder},
+    lewis::LewisStructure,
+    Compound, CompoundResult,
+};
+
+/// Aromaticity perception by Hückel's rule: a ring (or pair of fused rings)
+/// is aromatic when every atom brings a p orbital to the conjugated system
+/// and the system holds 4n + 2 π electrons.
+impl Compound {
+    /// Rings which are aromatic by themselves or as part of a fused system,
+    /// each listed in ring order.
+    pub fn aromatic_rings(&self) -> CompoundResult<Vec<Vec<usize>>> {
+        let lewis = LewisStructure::from_compound(self)?;
+        let orders = self.bond_orders();
+        let rings = self.rings().relevant_cycles;
+        let sets: Vec<BTreeSet<usize>> = rings
+            .iter()
+            .map(|ring| ring.iter().copied().collect())
+            .collect();
+        let huckel = |system: &BTreeSet<usize>| {
+            system
+                .iter()
+                .map(|&i| self.pi_electrons(i, system, &orders, &lewis))
+                .sum::<Option<u8>>()
+                .is_some_and(|electrons| electrons % 4 == 2)
+        };
+        let mut aromatic: Vec<bool> = sets.iter().map(huckel).collect();
+        for a in 0..sets.len() {
+            for b in a + 1..sets.len() {
+                if aromatic[a] && aromatic[b]
+                    || sets[a].intersection(&sets[b]).count() < 2
+                {
+                    continue;
+                }
+                let fused = sets[a].union(&sets[b]).copied().collect();
+                if huckel(&fused) {
+                    aromatic[a] = true;
+                    aromatic[b] = true;
+                }
+            }
+        }
+        Ok(rings
+            .into_iter()
+            .zip(aromatic)
+            .filter(|(_, aromatic)| *aromatic)
+            .map(|(ring, _)| ring)
+            .collect())
+    }
+
+    /// Atoms belonging to an aromatic ring
+    pub fn aromatic_atoms(&self) -> CompoundResult<BTreeSet<usize>> {
+        Ok(self.aromatic_rings()?.into_iter().flatten().collect())
+    }
+
+    /// Bonds of the aromatic rings, lowest atom index first
+    pub fn aromatic_bonds(&self) -> CompoundResult<BTreeSet<(usize, usize)>> {
+        Ok(self
+            .aromatic_rings()?
+            .iter()
+            .flat_map(|ring| {
+                (0..ring.len())
+                    .map(|k| bond_key(ring[k], ring[(k + 1) % ring.len()]))
+            })
+            .collect())
+    }
+
+    /// Whether the compound contains an aromatic ring
+    pub fn is_aromatic(&self) -> CompoundResult<bool> {
+        Ok(!self.aromatic_rings()?.is_empty())
+    }
+
+    /// Every bond along with its order, aromatic bonds reported as
+    /// [`BondOrder::Aromatic`] in place of their Kekulé order.
+    pub fn aromatic_bond_orders(
+        &self,
+    ) -> CompoundResult<BTreeMap<(usize, usize), BondOrder>> {
+        let aromatic = self.aromatic_bonds()?;
+        Ok(self
+            .bond_orders()
+            .into_iter()
+            .map(|(key, order)| {
+                if aromatic.contains(&key) {
+                    (key, BondOrder::Aromatic)
+                } else {
+                    (key, order)
+                }
+            })
+            .collect())
+    }
+
+    /// π electrons atom `i` brings to the conjugated `system`: one for a
+    /// double bond within it, two for a lone pair next to it (pyrrole N,
+    /// furan O, carbanions) and none for an empty p orbital (carbocations)
+    /// or a double bond out to a more electronegative atom. `None` when the
+    /// atom doesn't take part in the conjugation (sp³ atoms, triple bonds).
+    fn pi_electrons(
+        &self,
+        i: usize,
+        system: &BTreeSet<usize>,
+        orders: &BTreeMap<(usize, usize), BondOrder>,
+        lewis: &LewisStructure,
+    ) -> Option<u8> {
+        let mut double = Vec::new();
+        for (&(a, b), order) in
+            orders.iter().filter(|(&(a, b), _)| a == i || b == i)
+        {
+            let other = if a == i { b } else { a };
+            match order {
+                BondOrder::Single => (),
+                BondOrder::Double | BondOrder::Aromatic => double.push(other),
+                BondOrder::Triple => return None,
+            }
+        }
+        let electronegativity =
+            |j: usize| self.atoms[j].get_element().electronegativity();
+        match double[..] {
+            [j] if system.contains(&j) => Some(1),
+            [j] if electronegativity(j) > electronegativity(i) => Some(0),
+            [] if lewis.lone_pairs[i] > 0 => Some(2),
+            [] if lewis.electrons_around(i)
+                + 2 * self.implicit_hydrogens(i)
+                < 8 =>
+            {
+                Some(0)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::{
+        atom::Atom,
+        compound::{
+            builder::{BuildStep, CompoundBuilder},
+            substituent::AlkylGroup,
+        },
+    };
+
+    fn build(steps: &[BuildStep]) -> Compound {
+        let mut builder = CompoundBuilder::new();
+        for step in steps {
+            step(&mut builder).expect("Valid step expected");
+        }
+        builder.build().expect("Valid compound expected")
+    }
+
+    #[test]
+    fn aromaticity_follows_huckel() {
+        let cases: [(&[BuildStep], bool); 8] = [
+            (&[|b| b.benzene()], true),
+            (
+                &[|b| b.benzene(), |b| b.replace_atom(1, Atom::nitrogen())],
+                true,
+            ),
+            (
+                &[
+                    |b| b.ring(5),
+                    |b| b.double_bond(2, 3),
+                    |b| b.double_bond(4, 5),
+                    |b| b.replace_atom(1, Atom::nitrogen()),
+                ],
+                true,
+            ),
+            (
+                &[
+                    |b| b.ring(5),
+                    |b| b.double_bond(2, 3),
+                    |b| b.double_bond(4, 5),
+                    |b| b.replace_atom(1, Atom::carbon().ion(-1)),
+                ],
+                true,
+            ),
+            (
+                &[
+                    |b| b.ring(7),
+                    |b| b.double_bond(2, 3),
+                    |b| b.double_bond(4, 5),
+                    |b| b.double_bond(6, 7),
+                    |b| b.replace_atom(1, Atom::carbon().ion(1)),
+                ],
+                true,
+            ),
+            (
+                &[
+                    |b| b.ring(5),
+                    |b| b.double_bond(2, 3),
+                    |b| b.double_bond(4, 5),
+                ],
+                false,
+            ),
+            (
+                &[
+                    |b| b.ring(4),
+                    |b| b.double_bond(1, 2),
+                    |b| b.double_bond(3, 4),
+                ],
+                false,
+            ),
+            (&[|b| b.ring(6)], false),
+        ];
+        for (steps, expected) in cases {
+            let compound = build(steps);
+            assert_eq!(
+                compound.is_aromatic().expect("Valid structure expected"),
+                expected,
+                "{}",
+                compound
+            );
+        }
+    }
+
+    #[test]
+    fn aromaticity_of_fused_rings() {
+        let naphthalene = build(&[
+            |b| b.ring(10),
+            |b| b.ring_closure(1, 6),
+            |b| b.double_bond(1, 2),
+            |b| b.double_bond(3, 4),
+            |b| b.double_bond(5, 6),
+            |b| b.double_bond(7, 8),
+            |b| b.double_bond(9, 10),
+        ]);
+        assert_eq!(naphthalene.molecular_formula(), "C10H8");
+        let rings = naphthalene.aromatic_rings().expect("Valid structure");
+        assert_eq!(rings.len(), 2);
+        assert_eq!(naphthalene.aromatic_bonds().unwrap().len(), 11);
+    }
+
+    #[test]
+    fn aromaticity_bond_orders() {
+        let toluene =
+            build(&[|b| b.benzene(), |b| b.add_alkyl(1, AlkylGroup::Methyl)]);
+        let orders = toluene.aromatic_bond_orders().expect("Valid structure");
+        let aromatic = orders
+            .values()
+            .filter(|&&order| order == BondOrder::Aromatic)
+            .count();
+        assert_eq!(aromatic, 6);
+        assert_eq!(toluene.aromatic_atoms().unwrap().len(), 6);
+        assert_eq!(orders[&(0, 1)], BondOrder::Aromatic);
+        assert_eq!(toluene.bond_order(0, 1), Some(BondOrder::Double));
+    }
+}
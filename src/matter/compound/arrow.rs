@@ -0,0 +1,625 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use crate::matter::{
+    atom::Atom,
+    compound::{
+        bond::{bond_key, BondOrder},
+        lewis::LewisStructure,
+        Compound, CompoundError, CompoundResult,
+    },
+};
+
+/// Where the tail of a curved arrow sits: a pair of electrons
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum ElectronSource {
+    LonePair(usize),
+    Bond(usize, usize),
+}
+
+/// Where the head of a curved arrow points
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum ElectronSink {
+    Atom(usize),
+    Bond(usize, usize),
+}
+
+/// Electron-pushing arrow moving one pair of electrons
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct Arrow {
+    pub from: ElectronSource,
+    pub to: ElectronSink,
+}
+
+impl Arrow {
+    pub fn new(from: ElectronSource, to: ElectronSink) -> Self {
+        Self { from, to }
+    }
+
+    /// Lone pair on `a` becomes a bond between `a` and `b`.
+    pub fn lone_pair_to_bond(a: usize, b: usize) -> Self {
+        Self::new(ElectronSource::LonePair(a), ElectronSink::Bond(a, b))
+    }
+
+    /// Bond between `a` and `b` collapses onto `b` as a lone pair.
+    pub fn bond_to_atom(a: usize, b: usize) -> Self {
+        Self::new(ElectronSource::Bond(a, b), ElectronSink::Atom(b))
+    }
+
+    /// Bond between `a` and `b` shifts to between `b` and `c`.
+    pub fn bond_to_bond(a: usize, b: usize, c: usize) -> Self {
+        Self::new(ElectronSource::Bond(a, b), ElectronSink::Bond(b, c))
+    }
+
+    /// Change in the electrons each atom owns (lone pair electrons plus
+    /// half of its bonding electrons).
+    fn ownership_changes(&self) -> [(usize, i8); 4] {
+        let [from_a, from_b] = match self.from {
+            ElectronSource::LonePair(a) => [(a, -2), (a, 0)],
+            ElectronSource::Bond(a, b) => [(a, -1), (b, -1)],
+        };
+        let [to_a, to_b] = match self.to {
+            ElectronSink::Atom(a) => [(a, 2), (a, 0)],
+            ElectronSink::Bond(a, b) => [(a, 1), (b, 1)],
+        };
+        [from_a, from_b, to_a, to_b]
+    }
+}
+
+impl fmt::Display for ElectronSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LonePair(a) => write!(f, "lone pair on {}", a),
+            Self::Bond(a, b) => write!(f, "bond {}-{}", a, b),
+        }
+    }
+}
+
+impl fmt::Display for ElectronSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Atom(a) => write!(f, "atom {}", a),
+            Self::Bond(a, b) => write!(f, "bond {}-{}", a, b),
+        }
+    }
+}
+
+impl fmt::Display for Arrow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} → {}", self.from, self.to)
+    }
+}
+
+/// What arrows drawn together do to a structure, tallied over every arrow
+#[derive(Default)]
+struct ElectronFlow {
+    /// Change in the order of each bond an arrow starts or ends at
+    orders: BTreeMap<(usize, usize), i8>,
+    /// Change in the electrons each atom owns
+    owned: BTreeMap<usize, i8>,
+    /// Lone pairs each atom gives away
+    lone_pairs: BTreeMap<usize, i8>,
+}
+
+impl ElectronFlow {
+    fn of(arrows: &[Arrow]) -> Self {
+        let mut flow = Self::default();
+        for arrow in arrows {
+            match arrow.from {
+                ElectronSource::LonePair(a) => {
+                    *flow.lone_pairs.entry(a).or_default() += 1;
+                }
+                ElectronSource::Bond(a, b) => {
+                    *flow.orders.entry(bond_key(a, b)).or_default() -= 1;
+                }
+            }
+            if let ElectronSink::Bond(a, b) = arrow.to {
+                *flow.orders.entry(bond_key(a, b)).or_default() += 1;
+            }
+            for (atom, change) in arrow.ownership_changes() {
+                *flow.owned.entry(atom).or_default() += change;
+            }
+        }
+        flow
+    }
+
+    /// The atoms charged by the electrons they gained or lost
+    fn charged(&self, atoms: &[Atom]) -> Vec<Atom> {
+        let mut atoms = atoms.to_vec();
+        for (&i, &change) in self.owned.iter() {
+            atoms[i] = atoms[i].ion(atoms[i].charge() - change);
+        }
+        atoms
+    }
+}
+
+impl Compound {
+    /// Moves electrons along the given arrows at once, returning the
+    /// resulting Compound with updated bond orders and charges.
+    ///
+    /// Arrows may only rearrange π bonds and lone pairs: every arrow must
+    /// start next to where it ends, σ bonds can't be made or broken, and no
+    /// atom may end up beyond its valence shell.
+    pub fn push_electrons(&self, arrows: &[Arrow]) -> CompoundResult<Self> {
+        for arrow in arrows {
+            let tail_atoms = match arrow.from {
+                ElectronSource::LonePair(a) => vec![a],
+                ElectronSource::Bond(a, b) => vec![a, b],
+            };
+            let adjacent = match arrow.to {
+                ElectronSink::Atom(a) => tail_atoms.contains(&a),
+                ElectronSink::Bond(a, b) => {
+                    tail_atoms.contains(&a) || tail_atoms.contains(&b)
+                }
+            };
+            if !adjacent {
+                return Err(CompoundError::Arrow(format!(
+                    "Arrow {} doesn't end next to where it starts",
+                    arrow
+                )));
+            }
+            let atoms = arrow.ownership_changes().map(|(atom, _)| atom);
+            if atoms.iter().any(|&atom| atom >= self.atom_count()) {
+                return Err(CompoundError::Arrow(format!(
+                    "Arrow {} references a missing atom",
+                    arrow
+                )));
+            }
+        }
+
+        let flow = ElectronFlow::of(arrows);
+        let mut compound = self.clone();
+        for (&(a, b), &change) in flow.orders.iter() {
+            let Some(order) = self.bond_order(a, b) else {
+                return Err(CompoundError::Arrow(format!(
+                    "Atoms {} and {} aren't bonded",
+                    a, b
+                )));
+            };
+            let order =
+                BondOrder::from_u8((order.as_u8() as i8 + change).max(0) as u8)
+                    .ok_or_else(|| {
+                        CompoundError::Arrow(format!(
+                            "Bond {}-{} can't take a bond order of {}",
+                            a,
+                            b,
+                            order.as_u8() as i8 + change
+                        ))
+                    })?;
+            compound.set_bond_order(a, b, order);
+        }
+        compound.atoms = flow.charged(&compound.atoms);
+        LewisStructure::from_compound(&compound)
+            .map_err(|e| CompoundError::Arrow(e.to_string()))?;
+        Ok(compound)
+    }
+}
+
+/// Why an arrow of a mechanism step can't be drawn
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+pub enum IllegalMove {
+    #[error("Atom {0} doesn't exist")]
+    MissingAtom(usize),
+    #[error("Arrow {0} doesn't end next to where it starts")]
+    Detached(Arrow),
+    #[error("Atom {0} ({1}) has no lone pair left to give")]
+    NoLonePair(usize, String),
+    #[error("Atoms {0} and {1} aren't bonded")]
+    NotBonded(usize, usize),
+    #[error("Bond {0}-{1} can't take a bond order of {2}")]
+    BondOrder(usize, usize, i8),
+    /// Atom, its symbol and the electrons it would have around it, such
+    /// as 10 for a pentavalent carbon
+    #[error("Atom {0} ({1}) would have {2} valence electrons")]
+    ExceedsValenceShell(usize, String, u8),
+}
+
+/// Arrows drawn together, moving electrons in one elementary step
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MechanismStep {
+    pub arrows: Vec<Arrow>,
+}
+
+impl MechanismStep {
+    pub fn new(arrows: Vec<Arrow>) -> Self {
+        Self { arrows }
+    }
+}
+
+/// First illegal move of a mechanism, with the (0-based) step it's in
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+#[error("Step {}: {problem}", step + 1)]
+pub struct StepError {
+    pub step: usize,
+    pub problem: IllegalMove,
+}
+
+/// Electron-pushing mechanism: steps run in order on one Compound holding
+/// every species taking part, each as a separate fragment, so that atom
+/// indices stay the same from step to step. Hydrogens moved by an arrow
+/// must be explicit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArrowMechanism {
+    pub steps: Vec<MechanismStep>,
+}
+
+impl ArrowMechanism {
+    pub fn new(steps: Vec<MechanismStep>) -> Self {
+        Self { steps }
+    }
+
+    /// Structure after each step, the last being the products
+    pub fn intermediates(
+        &self,
+        start: &Compound,
+    ) -> CompoundResult<Vec<Compound>> {
+        self.run(start)
+            .map_err(|e| CompoundError::Arrow(e.to_string()))
+    }
+
+    /// Checks every step can be drawn, stopping at the first which can't
+    pub fn validate(&self, start: &Compound) -> Result<(), StepError> {
+        self.run(start).map(|_| ())
+    }
+
+    fn run(&self, start: &Compound) -> Result<Vec<Compound>, StepError> {
+        let mut structures: Vec<Compound> = Vec::new();
+        for (step, arrows) in self.steps.iter().enumerate() {
+            let current = structures.last().unwrap_or(start);
+            let next = current
+                .apply_arrows(&arrows.arrows)
+                .map_err(|problem| StepError { step, problem })?;
+            structures.push(next);
+        }
+        Ok(structures)
+    }
+}
+
+impl Compound {
+    /// Electrons on atom `i` outside its bonds
+    fn nonbonding_electrons(&self, i: usize) -> i8 {
+        let atom = &self.atoms[i];
+        atom.get_element().valence_electrons() as i8
+            - atom.charge()
+            - self.bonding_pairs(i) as i8
+    }
+
+    /// Electron pairs atom `i` shares, implicit hydrogens included
+    fn bonding_pairs(&self, i: usize) -> u8 {
+        self.implicit_hydrogens(i)
+            + self
+                .neighbors(i)
+                .into_iter()
+                .filter_map(|j| self.bond_order(i, j))
+                .map(|order| order.as_u8())
+                .sum::<u8>()
+    }
+
+    /// Moves electrons along the arrows of one mechanism step at once.
+    ///
+    /// Unlike [`Self::push_electrons`], arrows may form bonds between atoms
+    /// which weren't bonded and break σ bonds, leaving the fragments in the
+    /// same Compound. Atoms whose bonds change lose their stereo
+    /// configuration.
+    pub fn apply_arrows(&self, arrows: &[Arrow]) -> Result<Self, IllegalMove> {
+        for arrow in arrows {
+            let (source_atoms, sink_atoms) = match (arrow.from, arrow.to) {
+                (ElectronSource::LonePair(a), ElectronSink::Bond(b, c)) => {
+                    ([a, a], [b, c])
+                }
+                (ElectronSource::LonePair(_), ElectronSink::Atom(_)) => {
+                    return Err(IllegalMove::Detached(*arrow));
+                }
+                (ElectronSource::Bond(a, b), ElectronSink::Atom(c)) => {
+                    ([a, b], [c, c])
+                }
+                (ElectronSource::Bond(a, b), ElectronSink::Bond(c, d)) => {
+                    ([a, b], [c, d])
+                }
+            };
+            if let Some(&i) = source_atoms
+                .iter()
+                .chain(sink_atoms.iter())
+                .find(|&&i| i >= self.atom_count())
+            {
+                return Err(IllegalMove::MissingAtom(i));
+            }
+            if !sink_atoms.iter().any(|i| source_atoms.contains(i))
+                || sink_atoms[0] == sink_atoms[1]
+                    && matches!(arrow.to, ElectronSink::Bond(..))
+            {
+                return Err(IllegalMove::Detached(*arrow));
+            }
+        }
+        let flow = ElectronFlow::of(arrows);
+        for (&a, &used) in flow.lone_pairs.iter() {
+            if used > self.nonbonding_electrons(a) / 2 {
+                let symbol = self.atoms[a].to_string();
+                return Err(IllegalMove::NoLonePair(a, symbol));
+            }
+        }
+        let before = self.bond_orders();
+        let touched: BTreeSet<(usize, usize)> =
+            before.keys().chain(flow.orders.keys()).copied().collect();
+        let mut bonds = BTreeMap::new();
+        for (a, b) in touched {
+            let old = before.get(&(a, b)).map_or(0, |order| order.as_u8());
+            let order = old as i8 + flow.orders.get(&(a, b)).unwrap_or(&0);
+            if order == 0 {
+                continue;
+            }
+            if order < 0 && old == 0 {
+                return Err(IllegalMove::NotBonded(a, b));
+            }
+            let order = u8::try_from(order)
+                .ok()
+                .and_then(BondOrder::from_u8)
+                .ok_or(IllegalMove::BondOrder(a, b, order))?;
+            bonds.insert((a, b), order);
+        }
+
+        let atoms = flow.charged(&self.atoms);
+        let mut compound = Compound::from_bonds(atoms, &bonds);
+        compound.implicit_hydrogens = self.implicit_hydrogens.clone();
+        let changed: BTreeSet<usize> = before
+            .iter()
+            .filter(|(bond, order)| bonds.get(bond) != Some(order))
+            .chain(bonds.iter().filter(|(bond, _)| !before.contains_key(bond)))
+            .flat_map(|(&(a, b), _)| [a, b])
+            .collect();
+        compound.chirality = self
+            .chirality
+            .iter()
+            .filter(|(i, _)| !changed.contains(i))
+            .map(|(&i, &around)| (i, around))
+            .collect();
+        compound.double_bond_stereo = self
+            .double_bond_stereo
+            .iter()
+            .filter(|((a, b), _)| !changed.contains(a) && !changed.contains(b))
+            .map(|(&bond, &cis)| (bond, cis))
+            .collect();
+
+        for &i in changed.iter().chain(flow.owned.keys()) {
+            let element = compound.atoms[i].get_element();
+            let electrons = compound.nonbonding_electrons(i)
+                + 2 * compound.bonding_pairs(i) as i8;
+            if electrons > element.valence_shell_capacity() as i8 {
+                let symbol = compound.atoms[i].to_string();
+                return Err(IllegalMove::ExceedsValenceShell(
+                    i,
+                    symbol,
+                    electrons as u8,
+                ));
+            }
+        }
+        Ok(compound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matter::atom::Atom;
+
+    use super::*;
+
+    fn formaldehyde() -> Compound {
+        let oxygen = Atom::new_unchecked(8);
+        let edges = [
+            (0, 1, BondOrder::Double),
+            (0, 2, BondOrder::Single),
+            (0, 3, BondOrder::Single),
+        ];
+        let atoms =
+            vec![Atom::carbon(), oxygen, Atom::hydrogen(), Atom::hydrogen()];
+        Compound::from_edges(atoms, &edges)
+    }
+
+    /// Compound of several fragments, with implicit hydrogens given by
+    /// atom
+    fn species(
+        atoms: Vec<Atom>,
+        bonds: &[(usize, usize, BondOrder)],
+        hydrogens: &[(usize, u8)],
+    ) -> Compound {
+        let bonds = bonds
+            .iter()
+            .map(|&(a, b, order)| (bond_key(a, b), order))
+            .collect();
+        let mut compound = Compound::from_bonds(atoms, &bonds);
+        compound.implicit_hydrogens = hydrogens.iter().copied().collect();
+        compound
+    }
+
+    fn hydroxide() -> Atom {
+        Atom::new_unchecked(8).ion(-1)
+    }
+
+    #[test]
+    fn arrow_bond_to_atom_separates_charge() {
+        let pushed = formaldehyde()
+            .push_electrons(&[Arrow::bond_to_atom(0, 1)])
+            .expect("Arrow should be legal");
+        assert_eq!(pushed.bond_order(0, 1), Some(BondOrder::Single));
+        assert_eq!(pushed.atoms()[0].charge(), 1);
+        assert_eq!(pushed.atoms()[1].charge(), -1);
+    }
+
+    #[test]
+    fn arrow_round_trip() {
+        let compound = formaldehyde();
+        let pushed = compound
+            .push_electrons(&[Arrow::bond_to_atom(0, 1)])
+            .and_then(|c| c.push_electrons(&[Arrow::lone_pair_to_bond(1, 0)]))
+            .expect("Arrows should be legal");
+        assert_eq!(pushed, compound);
+    }
+
+    #[test]
+    fn arrow_rejects_illegal_moves() {
+        let compound = formaldehyde();
+        // Breaking a σ bond
+        assert!(compound
+            .push_electrons(&[Arrow::bond_to_atom(0, 2)])
+            .is_err());
+        // Hydrogen has no lone pair to give
+        assert!(compound
+            .push_electrons(&[Arrow::lone_pair_to_bond(2, 0)])
+            .is_err());
+        // Carbon would be pentavalent
+        assert!(compound
+            .push_electrons(&[Arrow::lone_pair_to_bond(1, 0)])
+            .is_err());
+        // Electrons can't jump to an unrelated atom
+        assert!(compound
+            .push_electrons(&[Arrow::new(
+                ElectronSource::LonePair(1),
+                ElectronSink::Atom(2)
+            )])
+            .is_err());
+    }
+
+    #[test]
+    fn arrow_mechanism_forms_and_breaks_bonds() {
+        // SN2: hydroxide and bromomethane give methanol and bromide
+        let start = species(
+            vec![hydroxide(), Atom::carbon(), Atom::bromine()],
+            &[(1, 2, BondOrder::Single)],
+            &[(0, 1), (1, 3)],
+        );
+        let sn2 = ArrowMechanism::new(vec![MechanismStep::new(vec![
+            Arrow::lone_pair_to_bond(0, 1),
+            Arrow::bond_to_atom(1, 2),
+        ])]);
+        let [products] = &sn2.intermediates(&start).expect("Legal step")[..]
+        else {
+            panic!("One structure per step expected");
+        };
+        assert_eq!(products.bond_order(0, 1), Some(BondOrder::Single));
+        assert_eq!(products.bond_order(1, 2), None);
+        let charges: Vec<i8> =
+            products.atoms().iter().map(|a| a.charge()).collect();
+        assert_eq!(charges, vec![0, 0, -1]);
+
+        // E2: hydroxide takes a proton from bromoethane as the C=C forms
+        let start = species(
+            vec![
+                hydroxide(),
+                Atom::carbon(),
+                Atom::hydrogen(),
+                Atom::carbon(),
+                Atom::bromine(),
+            ],
+            &[
+                (1, 2, BondOrder::Single),
+                (1, 3, BondOrder::Single),
+                (3, 4, BondOrder::Single),
+            ],
+            &[(0, 1), (1, 2), (3, 2)],
+        );
+        let e2 = ArrowMechanism::new(vec![MechanismStep::new(vec![
+            Arrow::lone_pair_to_bond(0, 2),
+            Arrow::bond_to_bond(2, 1, 3),
+            Arrow::bond_to_atom(3, 4),
+        ])]);
+        let products = e2.intermediates(&start).expect("Legal step");
+        let products = &products[0];
+        assert_eq!(products.bond_order(0, 2), Some(BondOrder::Single));
+        assert_eq!(products.bond_order(1, 2), None);
+        assert_eq!(products.bond_order(1, 3), Some(BondOrder::Double));
+        assert_eq!(products.atoms()[0].charge(), 0);
+        assert_eq!(products.atoms()[4].charge(), -1);
+    }
+
+    #[test]
+    fn arrow_mechanism_intermediates() {
+        // SN1 of tert-butyl bromide in water: ionisation, then attack
+        let start = species(
+            vec![
+                Atom::carbon(),
+                Atom::carbon(),
+                Atom::carbon(),
+                Atom::carbon(),
+                Atom::bromine(),
+                Atom::new_unchecked(8),
+            ],
+            &[
+                (0, 1, BondOrder::Single),
+                (0, 2, BondOrder::Single),
+                (0, 3, BondOrder::Single),
+                (0, 4, BondOrder::Single),
+            ],
+            &[(1, 3), (2, 3), (3, 3), (5, 2)],
+        );
+        let sn1 = ArrowMechanism::new(vec![
+            MechanismStep::new(vec![Arrow::bond_to_atom(0, 4)]),
+            MechanismStep::new(vec![Arrow::lone_pair_to_bond(5, 0)]),
+        ]);
+        assert_eq!(sn1.validate(&start), Ok(()));
+        let structures = sn1.intermediates(&start).expect("Legal steps");
+        assert_eq!(structures.len(), 2);
+        let [carbocation, oxonium] = &structures[..] else {
+            unreachable!();
+        };
+        assert_eq!(carbocation.atoms()[0].charge(), 1);
+        assert_eq!(carbocation.neighbors(0).len(), 3);
+        assert_eq!(oxonium.atoms()[0].charge(), 0);
+        assert_eq!(oxonium.atoms()[5].charge(), 1);
+        assert_eq!(oxonium.bond_order(0, 5), Some(BondOrder::Single));
+    }
+
+    #[test]
+    fn arrow_mechanism_rejects_illegal_moves() {
+        let start = species(
+            vec![hydroxide(), Atom::carbon(), Atom::bromine()],
+            &[(1, 2, BondOrder::Single)],
+            &[(0, 1), (1, 3)],
+        );
+        let problem = |steps: Vec<Vec<Arrow>>| {
+            ArrowMechanism::new(
+                steps.into_iter().map(MechanismStep::new).collect(),
+            )
+            .validate(&start)
+            .expect_err("Illegal move expected")
+        };
+        // Attack without the bromide leaving: a pentavalent carbon
+        assert_eq!(
+            problem(vec![vec![Arrow::lone_pair_to_bond(0, 1)]]),
+            StepError {
+                step: 0,
+                problem: IllegalMove::ExceedsValenceShell(1, "C".into(), 10)
+            }
+        );
+        // The bromide leaves, then the carbocation can't give a lone pair
+        assert_eq!(
+            problem(vec![
+                vec![Arrow::bond_to_atom(1, 2)],
+                vec![Arrow::lone_pair_to_bond(1, 0)],
+            ]),
+            StepError {
+                step: 1,
+                problem: IllegalMove::NoLonePair(1, "C".into())
+            }
+        );
+        assert_eq!(
+            problem(vec![vec![Arrow::bond_to_atom(0, 1)]]).problem,
+            IllegalMove::NotBonded(0, 1)
+        );
+        let stray =
+            Arrow::new(ElectronSource::Bond(1, 2), ElectronSink::Atom(0));
+        assert_eq!(
+            problem(vec![vec![stray]]).problem,
+            IllegalMove::Detached(stray)
+        );
+        assert_eq!(
+            problem(vec![vec![Arrow::lone_pair_to_bond(0, 7)]]).problem,
+            IllegalMove::MissingAtom(7)
+        );
+        assert_eq!(
+            problem(vec![vec![Arrow::lone_pair_to_bond(0, 1)]]).to_string(),
+            "Step 1: Atom 1 (C) would have 10 valence electrons"
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BondOrder {
+    Single,
+    Double,
+    Triple,
+    /// Bond of an aromatic ring, halfway between single and double. Only
+    /// reported by aromaticity perception; recorded bond orders stay in a
+    /// Kekulé structure.
+    Aromatic,
+}
+
+impl BondOrder {
+    pub fn from_u8(order: u8) -> Option<Self> {
+        match order {
+            1 => Some(Self::Single),
+            2 => Some(Self::Double),
+            3 => Some(Self::Triple),
+            _ => None,
+        }
+    }
+
+    /// Whole bond order; an aromatic bond counts as its σ bond only, its π
+    /// electron being shared around the ring.
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Self::Single | Self::Aromatic => 1,
+            Self::Double => 2,
+            Self::Triple => 3,
+        }
+    }
+
+    /// Number of electrons shared by the bond
+    pub fn electrons(&self) -> u8 {
+        match self {
+            Self::Aromatic => 3,
+            _ => 2 * self.as_u8(),
+        }
+    }
+}
+
+impl fmt::Display for BondOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Single => write!(f, "-"),
+            Self::Double => write!(f, "="),
+            Self::Triple => write!(f, "≡"),
+            Self::Aromatic => write!(f, ":"),
+        }
+    }
+}
+
+/// Bonds are keyed by their atom indices, lowest index first.
+pub fn bond_key(a: usize, b: usize) -> (usize, usize) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
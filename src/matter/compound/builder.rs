@@ -1,14 +1,17 @@
-use std::{
-    collections::{BTreeSet, HashMap},
-    error::Error,
-};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 
 use crate::matter::{
     atom::Atom,
-    compound::{Compound, Location},
+    compound::{
+        bond::{bond_key, BondOrder},
+        reindex::Reindex,
+        stereo::{CipDescriptor, EzDescriptor},
+        substituent::{AlkylGroup, ArenePosition, Substituent},
+        tree_bonds, Compound, Location,
+    },
 };
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct CompoundBuilder {
     /// Covalent Compound
     atoms: Vec<Atom>,
@@ -23,16 +26,36 @@ pub struct CompoundBuilder {
     /// ^ sort of like an undirected graph
     side_chains: HashMap<usize, BTreeSet<usize>>,
     // TODO: Ensure values != key or backbone idx
+    /// Orders of the bonds which aren't single bonds
+    bond_orders: HashMap<(usize, usize), BondOrder>,
+    /// Bonds closing rings, beyond the backbone and its side chains
+    ring_closures: BTreeSet<(usize, usize)>,
+    /// Requested configurations, by labeled atom number
+    stereocenters: BTreeMap<usize, CipDescriptor>,
+    /// Requested double bond configurations, by labeled atom numbers
+    double_bond_stereo: BTreeMap<(usize, usize), EzDescriptor>,
 }
 
 pub type CompoundBuilderResult<T> = Result<T, CompoundBuilderError>;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(thiserror::Error, strum_macros::Display, Clone, Debug)]
 pub enum CompoundBuilderError {
     SideChainError(String), // less generalized
-    OctetError(String),
+    /// Offending atom index and the reason
+    OctetError(usize, String),
     NoSuchBackboneError(String),
     NoRemoteAtomsError(String),
+    UnknownSubstituentError(String),
+    NoSuchBondError(String),
+    RingError(String),
+    StereoError(String),
+}
+
+impl Default for CompoundBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CompoundBuilder {
@@ -43,6 +66,10 @@ impl CompoundBuilder {
             locations: Vec::new(),
             location_to_idx: HashMap::new(),
             side_chains: HashMap::new(),
+            bond_orders: HashMap::new(),
+            ring_closures: BTreeSet::new(),
+            stereocenters: BTreeMap::new(),
+            double_bond_stereo: BTreeMap::new(),
         }
     }
 
@@ -52,7 +79,6 @@ impl CompoundBuilder {
         todo!()
     }
 
-    #[allow(dead_code)]
     fn has_side_chain(&self, idx: usize) -> bool {
         if let Some(side_chain) = self.side_chains.get(&idx) {
             !side_chain.is_empty()
@@ -111,135 +137,591 @@ impl CompoundBuilder {
 
     fn gen_locations(&mut self) -> CompoundBuilderResult<()> {
         // Called after octets are completed.
-        let mut locations = Vec::new();
+        let mut locations: Vec<Option<Location>> = vec![None; self.atoms.len()];
         let mut locations_to_idx = HashMap::new();
-        let backbone_len = self.backbone.len();
-        self.backbone.iter().for_each(|&i| {
-            let loc = Location::new(i as i16, 0);
-            locations.push(loc);
+        // A backbone closed into a ring folds back on itself, so that
+        // every ring bond joins neighbouring cells
+        let len = self.backbone.len();
+        let folded = len >= 3
+            && self
+                .ring_closures
+                .contains(&bond_key(self.backbone[0], self.backbone[len - 1]));
+        let top = len.div_ceil(2);
+        for (pos, &i) in self.backbone.iter().enumerate() {
+            let loc = if folded && pos >= top {
+                Location::new((len - 1 - pos) as i16, -1)
+            } else {
+                Location::new(pos as i16, 0)
+            };
+            locations[i] = Some(loc);
             locations_to_idx.insert(loc, i);
-        });
-        for i in 0..backbone_len {
-            let base_loc = *locations.get(i).expect("Backbone loc not found.");
+        }
+        // Side chains are placed breadth-first, outwards from the backbone
+        let mut queue: VecDeque<usize> =
+            self.backbone.iter().copied().collect();
+        while let Some(i) = queue.pop_front() {
+            let base_loc =
+                locations[i].expect("Parent placed before its side chain");
             let remote_atoms = self.get_remote_side_chains(i)?;
-            if remote_atoms.len() > 4 {
+            if remote_atoms.len() > 4
+                && !self.atoms[i].get_element().can_expand_octet()
+            {
                 return Err(CompoundBuilderError::OctetError(
-                    "Expanded octet prohibited (for now)".into(),
+                    i,
+                    "Expanded octet needs an element of period 3 or later"
+                        .into(),
                 ));
             }
-            let choices = Vec::from([(-1, 0), (1, 0), (0, 1), (0, -1)]);
-            // LEFT, RIGHT, UP, DOWN
             for (idx, _) in remote_atoms {
-                let side_chain_loc = choices
-                    .iter()
-                    .find_map(|&(dx, dy)| {
-                        let loc = base_loc.shift(dx, dy);
-                        if locations_to_idx.contains_key(&loc) {
-                            None // invalid because taken
-                        } else {
-                            Some(loc)
-                        }
-                    })
-                    .ok_or_else(|| {
-                        CompoundBuilderError::SideChainError(
-                            // octet likely exceed for side chain atom
-                            "Location assignment failed".into(),
-                        )
-                    })?;
-                assert!(
-                    locations.len() == idx,
-                    "Location vector length - remote atom's index mismatch",
-                );
-                locations.push(side_chain_loc); // clones
+                // LEFT, RIGHT, UP, DOWN, then further out once crowded
+                let side_chain_loc = base_loc.nearest_free(&locations_to_idx);
+                locations[idx] = Some(side_chain_loc);
                 locations_to_idx.insert(side_chain_loc, idx);
+                queue.push_back(idx);
             }
         }
-        self.locations = locations;
+        self.locations = locations
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| {
+                CompoundBuilderError::SideChainError(
+                    "Atom isn't connected to the backbone".into(),
+                )
+            })?;
         self.location_to_idx = locations_to_idx;
         Ok(())
     }
 
-    fn satisfy_backbone_octets(&mut self) {
-        // ONLY for linear compound generation
-        // Satisfy backbone octets
-        let backbone_len = self.backbone.len();
-        let mut working_idx = backbone_len;
-        for i in 0..backbone_len {
-            let mut primary_chain_atoms = 0;
-            if i < backbone_len - 1 {
-                // something comes after it
-                primary_chain_atoms += 1;
-            }
-            if i > 0 {
-                // something comes before it
-                primary_chain_atoms += 1;
-            }
-            let mut side_chain_len =
-                self.side_chains.get(&i).map(|c| c.len()).unwrap_or(0);
-            while primary_chain_atoms + side_chain_len < 4 {
+    /// Index of the atom at a (1-based) backbone atom number
+    fn backbone_idx(&self, atom_num: usize) -> CompoundBuilderResult<usize> {
+        if atom_num == 0 || atom_num > self.backbone.len() {
+            return Err(CompoundBuilderError::NoSuchBackboneError(format!(
+                "No backbone atom with atom num: {} (backbone length is {})",
+                atom_num,
+                self.backbone.len()
+            )));
+        }
+        Ok(self.backbone[atom_num - 1])
+    }
+
+    /// Checks `idx` is the index of a heavy atom, in the backbone or in a
+    /// side chain
+    fn heavy_atom_idx(&self, idx: usize) -> CompoundBuilderResult<usize> {
+        match self.atoms.get(idx) {
+            Some(atom) if !atom.is_plain_hydrogen() => Ok(idx),
+            _ => Err(CompoundBuilderError::SideChainError(format!(
+                "No heavy atom at index {} to attach a group to",
+                idx
+            ))),
+        }
+    }
+
+    /// Every bond, ring closures included
+    fn bonds(&self) -> BTreeSet<(usize, usize)> {
+        let mut bonds = tree_bonds(&self.backbone, &self.side_chains);
+        bonds.extend(self.ring_closures.iter().copied());
+        bonds
+    }
+
+    /// Sum of the bond orders around every atom
+    fn bond_order_sums(&self) -> Vec<u8> {
+        let mut sums = vec![0; self.atoms.len()];
+        for key in self.bonds() {
+            let order = self
+                .bond_orders
+                .get(&key)
+                .copied()
+                .unwrap_or(BondOrder::Single)
+                .as_u8();
+            sums[key.0] += order;
+            sums[key.1] += order;
+        }
+        sums
+    }
+
+    /// Drops the given atoms, shifting the remaining indices down.
+    fn remove_atoms(&mut self, removed: &BTreeSet<usize>) {
+        let reindex = Reindex::removing(self.atoms.len(), removed);
+        self.atoms = reindex.items(std::mem::take(&mut self.atoms));
+        self.backbone = reindex.indices(&self.backbone);
+        self.side_chains = reindex.side_chains(&self.side_chains);
+        self.bond_orders = reindex.bond_map(&self.bond_orders);
+        self.ring_closures = reindex.bonds(&self.ring_closures);
+    }
+
+    /// Satisfies every atom's valence with hydrogens.
+    ///
+    /// Plain hydrogens are stripped and re-added so that each atom reaches
+    /// the lowest standard valence (given its charge) at or above the bonds
+    /// it already forms, accounting for bond orders.
+    fn fill_hydrogens(&mut self) {
+        let hydrogens: BTreeSet<usize> = (0..self.atoms.len())
+            .filter(|&i| {
+                self.atoms[i].is_plain_hydrogen() && !self.backbone.contains(&i)
+            })
+            .collect();
+        self.remove_atoms(&hydrogens);
+        let sums = self.bond_order_sums();
+        for (i, bonds) in sums.into_iter().enumerate() {
+            let Some(&valence) =
+                self.atoms[i].valences().iter().find(|&&v| v >= bonds)
+            else {
+                continue; // Reported by `validate`
+            };
+            for _ in bonds..valence {
+                let hydrogen_i = self.atoms.len();
                 self.atoms.push(Atom::hydrogen());
-                self.side_chains.entry(i).or_default().insert(working_idx);
-                working_idx += 1;
-                side_chain_len =
-                    self.side_chains.get(&i).map(|c| c.len()).unwrap_or(0);
-                // I could simplify this line
+                self.side_chains.entry(i).or_default().insert(hydrogen_i);
             }
         }
     }
 
-    pub fn build(&mut self) -> Compound {
-        Compound::new(
+    /// Checks atom `i` forms one of its standard numbers of bonds, given
+    /// the bond order sums of every atom.
+    fn check_valence(
+        &self,
+        i: usize,
+        sums: &[u8],
+    ) -> CompoundBuilderResult<()> {
+        let atom = &self.atoms[i];
+        let valences = atom.valences();
+        let bonds = sums[i];
+        if !valences.is_empty() && !valences.contains(&bonds) {
+            return Err(CompoundBuilderError::OctetError(
+                i,
+                format!(
+                    "{} (charge {}) forms {} bonds, expected one of {:?}",
+                    atom,
+                    atom.charge(),
+                    bonds,
+                    valences
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks every atom forms one of its standard numbers of bonds.
+    fn validate(&self) -> CompoundBuilderResult<()> {
+        let sums = self.bond_order_sums();
+        (0..self.atoms.len()).try_for_each(|i| self.check_valence(i, &sums))
+    }
+
+    /// Validates every atom's valence and builds the Compound.
+    pub fn build(&mut self) -> CompoundBuilderResult<Compound> {
+        self.validate()?;
+        let mut compound = Compound::new(
             self.atoms.clone(),
             self.locations.clone(),
             self.location_to_idx.clone(),
             self.backbone.clone(),
             self.side_chains.clone(),
-        )
+            self.bond_orders.clone(),
+        );
+        compound.ring_closures = self.ring_closures.clone();
+        for (&atom_num, &descriptor) in self.stereocenters.iter() {
+            compound
+                .set_cip_descriptor(self.backbone_idx(atom_num)?, descriptor)
+                .map_err(|e| {
+                    CompoundBuilderError::StereoError(e.to_string())
+                })?;
+        }
+        for (&(a, b), &descriptor) in self.double_bond_stereo.iter() {
+            let (a, b) = (self.backbone_idx(a)?, self.backbone_idx(b)?);
+            compound.set_ez_descriptor(a, b, descriptor).map_err(|e| {
+                CompoundBuilderError::StereoError(e.to_string())
+            })?;
+        }
+        Ok(compound)
     }
 
-    /// Adds a bromine to a certain atom in a Compound.
+    /// Gives a backbone atom the configuration with the given CIP
+    /// descriptor. Priorities are only ranked once the compound is built,
+    /// so this may come before the substituents making it a stereocenter.
     ///
     /// # Arguments
     ///
     /// `atom_num` - Labeled atom number in the compound
+    /// `descriptor` - R or S
+    pub fn stereocenter(
+        &mut self,
+        atom_num: usize,
+        descriptor: CipDescriptor,
+    ) -> CompoundBuilderResult<&mut Self> {
+        self.backbone_idx(atom_num)?;
+        self.stereocenters.insert(atom_num, descriptor);
+        Ok(self)
+    }
+
+    /// Replaces a backbone atom (e.g. with a heteroatom), refilling
+    /// hydrogens around it.
     ///
-    /// # Returns
+    /// # Arguments
     ///
-    /// A Compound resulting from bromination.
-    pub fn brominate(
+    /// `atom_num` - Labeled atom number in the compound
+    /// `atom` - Atom taking its place
+    pub fn replace_atom(
         &mut self,
         atom_num: usize,
+        atom: Atom,
     ) -> CompoundBuilderResult<&mut Self> {
-        let i = atom_num - 1;
-        if atom_num > self.backbone.len() {
-            return Err(CompoundBuilderError::NoSuchBackboneError(format!(
-                "Cannot brominate non-existant backbone atom with atom num: {}",
-                atom_num
-            )));
-        } else if !self.has_side_chain(i) {
-            return Err(CompoundBuilderError::NoRemoteAtomsError(
-                "No remote atoms exist to brominate".into(),
+        let i = self.backbone_idx(atom_num)?;
+        self.atoms[i] = atom;
+        self.fill_hydrogens();
+        self.gen_locations()?;
+        Ok(self)
+    }
+
+    /// Swaps a hydrogen on a backbone atom for a group, bonding the group
+    /// through its first atom and refilling hydrogens.
+    ///
+    /// # Arguments
+    ///
+    /// `atom_num` - Labeled atom number in the compound
+    /// `group` - Atom or group replacing the hydrogen
+    pub fn substitute(
+        &mut self,
+        atom_num: usize,
+        group: Substituent,
+    ) -> CompoundBuilderResult<&mut Self> {
+        self.attach_group(
+            self.backbone_idx(atom_num)?,
+            &group.to_string(),
+            group.atoms(),
+            group.bonds(),
+        )
+    }
+
+    /// Swaps a hydrogen on a backbone atom for an alkyl group, which may
+    /// itself be branched.
+    ///
+    /// # Arguments
+    ///
+    /// `atom_num` - Labeled atom number in the compound
+    /// `group` - Alkyl group replacing the hydrogen
+    pub fn add_alkyl(
+        &mut self,
+        atom_num: usize,
+        group: AlkylGroup,
+    ) -> CompoundBuilderResult<&mut Self> {
+        let i = self.backbone_idx(atom_num)?;
+        self.attach_alkyl(i, group)
+    }
+
+    /// Swaps a hydrogen on any heavy atom, including one of a side chain,
+    /// for an alkyl group, nesting it within the side chain.
+    ///
+    /// # Arguments
+    ///
+    /// `idx` - Index of the atom; heavy atoms are indexed in the order
+    /// they were added, the backbone first
+    /// `group` - Alkyl group replacing the hydrogen
+    pub fn add_alkyl_at(
+        &mut self,
+        idx: usize,
+        group: AlkylGroup,
+    ) -> CompoundBuilderResult<&mut Self> {
+        let i = self.heavy_atom_idx(idx)?;
+        self.attach_alkyl(i, group)
+    }
+
+    fn attach_alkyl(
+        &mut self,
+        i: usize,
+        group: AlkylGroup,
+    ) -> CompoundBuilderResult<&mut Self> {
+        if group.carbon_count() == 0 {
+            return Err(CompoundBuilderError::SideChainError(
+                "Alkyl groups need at least one carbon".into(),
             ));
         }
+        let bonds = group
+            .bonds()
+            .into_iter()
+            .map(|(a, b)| (a, b, BondOrder::Single))
+            .collect();
+        self.attach_group(i, &group.to_string(), group.atoms(), bonds)
+    }
+
+    /// Replaces a plain hydrogen on atom `i` with `atoms`, bonded to it
+    /// through the first one and to each other by `bonds`.
+    fn attach_group(
+        &mut self,
+        i: usize,
+        name: &str,
+        atoms: Vec<Atom>,
+        bonds: Vec<(usize, usize, BondOrder)>,
+    ) -> CompoundBuilderResult<&mut Self> {
+        if !self.has_side_chain(i) {
+            return Err(CompoundBuilderError::NoRemoteAtomsError(format!(
+                "No remote atoms exist to substitute with {}",
+                name
+            )));
+        }
         let hydrogen_i: usize = self
             .get_remote_side_chains(i)?
             .iter()
             .find_map(|(atom_i, atom)| {
-                if atom.get_element_num() == 1 {
+                if atom.is_plain_hydrogen() {
                     Some(*atom_i)
                 } else {
                     None
                 }
             }).ok_or(CompoundBuilderError::NoRemoteAtomsError(
-                format!("No free hydrogen remaining in the remote side chain for backbone atom number {}", atom_num)
+                format!("No free hydrogen remaining in the remote side chain for atom {}", i)
             ))?;
-        let matched_hydrogen = self
-            .atoms
-            .get_mut(hydrogen_i)
-            .ok_or(CompoundBuilderError::NoRemoteAtomsError(
-                format!("Hydrogen missing error: Hydrogen atom could not be found while brominating atom at atom num {}", atom_num)
-            ))?;
-        *matched_hydrogen = Atom::bromine();
+        self.remove_atoms(&BTreeSet::from([hydrogen_i]));
+        let i = if hydrogen_i < i { i - 1 } else { i };
+        let offset = self.atoms.len();
+        self.atoms.extend(atoms);
+        self.side_chains.entry(i).or_default().insert(offset);
+        for (a, b, order) in bonds {
+            let (a, b) = (offset + a, offset + b);
+            self.side_chains.entry(a).or_default().insert(b);
+            if order != BondOrder::Single {
+                self.bond_orders.insert(bond_key(a, b), order);
+            }
+        }
+        self.fill_hydrogens();
+        self.gen_locations()?;
+        Ok(self)
+    }
+
+    /// Adds a bromine to a certain atom in a Compound.
+    ///
+    /// # Arguments
+    ///
+    /// `atom_num` - Labeled atom number in the compound
+    ///
+    /// # Returns
+    ///
+    /// A Compound resulting from bromination.
+    pub fn brominate(
+        &mut self,
+        atom_num: usize,
+    ) -> CompoundBuilderResult<&mut Self> {
+        self.substitute(atom_num, Substituent::Bromo)
+    }
+
+    /// Makes the bond between two neighbouring backbone atoms a double
+    /// bond, removing a hydrogen from each.
+    ///
+    /// # Arguments
+    ///
+    /// `a`, `b` - Labeled atom numbers of the bonded atoms
+    pub fn double_bond(
+        &mut self,
+        a: usize,
+        b: usize,
+    ) -> CompoundBuilderResult<&mut Self> {
+        self.set_bond_order(a, b, BondOrder::Double)
+    }
+
+    /// Makes the bond between two neighbouring backbone atoms a double
+    /// bond with the given configuration. Like [`Self::stereocenter`], the
+    /// priorities are only ranked once the compound is built.
+    ///
+    /// # Arguments
+    ///
+    /// `a`, `b` - Labeled atom numbers of the bonded atoms
+    /// `descriptor` - E or Z
+    pub fn stereo_double_bond(
+        &mut self,
+        a: usize,
+        b: usize,
+        descriptor: EzDescriptor,
+    ) -> CompoundBuilderResult<&mut Self> {
+        self.double_bond(a, b)?;
+        self.double_bond_stereo.insert((a, b), descriptor);
+        Ok(self)
+    }
+
+    /// Makes the bond between two neighbouring backbone atoms a triple
+    /// bond, removing hydrogens as needed.
+    ///
+    /// # Arguments
+    ///
+    /// `a`, `b` - Labeled atom numbers of the bonded atoms
+    pub fn triple_bond(
+        &mut self,
+        a: usize,
+        b: usize,
+    ) -> CompoundBuilderResult<&mut Self> {
+        self.set_bond_order(a, b, BondOrder::Triple)
+    }
+
+    /// Sets the order of the bond between backbone atoms `a` and `b`, left
+    /// untouched when either atom can't take it.
+    fn set_bond_order(
+        &mut self,
+        a: usize,
+        b: usize,
+        order: BondOrder,
+    ) -> CompoundBuilderResult<&mut Self> {
+        let key = bond_key(self.backbone_idx(a)?, self.backbone_idx(b)?);
+        if !self.bonds().contains(&key) {
+            return Err(CompoundBuilderError::NoSuchBondError(format!(
+                "Backbone atoms {} and {} aren't bonded",
+                a, b
+            )));
+        }
+        self.try_bond_change([a, b], |builder| {
+            builder.bond_orders.insert(key, order);
+        })
+    }
+
+    /// Applies a change to the bonds between backbone atoms `atom_nums`,
+    /// then refills hydrogens. The builder is restored when either atom
+    /// ends up with the wrong valence.
+    fn try_bond_change(
+        &mut self,
+        atom_nums: [usize; 2],
+        change: impl FnOnce(&mut Self),
+    ) -> CompoundBuilderResult<&mut Self> {
+        let before = self.clone();
+        change(self);
+        self.fill_hydrogens();
+        let sums = self.bond_order_sums();
+        for atom_num in atom_nums {
+            let checked =
+                self.check_valence(self.backbone_idx(atom_num)?, &sums);
+            if checked.is_err() {
+                *self = before;
+                return checked.map(|_| self);
+            }
+        }
+        self.gen_locations()?;
+        Ok(self)
+    }
+
+    /// Bonds two backbone atoms which aren't bonded yet, closing a ring;
+    /// each loses a hydrogen.
+    ///
+    /// # Arguments
+    ///
+    /// `a`, `b` - Labeled atom numbers of the atoms to bond
+    pub fn ring_closure(
+        &mut self,
+        a: usize,
+        b: usize,
+    ) -> CompoundBuilderResult<&mut Self> {
+        let key = bond_key(self.backbone_idx(a)?, self.backbone_idx(b)?);
+        if a == b || self.bonds().contains(&key) {
+            return Err(CompoundBuilderError::RingError(format!(
+                "Backbone atoms {} and {} can't be bonded again",
+                a, b
+            )));
+        }
+        self.try_bond_change([a, b], |builder| {
+            builder.ring_closures.insert(key);
+        })
+    }
+
+    /// Makes a ring of `count` carbons (a cycloalkane), numbered around it.
+    pub fn ring(&mut self, count: usize) -> CompoundBuilderResult<&mut Self> {
+        if count < 3 {
+            return Err(CompoundBuilderError::RingError(format!(
+                "Rings need at least 3 atoms, got {}",
+                count
+            )));
+        }
+        self.linear_chain(count)?.ring_closure(1, count)
+    }
+
+    /// Makes a benzene ring, numbered around it, as the Kekulé structure
+    /// with double bonds 1=2, 3=4 and 5=6.
+    pub fn benzene(&mut self) -> CompoundBuilderResult<&mut Self> {
+        self.ring(6)?
+            .double_bond(1, 2)?
+            .double_bond(3, 4)?
+            .double_bond(5, 6)
+    }
+
+    /// Makes a benzene ring carrying `first` on atom 1 and the `others`
+    /// ortho, meta or para to it.
+    ///
+    /// # Arguments
+    ///
+    /// `first` - Group on atom 1
+    ///
+    /// `others` - Groups along with their position relative to `first`
+    pub fn substituted_benzene(
+        &mut self,
+        first: Substituent,
+        others: &[(ArenePosition, Substituent)],
+    ) -> CompoundBuilderResult<&mut Self> {
+        self.benzene()?.substitute(1, first)?;
+        for (position, group) in others {
+            self.substitute(position.atom_num(), *group)?;
+        }
+        Ok(self)
+    }
+
+    /// Builds the compound from its heavy atoms and the bonds between
+    /// them, then fills hydrogens.
+    ///
+    /// The longest path through the atoms becomes the backbone (numbered
+    /// from one end), the other atoms hang off it as side chains and the
+    /// bonds left over close rings.
+    ///
+    /// # Arguments
+    ///
+    /// `atoms` - Atoms of the compound, hydrogens aside
+    ///
+    /// `bonds` - Bonds as `(a, b, order)`, indexing `atoms`
+    pub fn graph(
+        &mut self,
+        atoms: Vec<Atom>,
+        bonds: &[(usize, usize, BondOrder)],
+    ) -> CompoundBuilderResult<&mut Self> {
+        let mut adjacency = vec![BTreeSet::new(); atoms.len()];
+        for &(a, b, _) in bonds {
+            if a == b || a.max(b) >= atoms.len() {
+                return Err(CompoundBuilderError::NoSuchBondError(format!(
+                    "Can't bond atoms {} and {} of {}",
+                    a,
+                    b,
+                    atoms.len()
+                )));
+            }
+            adjacency[a].insert(b);
+            adjacency[b].insert(a);
+        }
+        let backbone = longest_path(&adjacency);
+        let mut side_chains: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+        let mut placed: BTreeSet<usize> = backbone.iter().copied().collect();
+        let mut queue: VecDeque<usize> = backbone.iter().copied().collect();
+        while let Some(i) = queue.pop_front() {
+            for &j in adjacency[i].iter() {
+                if placed.insert(j) {
+                    side_chains.entry(i).or_default().insert(j);
+                    queue.push_back(j);
+                }
+            }
+        }
+        if placed.len() != atoms.len() {
+            return Err(CompoundBuilderError::SideChainError(
+                "Atom isn't connected to the backbone".into(),
+            ));
+        }
+        let tree = tree_bonds(&backbone, &side_chains);
+        self.atoms = atoms;
+        self.backbone = backbone;
+        self.side_chains = side_chains;
+        self.bond_orders = bonds
+            .iter()
+            .filter(|&&(_, _, order)| order != BondOrder::Single)
+            .map(|&(a, b, order)| (bond_key(a, b), order))
+            .collect();
+        self.ring_closures = bonds
+            .iter()
+            .map(|&(a, b, _)| bond_key(a, b))
+            .filter(|key| !tree.contains(key))
+            .collect();
+        self.stereocenters.clear();
+        self.double_bond_stereo.clear();
+        self.fill_hydrogens();
+        self.gen_locations()?;
         Ok(self)
     }
 
@@ -249,13 +731,623 @@ impl CompoundBuilder {
     ) -> CompoundBuilderResult<&mut Self> {
         self.atoms.clear();
         self.backbone.clear();
+        self.side_chains.clear();
+        self.bond_orders.clear();
+        self.ring_closures.clear();
+        self.stereocenters.clear();
+        self.double_bond_stereo.clear();
         for i in 0..count {
             self.atoms.push(Atom::carbon());
             self.backbone.push(i);
         }
-        // self.side_chains.clear();
-        self.satisfy_backbone_octets();
+        self.fill_hydrogens();
         self.gen_locations()?;
         Ok(self)
     }
 }
+
+/// Extensions the search for a backbone through a ring system may try
+/// before it settles for the longest path found so far.
+const MAX_PATH_EXTENSIONS: usize = 20_000;
+
+/// Longest simple path through the graph, preferring paths whose ends are
+/// bonded (a ring which can be folded onto the grid).
+///
+/// Trees take their diameter exactly. Finding the longest path through
+/// rings is NP-hard, so that search stops after [`MAX_PATH_EXTENSIONS`]
+/// steps, which covers the ring systems the builder handles in practice.
+fn longest_path(adjacency: &[BTreeSet<usize>]) -> Vec<usize> {
+    let bonds = adjacency.iter().map(BTreeSet::len).sum::<usize>() / 2;
+    if adjacency.is_empty() {
+        return Vec::new();
+    } else if bonds < adjacency.len() {
+        // A tree's diameter runs from the atom farthest from an arbitrary
+        // atom to the atom farthest from that one
+        let end = *farthest_path(adjacency, 0).last().expect("Start");
+        return farthest_path(adjacency, end);
+    }
+
+    fn extend(
+        adjacency: &[BTreeSet<usize>],
+        path: &mut Vec<usize>,
+        best: &mut (usize, bool, Vec<usize>),
+        budget: &mut usize,
+    ) {
+        let last = *path.last().expect("Paths aren't empty");
+        let closed = path.len() >= 3 && adjacency[last].contains(&path[0]);
+        if (path.len(), closed) > (best.0, best.1) {
+            *best = (path.len(), closed, path.clone());
+        }
+        for &j in adjacency[last].iter() {
+            if *budget == 0 {
+                return;
+            }
+            if !path.contains(&j) {
+                *budget -= 1;
+                path.push(j);
+                extend(adjacency, path, best, budget);
+                path.pop();
+            }
+        }
+    }
+    let mut best = (0, false, Vec::new());
+    let mut budget = MAX_PATH_EXTENSIONS;
+    for start in 0..adjacency.len() {
+        extend(adjacency, &mut vec![start], &mut best, &mut budget);
+    }
+    best.2
+}
+
+/// Shortest path from the atom farthest from `start` (the last one reached,
+/// breadth-first) back to `start`
+fn farthest_path(adjacency: &[BTreeSet<usize>], start: usize) -> Vec<usize> {
+    let mut parent = vec![None; adjacency.len()];
+    let mut seen = vec![false; adjacency.len()];
+    seen[start] = true;
+    let mut last = start;
+    let mut queue = VecDeque::from([start]);
+    while let Some(i) = queue.pop_front() {
+        last = i;
+        for &j in adjacency[i].iter() {
+            if !seen[j] {
+                seen[j] = true;
+                parent[j] = Some(i);
+                queue.push_back(j);
+            }
+        }
+    }
+    let mut path = vec![last];
+    while let Some(i) = parent[*path.last().expect("Paths aren't empty")] {
+        path.push(i);
+    }
+    path
+}
+
+#[cfg(test)]
+pub(crate) type BuildStep =
+    fn(&mut CompoundBuilder) -> CompoundBuilderResult<&mut CompoundBuilder>;
+
+/// Compounds shared by the tests of several modules
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+
+    /// Compound built by a single step
+    pub(crate) fn build(step: BuildStep) -> Compound {
+        step(&mut CompoundBuilder::new())
+            .and_then(|b| b.build())
+            .expect("Valid compound expected")
+    }
+
+    /// Bare compound of the given atoms and bonds, without hydrogens
+    pub(crate) fn bare(
+        atoms: Vec<Atom>,
+        bonds: &[(usize, usize, BondOrder)],
+    ) -> Compound {
+        Compound::from_edges(atoms, bonds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_fills_heteroatom_hydrogens() {
+        let oxygen = Atom::new_unchecked(8);
+        let cases = [
+            (5, 3, oxygen.clone(), "CH3CH2OCH2CH3"),
+            (2, 2, Atom::nitrogen(), "CH3NH2"),
+            (2, 2, oxygen, "CH3OH"),
+            (3, 2, Atom::new_unchecked(16), "CH3SCH3"),
+        ];
+        for (count, atom_num, atom, expected) in cases {
+            let compound = CompoundBuilder::new()
+                .linear_chain(count)
+                .and_then(|b| b.replace_atom(atom_num, atom))
+                .and_then(|b| b.build())
+                .expect("Valid compound expected");
+            assert_eq!(compound.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn builder_expanded_octets() {
+        let star = |center: u8, ligand: u8, count: usize| {
+            let mut atoms = vec![Atom::new_unchecked(center)];
+            atoms.extend((0..count).map(|_| Atom::new_unchecked(ligand)));
+            let bonds: Vec<(usize, usize, BondOrder)> =
+                (1..=count).map(|i| (0, i, BondOrder::Single)).collect();
+            CompoundBuilder::new()
+                .graph(atoms, &bonds)
+                .and_then(|b| b.build())
+        };
+        let sulfur_hexafluoride = star(16, 9, 6).expect("Valid SF6 expected");
+        assert_eq!(sulfur_hexafluoride.molecular_formula(), "F6S");
+        let phosphorus_pentachloride =
+            star(15, 17, 5).expect("Valid PCl5 expected");
+        assert_eq!(phosphorus_pentachloride.molecular_formula(), "Cl5P");
+        // Carbon can't hold more than four neighbours
+        assert!(matches!(
+            star(6, 9, 6),
+            Err(CompoundBuilderError::OctetError(0, _))
+        ));
+    }
+
+    #[test]
+    fn builder_fills_hydrogens_from_charge() {
+        let ammonium = CompoundBuilder::new()
+            .linear_chain(2)
+            .and_then(|b| b.replace_atom(2, Atom::nitrogen().ion(1)))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(ammonium.to_string(), "CH3NH3");
+        let methoxide = CompoundBuilder::new()
+            .linear_chain(2)
+            .and_then(|b| b.replace_atom(2, Atom::new_unchecked(8).ion(-1)))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(methoxide.to_string(), "CH3O");
+    }
+
+    #[test]
+    fn builder_rejects_overbonded_atom() {
+        let mut builder = CompoundBuilder::new();
+        builder
+            .linear_chain(3)
+            .and_then(|b| b.replace_atom(2, Atom::new_unchecked(9)))
+            .expect("Replacement itself is allowed");
+        match builder.build() {
+            Err(CompoundBuilderError::OctetError(idx, _)) => assert_eq!(idx, 1),
+            other => panic!("Expected an octet error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn builder_substitutes_groups() {
+        let cases = [
+            (3, 1, "Cl", "ClCH2CH2CH3"),
+            (3, 2, "OH", "CH3CH(OH)CH3"),
+            (3, 3, "Br", "CH3CH2CH2Br"),
+            (2, 2, "NH2", "CH3CH2NH2"),
+            (2, 1, "CN", "NCCH2CH3"),
+            (2, 2, "OCH3", "CH3CH2OCH3"),
+            (3, 2, "NO2", "CH3CH(NO2)CH3"),
+            (1, 1, "I", "CH3I"),
+            (6, 6, "Br", "CH3(CH2)4CH2Br"),
+        ];
+        for (count, atom_num, group, expected) in cases {
+            let group: Substituent = group.parse().expect("Known group");
+            let compound = CompoundBuilder::new()
+                .linear_chain(count)
+                .and_then(|b| b.substitute(atom_num, group))
+                .and_then(|b| b.build())
+                .expect("Valid compound expected");
+            assert_eq!(compound.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn builder_substitutes_until_saturated() {
+        let mut builder = CompoundBuilder::new();
+        builder.linear_chain(2).expect("Linear chain expected");
+        for _ in 0..3 {
+            builder
+                .substitute(1, Substituent::Fluoro)
+                .expect("Free hydrogen expected");
+        }
+        let compound = builder.build().expect("Valid compound expected");
+        assert_eq!(compound.to_string(), "CF3CH3");
+        assert!(matches!(
+            builder.substitute(1, Substituent::Chloro),
+            Err(CompoundBuilderError::NoRemoteAtomsError(_))
+        ));
+        assert!(matches!(
+            "OH2".parse::<Substituent>(),
+            Err(CompoundBuilderError::UnknownSubstituentError(_))
+        ));
+    }
+
+    #[test]
+    fn builder_adds_branched_alkyls() {
+        let cases = [
+            (4, 2, AlkylGroup::Methyl, "CH3CH(CH3)CH2CH3"),
+            (5, 3, AlkylGroup::Ethyl, "CH3CH2CH(CH2CH3)CH2CH3"),
+            (5, 3, AlkylGroup::Isopropyl, "CH3CH2CH(CH(CH3)2)CH2CH3"),
+            (5, 3, AlkylGroup::TertButyl, "CH3CH2CH(C(CH3)3)CH2CH3"),
+            (5, 3, AlkylGroup::Isobutyl, "CH3CH2CH(CH2CH(CH3)2)CH2CH3"),
+            (5, 3, AlkylGroup::SecButyl, "CH3CH2CH(CH(CH3)CH2CH3)CH2CH3"),
+            (5, 3, AlkylGroup::NAlkyl(3), "CH3CH2CH(CH2CH2CH3)CH2CH3"),
+        ];
+        for (count, atom_num, group, expected) in cases {
+            let compound = CompoundBuilder::new()
+                .linear_chain(count)
+                .and_then(|b| b.add_alkyl(atom_num, group))
+                .and_then(|b| b.build())
+                .expect("Valid compound expected");
+            assert_eq!(compound.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn builder_nests_alkyls_in_side_chains() {
+        // 3-ethylpentane's ethyl carbons follow the five of the backbone
+        let nested = |idx, group| {
+            CompoundBuilder::new()
+                .linear_chain(5)
+                .and_then(|b| b.add_alkyl(3, AlkylGroup::Ethyl))
+                .and_then(|b| b.add_alkyl_at(idx, group))
+                .and_then(|b| b.build())
+                .expect("Valid compound expected")
+        };
+        let direct = |group| {
+            CompoundBuilder::new()
+                .linear_chain(5)
+                .and_then(|b| b.add_alkyl(3, group))
+                .and_then(|b| b.build())
+                .expect("Valid compound expected")
+        };
+        // 3-(1-methylethyl)pentane
+        let methylethyl = nested(5, AlkylGroup::Methyl);
+        assert_eq!(methylethyl.to_string(), "CH3CH2CH(CH(CH3)2)CH2CH3");
+        assert!(methylethyl.same_constitution(&direct(AlkylGroup::Isopropyl)));
+        // 3-(2-methylpropyl)pentane, two levels down
+        let methylpropyl = CompoundBuilder::new()
+            .linear_chain(5)
+            .and_then(|b| b.add_alkyl(3, AlkylGroup::Ethyl))
+            .and_then(|b| b.add_alkyl_at(6, AlkylGroup::Methyl))
+            .and_then(|b| b.add_alkyl_at(6, AlkylGroup::Methyl))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert!(methylpropyl.same_constitution(&direct(AlkylGroup::Isobutyl)));
+        assert!(nested(6, AlkylGroup::Methyl)
+            .same_constitution(&direct(AlkylGroup::Propyl)));
+        assert!(matches!(
+            CompoundBuilder::new()
+                .linear_chain(5)
+                .and_then(|b| b.add_alkyl_at(5, AlkylGroup::Methyl)),
+            Err(CompoundBuilderError::SideChainError(_))
+        ));
+    }
+
+    #[test]
+    fn builder_adds_several_alkyls() {
+        let compound = CompoundBuilder::new()
+            .linear_chain(3)
+            .and_then(|b| b.add_alkyl(2, AlkylGroup::Methyl))
+            .and_then(|b| b.add_alkyl(2, AlkylGroup::Methyl))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(compound.to_string(), "CH3C(CH3)2CH3");
+        assert_eq!(compound.atom_count(), 17);
+        let mut builder = CompoundBuilder::new();
+        builder.linear_chain(2).expect("Linear chain expected");
+        assert!(matches!(
+            builder.add_alkyl(1, AlkylGroup::NAlkyl(0)),
+            Err(CompoundBuilderError::SideChainError(_))
+        ));
+    }
+
+    #[test]
+    fn builder_multiple_bonds() {
+        let propene = CompoundBuilder::new()
+            .linear_chain(3)
+            .and_then(|b| b.double_bond(1, 2))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(propene.to_string(), "CH2=CHCH3");
+        assert_eq!(propene.atom_count(), 9);
+        let propyne = CompoundBuilder::new()
+            .linear_chain(3)
+            .and_then(|b| b.triple_bond(2, 1))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(propyne.to_string(), "HC≡CCH3");
+        let but_2_ene = CompoundBuilder::new()
+            .linear_chain(4)
+            .and_then(|b| b.double_bond(2, 3))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(but_2_ene.to_string(), "CH3CH=CHCH3");
+        let diene = CompoundBuilder::new()
+            .linear_chain(4)
+            .and_then(|b| b.double_bond(1, 2))
+            .and_then(|b| b.double_bond(3, 4))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(diene.to_string(), "CH2=CHCH=CH2");
+    }
+
+    #[test]
+    fn builder_rejects_impossible_multiple_bonds() {
+        let mut builder = CompoundBuilder::new();
+        builder
+            .linear_chain(3)
+            .and_then(|b| b.double_bond(1, 2))
+            .expect("Double bond expected");
+        assert!(matches!(
+            builder.triple_bond(2, 3),
+            Err(CompoundBuilderError::OctetError(_, _))
+        ));
+        assert!(matches!(
+            builder.double_bond(1, 3),
+            Err(CompoundBuilderError::NoSuchBondError(_))
+        ));
+        let compound = builder.build().expect("Failed bonds are undone");
+        assert_eq!(compound.to_string(), "CH2=CHCH3");
+    }
+
+    #[test]
+    fn builder_rings() {
+        let cases: [(BuildStep, &str, usize); 4] = [
+            (|b| b.ring(6), "C6H12", 6),
+            (|b| b.ring(5)?.add_alkyl(1, AlkylGroup::Methyl), "C6H12", 6),
+            // Decalin: cyclodecane bridged across
+            (|b| b.ring(10)?.ring_closure(1, 6), "C10H18", 11),
+            // Spiro[4.4]nonane: two rings sharing atom 5
+            (
+                |b| b.linear_chain(9)?.ring_closure(1, 5)?.ring_closure(5, 9),
+                "C9H16",
+                10,
+            ),
+        ];
+        for (build, formula, carbon_bonds) in cases {
+            let mut builder = CompoundBuilder::new();
+            let compound = build(&mut builder)
+                .and_then(|b| b.build())
+                .expect("Valid compound expected");
+            assert!(compound.is_cyclic());
+            assert_eq!(compound.to_string(), formula);
+            let carbons: Vec<usize> = (0..compound.atom_count())
+                .filter(|&i| compound.atoms()[i] == Atom::carbon())
+                .collect();
+            let ring_bonds = compound
+                .bonds()
+                .into_iter()
+                .filter(|(a, b)| carbons.contains(a) && carbons.contains(b))
+                .count();
+            assert_eq!(ring_bonds, carbon_bonds);
+            let distinct: BTreeSet<(i16, i16)> =
+                compound.locations().iter().map(|l| (l.x, l.y)).collect();
+            assert_eq!(distinct.len(), compound.atom_count());
+        }
+    }
+
+    #[test]
+    fn builder_ring_layout_joins_neighbours() {
+        let compound = CompoundBuilder::new()
+            .ring(5)
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        for (a, b) in compound.bonds() {
+            let (la, lb) = (compound.locations()[a], compound.locations()[b]);
+            assert!((la.x - lb.x).abs() <= 1 && (la.y - lb.y).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn builder_rejects_bad_rings() {
+        let mut builder = CompoundBuilder::new();
+        assert!(matches!(
+            builder.ring(2),
+            Err(CompoundBuilderError::RingError(_))
+        ));
+        builder.ring(4).expect("Cyclobutane expected");
+        assert!(matches!(
+            builder.ring_closure(1, 2),
+            Err(CompoundBuilderError::RingError(_))
+        ));
+        builder
+            .ring_closure(1, 3)
+            .and_then(|b| b.ring_closure(2, 4))
+            .expect("Tetrahedrane closures are allowed");
+        assert!(matches!(
+            builder.ring_closure(2, 4),
+            Err(CompoundBuilderError::RingError(_))
+        ));
+        let compound = builder.build().expect("Valid compound expected");
+        assert_eq!(compound.to_string(), "C4H4");
+    }
+
+    #[test]
+    fn builder_benzene_derivatives() {
+        let benzene = CompoundBuilder::new()
+            .benzene()
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(benzene.to_string(), "C6H6");
+        let cases = [
+            (Substituent::Chloro, ArenePosition::Ortho, "C6H4Cl2"),
+            (Substituent::Nitro, ArenePosition::Meta, "C6H4N2O4"),
+            (Substituent::Hydroxy, ArenePosition::Para, "C6H6O2"),
+        ];
+        for (group, position, formula) in cases {
+            let compound = CompoundBuilder::new()
+                .substituted_benzene(group, &[(position, group)])
+                .and_then(|b| b.build())
+                .expect("Valid compound expected");
+            assert_eq!(compound.molecular_formula(), formula);
+            let carrying: Vec<usize> = (0..6)
+                .filter(|&i| {
+                    compound.neighbors(i).iter().any(|&j| {
+                        compound.atoms()[j].get_element_num() != 1 && j >= 6
+                    })
+                })
+                .collect();
+            assert_eq!(carrying, vec![0, position.atom_num() - 1]);
+            assert_eq!(
+                ArenePosition::from_atom_num(position.atom_num()),
+                Some(position)
+            );
+        }
+    }
+
+    #[test]
+    fn builder_from_graph() {
+        // Isobutane and propan-2-ol, given with the branch first
+        let carbon = Atom::carbon;
+        let isobutane = CompoundBuilder::new()
+            .graph(
+                vec![carbon(), carbon(), carbon(), carbon()],
+                &[
+                    (1, 0, BondOrder::Single),
+                    (1, 2, BondOrder::Single),
+                    (1, 3, BondOrder::Single),
+                ],
+            )
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(isobutane.to_string(), "CH3CH(CH3)CH3");
+        let cyclopropene = CompoundBuilder::new()
+            .graph(
+                vec![carbon(), carbon(), carbon()],
+                &[
+                    (0, 1, BondOrder::Double),
+                    (1, 2, BondOrder::Single),
+                    (2, 0, BondOrder::Single),
+                ],
+            )
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(cyclopropene.molecular_formula(), "C3H4");
+        assert!(cyclopropene.is_cyclic());
+        assert!(matches!(
+            CompoundBuilder::new().graph(vec![carbon(), carbon()], &[]),
+            Err(CompoundBuilderError::SideChainError(_))
+        ));
+        // A graph replaces whatever the builder held, descriptors included
+        let mut builder = CompoundBuilder::new();
+        builder
+            .linear_chain(4)
+            .and_then(|b| b.brominate(2))
+            .and_then(|b| b.stereocenter(2, CipDescriptor::S))
+            .and_then(|b| b.stereo_double_bond(3, 4, EzDescriptor::Z))
+            .expect("Valid steps expected");
+        let ethane = builder
+            .graph(vec![carbon(), carbon()], &[(0, 1, BondOrder::Single)])
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(ethane.molecular_formula(), "C2H6");
+    }
+
+    #[test]
+    fn builder_longest_path() {
+        let adjacency = |n: usize, bonds: &[(usize, usize)]| {
+            let mut adjacency = vec![BTreeSet::new(); n];
+            for &(a, b) in bonds {
+                adjacency[a].insert(b);
+                adjacency[b].insert(a);
+            }
+            adjacency
+        };
+        // 2,3-dimethylpentane, given from a methyl branch
+        let tree =
+            adjacency(7, &[(0, 1), (1, 2), (1, 3), (3, 4), (4, 5), (3, 6)]);
+        let path = longest_path(&tree);
+        assert_eq!(path.len(), 5);
+        assert!(path.windows(2).all(|pair| tree[pair[0]].contains(&pair[1])));
+        // A ladder of 40 fused four-membered rings has far too many simple
+        // paths to try them all
+        let mut bonds: Vec<_> = (0..40).map(|i| (i, i + 41)).collect();
+        bonds.extend((0..40).flat_map(|i| [(i, i + 1), (i + 41, i + 42)]));
+        let ladder = adjacency(82, &bonds);
+        let path = longest_path(&ladder);
+        assert!(path.len() >= 41);
+        assert!(path
+            .windows(2)
+            .all(|pair| ladder[pair[0]].contains(&pair[1])));
+    }
+
+    #[test]
+    fn builder_stereocenters() {
+        // Requested before the bromine which makes C2 a stereocenter
+        let compound = CompoundBuilder::new()
+            .linear_chain(4)
+            .and_then(|b| b.stereocenter(2, CipDescriptor::S))
+            .and_then(|b| b.brominate(2))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(compound.cip_descriptor(1), Some(CipDescriptor::S));
+        assert!(matches!(
+            CompoundBuilder::new()
+                .linear_chain(4)
+                .and_then(|b| b.stereocenter(2, CipDescriptor::R))
+                .and_then(|b| b.build()),
+            Err(CompoundBuilderError::StereoError(_))
+        ));
+        assert!(matches!(
+            CompoundBuilder::new()
+                .linear_chain(4)
+                .and_then(|b| b.stereocenter(5, CipDescriptor::R)),
+            Err(CompoundBuilderError::NoSuchBackboneError(_))
+        ));
+        // A new chain starts without the descriptors of the last one
+        let mut builder = CompoundBuilder::new();
+        builder
+            .linear_chain(4)
+            .and_then(|b| b.brominate(2))
+            .and_then(|b| b.stereocenter(2, CipDescriptor::R))
+            .and_then(|b| b.stereo_double_bond(3, 4, EzDescriptor::E))
+            .expect("Valid steps expected");
+        let butane = builder
+            .linear_chain(4)
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert!(butane.cip_descriptors().is_empty());
+    }
+
+    #[test]
+    fn builder_stereo_double_bonds() {
+        for descriptor in [EzDescriptor::E, EzDescriptor::Z] {
+            let compound = CompoundBuilder::new()
+                .linear_chain(4)
+                .and_then(|b| b.stereo_double_bond(2, 3, descriptor))
+                .and_then(|b| b.build())
+                .expect("Valid compound expected");
+            assert_eq!(compound.ez_descriptor(1, 2), Some(descriptor));
+            assert_eq!(compound.stereogenic_double_bonds(), vec![(1, 2)]);
+        }
+        // But-1-ene has two hydrogens on C1
+        assert!(matches!(
+            CompoundBuilder::new()
+                .linear_chain(4)
+                .and_then(|b| b.stereo_double_bond(1, 2, EzDescriptor::E))
+                .and_then(|b| b.build()),
+            Err(CompoundBuilderError::StereoError(_))
+        ));
+    }
+
+    #[test]
+    fn builder_rejects_missing_backbone_atom() {
+        let mut builder = CompoundBuilder::new();
+        builder.linear_chain(3).expect("Linear chain expected");
+        assert!(matches!(
+            builder.brominate(4),
+            Err(CompoundBuilderError::NoSuchBackboneError(_))
+        ));
+        assert!(matches!(
+            builder.replace_atom(0, Atom::nitrogen()),
+            Err(CompoundBuilderError::NoSuchBackboneError(_))
+        ));
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::BTreeSet;
+
+use crate::matter::compound::{bond::BondOrder, Compound};
+
+/// What sets an atom apart before looking at its neighbours: element,
+/// isotope, charge and implicit hydrogens
+type Invariant = (u8, u8, i8, u8);
+
+/// Atoms (by canonical position) and bonds `(a, b, order)` between them
+type Encoding = (Vec<Invariant>, Vec<(usize, usize, u8)>);
+
+/// Atom graph with bond orders, as seen by canonical labelling
+struct Graph {
+    invariants: Vec<Invariant>,
+    adjacency: Vec<Vec<(usize, u8)>>,
+}
+
+impl Graph {
+    /// The compound's graph; aromatic bonds are told apart from the
+    /// Kekulé orders recorded on the compound when `aromatic` is set.
+    fn of(compound: &Compound, aromatic: bool) -> Self {
+        let orders = if aromatic {
+            compound
+                .aromatic_bond_orders()
+                .unwrap_or_else(|_| compound.bond_orders())
+        } else {
+            compound.bond_orders()
+        };
+        let mut adjacency = vec![Vec::new(); compound.atom_count()];
+        for (&(a, b), order) in orders.iter() {
+            let order = match order {
+                BondOrder::Aromatic => 4,
+                order => order.as_u8(),
+            };
+            adjacency[a].push((b, order));
+            adjacency[b].push((a, order));
+        }
+        let invariants = compound
+            .atoms()
+            .iter()
+            .enumerate()
+            .map(|(i, atom)| {
+                (
+                    atom.get_element_num(),
+                    atom.neutrons,
+                    atom.charge(),
+                    compound.implicit_hydrogens(i),
+                )
+            })
+            .collect();
+        Self {
+            invariants,
+            adjacency,
+        }
+    }
+
+    fn initial_colors(&self) -> Vec<usize> {
+        let keys: Vec<(Invariant, usize)> = self
+            .invariants
+            .iter()
+            .zip(self.adjacency.iter())
+            .map(|(&invariant, bonds)| (invariant, bonds.len()))
+            .collect();
+        ranks(&keys)
+    }
+
+    /// Refines the colors by the colors of each atom's neighbours until no
+    /// class splits further (Morgan's algorithm). Classes keep their
+    /// relative order.
+    fn refine(&self, mut colors: Vec<usize>) -> Vec<usize> {
+        loop {
+            let signatures: Vec<(usize, Vec<(u8, usize)>)> = self
+                .adjacency
+                .iter()
+                .enumerate()
+                .map(|(i, bonds)| {
+                    let mut around: Vec<(u8, usize)> = bonds
+                        .iter()
+                        .map(|&(j, order)| (order, colors[j]))
+                        .collect();
+                    around.sort();
+                    (colors[i], around)
+                })
+                .collect();
+            let refined = ranks(&signatures);
+            if class_count(&refined) == class_count(&colors) {
+                return refined;
+            }
+            colors = refined;
+        }
+    }
+
+    /// Smallest encoding over every way of breaking the ties left by
+    /// refinement, along with a labelling giving it and automorphisms
+    /// generating every symmetry of the graph.
+    fn canonical(
+        &self,
+        colors: Vec<usize>,
+    ) -> (Encoding, Vec<usize>, Vec<Vec<usize>>) {
+        let mut search = Search::default();
+        self.search(colors, &mut Vec::new(), &mut search);
+        let (encoding, labelling) =
+            search.best.expect("The search reaches a labelling");
+        (encoding, labelling, search.automorphisms)
+    }
+
+    /// Individualises each atom of the first tied class in turn, skipping
+    /// atoms which an automorphism fixing the atoms individualised so far
+    /// maps onto one already tried: their branches give the same encodings.
+    fn search(
+        &self,
+        colors: Vec<usize>,
+        fixed: &mut Vec<usize>,
+        search: &mut Search,
+    ) {
+        let colors = self.refine(colors);
+        let tied = (0..colors.len())
+            .filter(|&c| colors.iter().filter(|&&x| x == c).count() > 1)
+            .min();
+        let Some(tied) = tied else {
+            search.reach(self.encode(&colors), colors);
+            return;
+        };
+        let mut tried: Vec<usize> = Vec::new();
+        for chosen in (0..colors.len()).filter(|&i| colors[i] == tied) {
+            let stabilizer = search
+                .automorphisms
+                .iter()
+                .filter(|map| fixed.iter().all(|&i| map[i] == i));
+            let orbit = orbits(colors.len(), stabilizer);
+            if tried.iter().any(|&i| orbit[i] == orbit[chosen]) {
+                continue;
+            }
+            tried.push(chosen);
+            let split: Vec<(usize, bool)> = colors
+                .iter()
+                .enumerate()
+                .map(|(i, &c)| (c, c == tied && i != chosen))
+                .collect();
+            fixed.push(chosen);
+            self.search(ranks(&split), fixed, search);
+            fixed.pop();
+        }
+    }
+
+    fn encode(&self, positions: &[usize]) -> Encoding {
+        let mut atoms = vec![(0, 0, 0, 0); positions.len()];
+        for (i, &p) in positions.iter().enumerate() {
+            atoms[p] = self.invariants[i];
+        }
+        let mut bonds: Vec<(usize, usize, u8)> = self
+            .adjacency
+            .iter()
+            .enumerate()
+            .flat_map(|(i, bonds)| {
+                bonds.iter().filter(move |&&(j, _)| i < j).map(
+                    move |&(j, order)| {
+                        let (a, b) = (positions[i], positions[j]);
+                        (a.min(b), a.max(b), order)
+                    },
+                )
+            })
+            .collect();
+        bonds.sort();
+        (atoms, bonds)
+    }
+}
+
+/// Leaves of the search for the canonical labelling seen so far
+#[derive(Default)]
+struct Search {
+    /// The first labelling reached, with its encoding
+    first: Option<(Encoding, Vec<usize>)>,
+    /// The labelling with the smallest encoding so far
+    best: Option<(Encoding, Vec<usize>)>,
+    /// Automorphisms found as pairs of labellings with equal encodings,
+    /// each mapping atom `i` to `map[i]`
+    automorphisms: Vec<Vec<usize>>,
+}
+
+impl Search {
+    fn reach(&mut self, encoding: Encoding, labelling: Vec<usize>) {
+        for (seen, earlier) in self.first.iter().chain(self.best.iter()) {
+            if *seen != encoding || *earlier == labelling {
+                continue;
+            }
+            let mut atom_at = vec![0; earlier.len()];
+            for (i, &p) in earlier.iter().enumerate() {
+                atom_at[p] = i;
+            }
+            let map: Vec<usize> =
+                labelling.iter().map(|&p| atom_at[p]).collect();
+            if !self.automorphisms.contains(&map) {
+                self.automorphisms.push(map);
+            }
+        }
+        if self.first.is_none() {
+            self.first = Some((encoding.clone(), labelling.clone()));
+        }
+        if self.best.as_ref().is_none_or(|(best, _)| encoding < *best) {
+            self.best = Some((encoding, labelling));
+        }
+    }
+}
+
+/// Lowest atom each atom can be mapped onto by combining the given
+/// automorphisms
+fn orbits<'a>(
+    atoms: usize,
+    automorphisms: impl Iterator<Item = &'a Vec<usize>>,
+) -> Vec<usize> {
+    fn root(orbit: &mut [usize], mut i: usize) -> usize {
+        while orbit[i] != i {
+            orbit[i] = orbit[orbit[i]];
+            i = orbit[i];
+        }
+        i
+    }
+    let mut orbit: Vec<usize> = (0..atoms).collect();
+    for map in automorphisms {
+        for (i, &j) in map.iter().enumerate() {
+            let (a, b) = (root(&mut orbit, i), root(&mut orbit, j));
+            orbit[a.max(b)] = a.min(b);
+        }
+    }
+    (0..atoms).map(|i| root(&mut orbit, i)).collect()
+}
+
+/// Dense ranks of the keys: equal keys share a rank, lower keys get lower
+/// ranks.
+fn ranks<T: Ord + Clone>(keys: &[T]) -> Vec<usize> {
+    let mut sorted = keys.to_vec();
+    sorted.sort();
+    sorted.dedup();
+    keys.iter()
+        .map(|key| sorted.binary_search(key).expect("Key was sorted"))
+        .collect()
+}
+
+fn class_count(colors: &[usize]) -> usize {
+    colors.iter().max().map_or(0, |&max| max + 1)
+}
+
+impl Compound {
+    /// Symmetry class of every atom: atoms sharing a class can't be told
+    /// apart by their element, charge, isotope or surroundings.
+    pub fn symmetry_classes(&self) -> Vec<usize> {
+        let graph = Graph::of(self, true);
+        graph.refine(graph.initial_colors())
+    }
+
+    /// Key which is the same for two compounds exactly when they have the
+    /// same constitution (atoms, charges and bonds), whatever the order of
+    /// their atoms or whether their hydrogens are explicit.
+    pub fn canonical_key(&self) -> String {
+        self.key(true)
+    }
+
+    /// Canonical key telling apart the Kekulé structures of an aromatic
+    /// ring, for graphs which are still being built.
+    pub(crate) fn kekule_key(&self) -> String {
+        self.key(false)
+    }
+
+    fn key(&self, aromatic: bool) -> String {
+        let graph = Graph::of(&self.with_implicit_hydrogens(), aromatic);
+        let ((atoms, bonds), _, _) = graph.canonical(graph.initial_colors());
+        let atoms: Vec<String> = atoms
+            .iter()
+            .map(|(z, neutrons, charge, h)| {
+                format!("{}/{}/{}/{}", z, neutrons, charge, h)
+            })
+            .collect();
+        let bonds: Vec<String> = bonds
+            .iter()
+            .map(|(a, b, order)| format!("{}-{}:{}", a, b, order))
+            .collect();
+        format!("{};{}", atoms.join(","), bonds.join(","))
+    }
+
+    /// Canonical positions of the given atoms under every canonical
+    /// labelling of the compound (as it is, hydrogens included), one list
+    /// per distinct placement: the symmetries of the compound map the
+    /// atoms onto each other, and each image is labelled canonically.
+    pub(crate) fn canonical_labellings(
+        &self,
+        atoms: &[usize],
+    ) -> Vec<Vec<usize>> {
+        let graph = Graph::of(self, true);
+        let (_, positions, automorphisms) =
+            graph.canonical(graph.initial_colors());
+        let mut images = BTreeSet::from([atoms.to_vec()]);
+        let mut queue = vec![atoms.to_vec()];
+        while let Some(image) = queue.pop() {
+            for map in automorphisms.iter() {
+                let next: Vec<usize> = image.iter().map(|&i| map[i]).collect();
+                if images.insert(next.clone()) {
+                    queue.push(next);
+                }
+            }
+        }
+        images
+            .into_iter()
+            .map(|image| image.iter().map(|&i| positions[i]).collect())
+            .collect()
+    }
+
+    /// Whether the two compounds have the same constitution
+    pub fn same_constitution(&self, other: &Compound) -> bool {
+        self.canonical_key() == other.canonical_key()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use crate::matter::{
+        atom::Atom,
+        compound::{
+            bond::BondOrder,
+            builder::CompoundBuilder,
+            substituent::{AlkylGroup, Substituent},
+            Compound,
+        },
+    };
+
+    #[test]
+    fn canonical_key_ignores_atom_order() {
+        // 1-bromobutane, built from either end
+        let from_start = CompoundBuilder::new()
+            .linear_chain(4)
+            .and_then(|b| b.brominate(1))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        let from_end = CompoundBuilder::new()
+            .linear_chain(4)
+            .and_then(|b| b.brominate(4))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        let secondary = CompoundBuilder::new()
+            .linear_chain(4)
+            .and_then(|b| b.brominate(2))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert!(from_start.same_constitution(&from_end));
+        assert!(
+            from_start.same_constitution(&from_end.with_implicit_hydrogens())
+        );
+        assert!(!from_start.same_constitution(&secondary));
+
+        let isobutane = CompoundBuilder::new()
+            .linear_chain(3)
+            .and_then(|b| b.add_alkyl(2, AlkylGroup::Methyl))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        let butane = CompoundBuilder::new()
+            .linear_chain(4)
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert!(!isobutane.same_constitution(&butane));
+    }
+
+    #[test]
+    fn canonical_symmetry_classes() {
+        let compound = CompoundBuilder::new()
+            .linear_chain(3)
+            .and_then(|b| b.substitute(2, Substituent::Chloro))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        let classes = compound.symmetry_classes();
+        assert_eq!(classes[0], classes[2]);
+        assert_ne!(classes[0], classes[1]);
+        let hydrogens: Vec<usize> = (0..compound.atom_count())
+            .filter(|&i| compound.atoms()[i].get_element_num() == 1)
+            .map(|i| classes[i])
+            .collect();
+        assert_eq!(hydrogens.len(), 7);
+        let largest = hydrogens
+            .iter()
+            .map(|&c| hydrogens.iter().filter(|&&x| x == c).count())
+            .max();
+        assert_eq!(largest, Some(6));
+    }
+
+    #[test]
+    fn canonical_labellings_of_a_symmetric_compound() {
+        // Dodecane with two methyls on each inner carbon: 18432
+        // automorphisms, far too many to try every labelling
+        let mut bonds: BTreeMap<(usize, usize), BondOrder> =
+            (0..11).map(|i| ((i, i + 1), BondOrder::Single)).collect();
+        for i in 1..11 {
+            for methyl in [10 + 2 * i, 11 + 2 * i] {
+                bonds.insert((i, methyl), BondOrder::Single);
+            }
+        }
+        let compound = Compound::from_bonds(vec![Atom::carbon(); 32], &bonds);
+        let positions = |atoms: &[usize]| -> BTreeSet<Vec<usize>> {
+            compound.canonical_labellings(atoms).into_iter().collect()
+        };
+        // The ends of the chain are methyls of the tert-butyl groups too,
+        // so any of their 6 methyls can take the place of the first
+        assert_eq!(positions(&[12]).len(), 6);
+        assert_eq!(positions(&[12]), positions(&[0]));
+        assert_eq!(positions(&[12]), positions(&[31]));
+        assert!(positions(&[12]).is_disjoint(&positions(&[14])));
+        // Two methyls of a tert-butyl group go to two methyls of either
+        // tert-butyl group, in either order
+        assert_eq!(positions(&[12, 13]).len(), 12);
+    }
+}
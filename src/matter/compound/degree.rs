@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+
+use crate::matter::{atom::Atom, compound::Compound};
+
+/// Classification of a carbon by the number of carbons bonded to it
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Hash,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    strum_macros::Display,
+)]
+pub enum CarbonDegree {
+    /// No carbon neighbour (CH₄, CH₃X)
+    #[strum(serialize = "methyl")]
+    Methyl,
+    #[strum(serialize = "1°")]
+    Primary,
+    #[strum(serialize = "2°")]
+    Secondary,
+    #[strum(serialize = "3°")]
+    Tertiary,
+    #[strum(serialize = "4°")]
+    Quaternary,
+}
+
+impl CarbonDegree {
+    pub fn from_carbon_neighbors(count: usize) -> Self {
+        match count {
+            0 => Self::Methyl,
+            1 => Self::Primary,
+            2 => Self::Secondary,
+            3 => Self::Tertiary,
+            _ => Self::Quaternary,
+        }
+    }
+}
+
+impl Compound {
+    /// Atoms directly bonded to atom `i`, along with their index
+    pub fn adjacent_atoms(&self, i: usize) -> Vec<(usize, &Atom)> {
+        self.neighbors(i)
+            .into_iter()
+            .map(|j| (j, self.get_atom_unsafe(j)))
+            .collect()
+    }
+
+    /// Degree of carbon `i`; `None` when atom `i` isn't a carbon.
+    pub fn carbon_degree(&self, i: usize) -> Option<CarbonDegree> {
+        if self.get_atom(i)?.get_element_num() != 6 {
+            return None;
+        }
+        let carbons = self
+            .adjacent_atoms(i)
+            .into_iter()
+            .filter(|(_, atom)| atom.get_element_num() == 6)
+            .count();
+        Some(CarbonDegree::from_carbon_neighbors(carbons))
+    }
+
+    /// Degree of every carbon, by atom index
+    pub fn carbon_degrees(&self) -> BTreeMap<usize, CarbonDegree> {
+        (0..self.atom_count())
+            .filter_map(|i| Some((i, self.carbon_degree(i)?)))
+            .collect()
+    }
+
+    /// Degree of the carbon carrying hydrogen `h`; `None` when atom `h`
+    /// isn't a hydrogen on a carbon.
+    pub fn hydrogen_degree(&self, h: usize) -> Option<CarbonDegree> {
+        if self.get_atom(h)?.get_element_num() != 1 {
+            return None;
+        }
+        match self.neighbors(h)[..] {
+            [c] => self.carbon_degree(c),
+            _ => None,
+        }
+    }
+
+    /// Degree of every (explicit) hydrogen on a carbon, by atom index
+    pub fn hydrogen_degrees(&self) -> BTreeMap<usize, CarbonDegree> {
+        (0..self.atom_count())
+            .filter_map(|h| Some((h, self.hydrogen_degree(h)?)))
+            .collect()
+    }
+
+    /// Number of hydrogens, explicit or implicit, on carbons of each degree
+    pub fn hydrogen_counts_by_degree(&self) -> BTreeMap<CarbonDegree, usize> {
+        let mut counts = BTreeMap::new();
+        for (i, degree) in self.carbon_degrees() {
+            let hydrogens = self.hydrogen_count(i);
+            if hydrogens > 0 {
+                *counts.entry(degree).or_default() += hydrogens as usize;
+            }
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::compound::{
+        builder::CompoundBuilder, substituent::AlkylGroup,
+    };
+    use CarbonDegree::*;
+
+    #[test]
+    fn degree_of_carbons() {
+        let methane = CompoundBuilder::new()
+            .linear_chain(1)
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(methane.carbon_degrees(), BTreeMap::from([(0, Methyl)]));
+
+        // 2,2,3-trimethylbutane: every degree but methyl
+        let compound = CompoundBuilder::new()
+            .linear_chain(4)
+            .and_then(|b| b.add_alkyl(2, AlkylGroup::Methyl))
+            .and_then(|b| b.add_alkyl(2, AlkylGroup::Methyl))
+            .and_then(|b| b.add_alkyl(3, AlkylGroup::Methyl))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        let degrees = compound.carbon_degrees();
+        assert_eq!(degrees[&0], Primary);
+        assert_eq!(degrees[&1], Quaternary);
+        assert_eq!(degrees[&2], Tertiary);
+        assert_eq!(degrees[&3], Primary);
+        assert_eq!(degrees.values().filter(|&&d| d == Primary).count(), 5);
+        assert_eq!(
+            compound.hydrogen_counts_by_degree(),
+            BTreeMap::from([(Primary, 15), (Tertiary, 1)])
+        );
+        assert_eq!(Quaternary.to_string(), "4°");
+    }
+
+    #[test]
+    fn degree_of_hydrogens() {
+        let propane = CompoundBuilder::new()
+            .linear_chain(3)
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        let degrees = propane.hydrogen_degrees();
+        assert_eq!(degrees.len(), 8);
+        assert_eq!(degrees.values().filter(|&&d| d == Secondary).count(), 2);
+        assert_eq!(propane.hydrogen_degree(0), None);
+        let implicit = propane.with_implicit_hydrogens();
+        assert!(implicit.hydrogen_degrees().is_empty());
+        assert_eq!(
+            implicit.hydrogen_counts_by_degree(),
+            BTreeMap::from([(Primary, 6), (Secondary, 2)])
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use crate::matter::{atom::Atom, compound::Compound};
+use crate::matter::{
+    atom::Atom,
+    compound::{bond::BondOrder, Compound},
+};
 use std::fmt::{self, Display, Write};
 
 #[derive(Clone, Debug)]
@@ -10,6 +13,17 @@ where
     Vec(Vec<Chain<T>>, usize),
     /// Atoms
     KV(T, usize),
+    /// Multiple bond written between two neighbouring groups
+    Bond(BondOrder),
+}
+
+impl<T> Default for Chain<T>
+where
+    T: Display + Clone + PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T> Chain<T>
@@ -44,7 +58,7 @@ where
                 new_vec.push(other);
                 Self::Vec(new_vec, c).group().minimize()
             }
-            Self::KV(_, _) => {
+            Self::KV(_, _) | Self::Bond(_) => {
                 // Grouping & Minimization handled
                 Self::Vec(Vec::from([self, other]), 1).group().minimize()
             }
@@ -58,7 +72,7 @@ where
     /// Reverses order of chain
     fn reverse(&mut self) {
         match self {
-            Self::KV(_, _) => {}
+            Self::KV(_, _) | Self::Bond(_) => {}
             Self::Vec(v, _) => {
                 v.reverse();
                 for chain in v.iter_mut() {
@@ -80,6 +94,7 @@ where
         match self {
             Self::Vec(_, c) => *c += count,
             Self::KV(_, c) => *c += count,
+            Self::Bond(_) => {}
         }
     }
 
@@ -87,6 +102,7 @@ where
         match self {
             Self::Vec(_, c) => *c,
             Self::KV(_, c) => *c,
+            Self::Bond(_) => 1,
         }
     }
 
@@ -94,12 +110,24 @@ where
         match self {
             Self::Vec(chains, count) => {
                 let mut new_chains = Vec::<Chain<T>>::new();
-                for chain in chains {
+                let bonded: Vec<bool> = (0..chains.len())
+                    .map(|k| {
+                        let is_bond = |k: usize| {
+                            matches!(chains.get(k), Some(Self::Bond(_)))
+                        };
+                        (k > 0 && is_bond(k - 1)) || is_bond(k + 1)
+                    })
+                    .collect();
+                let mut last_bonded = false;
+                for (chain, bonded) in chains.iter().zip(bonded) {
                     let curr = chain.clone().group();
                     // Vec ordering implies connectivity.
-                    // Connects side chains together
+                    // Connects side chains together, except for groups on a
+                    // multiple bond (CH2=CHCH=CH2, not CH2=(CH)2=CH2)
+                    let merges = !bonded && !last_bonded;
+                    last_bonded = bonded;
                     if let Some(matched) = new_chains.last_mut() {
-                        if matched.custom_eq(&curr) {
+                        if merges && matched.custom_eq(&curr) {
                             matched.incr_count_by(curr.get_count());
                         } else {
                             new_chains.push(curr);
@@ -110,13 +138,14 @@ where
                 }
                 Self::Vec(new_chains, count)
             }
-            Self::KV(_, _) => self,
+            Self::KV(_, _) | Self::Bond(_) => self,
         }
     }
 
     fn minimize_by_factor(self, factor: usize) -> Self {
         match self {
             Self::KV(s, c) => Self::KV(s, c * factor),
+            Self::Bond(_) => self,
             Self::Vec(ref v, c) => {
                 if v.len() != 1 {
                     self
@@ -135,6 +164,18 @@ where
     }
 
     fn write_to<W: Write>(&self, w: &mut W) -> fmt::Result {
+        self.write_at(w, 0, false)
+    }
+
+    /// Writes the chain `depth` groups deep. The top level lists the
+    /// backbone in order; within an atom's group, nested groups are
+    /// branches and get parenthesized.
+    fn write_at<W: Write>(
+        &self,
+        w: &mut W,
+        depth: usize,
+        branch: bool,
+    ) -> fmt::Result {
         match self.clone().group().minimize() {
             Self::KV(s, c) => {
                 if c == 1 {
@@ -143,6 +184,7 @@ where
                     write!(w, "{}{}", s, c)?;
                 }
             }
+            Self::Bond(order) => write!(w, "{}", order)?,
             Self::Vec(v, count) => {
                 assert!(!v.is_empty());
                 if v.len() == 1 {
@@ -152,14 +194,18 @@ where
                         panic!("Minimalization isn't working correctly");
                     }
                 }
-                if count != 1 {
+                let parenthesize = branch || count != 1;
+                if parenthesize {
                     write!(w, "(")?;
                 }
-                for chain in v {
-                    chain.write_to(w)?;
+                for (k, chain) in v.iter().enumerate() {
+                    chain.write_at(w, depth + 1, depth > 0 && k > 0)?;
+                }
+                if parenthesize {
+                    write!(w, ")")?;
                 }
                 if count != 1 {
-                    write!(w, "){}", count)?;
+                    write!(w, "{}", count)?;
                 }
             }
         }
@@ -176,6 +222,8 @@ where
                 s_left == s_right
             }
             (Self::Vec(_, _), Self::Vec(_, _)) => self == other, // EXACT checks
+            // Groups on either side of a bond never merge
+            (Self::Bond(_), _) | (_, Self::Bond(_)) => false,
             (_, _) => false,
         }
     }
@@ -197,6 +245,7 @@ where
                     && lhs.iter().all(|l| rhs.contains(l))
                     && rhs.iter().all(|r| lhs.contains(r))
             }
+            (Self::Bond(lhs), Self::Bond(rhs)) => lhs == rhs,
             (_, _) => false,
         }
     }
@@ -212,31 +261,116 @@ where
 }
 
 impl Chain<Atom> {
-    fn from_atom(cmp: &Compound, i: usize) -> Self {
-        assert!(
-            cmp.has_side_chain(i),
-            "Compound must have side chain to deserialize it."
-        );
+    /// Whether atom `i` carries anything to write after it
+    fn has_substituents(cmp: &Compound, i: usize) -> bool {
+        cmp.has_side_chain(i) || cmp.implicit_hydrogens(i) > 0
+    }
+
+    /// Side chain atoms of `i` which aren't hydrogen leaves
+    fn heavy_branches(cmp: &Compound, i: usize) -> Vec<usize> {
+        cmp.side_chains
+            .get(&i)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|&j| {
+                cmp.get_atom_unsafe(j).get_element_num() != 1
+                    || cmp.has_side_chain(j)
+            })
+            .collect()
+    }
+
+    /// Atom `i` and everything hanging off it: hydrogens first, then its
+    /// branches, which are parenthesized. With `inline_last` the last
+    /// branch continues the chain instead. `skip` leaves one branch out.
+    fn atom_parts(
+        cmp: &Compound,
+        i: usize,
+        skip: Option<usize>,
+        inline_last: bool,
+    ) -> Vec<Self> {
         let mut chains = Vec::new();
         chains.push(Self::KV(cmp.get_atom_unsafe(i).clone(), 1));
-        let side_chain = cmp.get_sidechain_unsafe(i);
-        for &j in side_chain {
-            if cmp.has_side_chain(j) {
-                chains.push(Self::from_atom(cmp, j));
-            } else {
+        let implicit = cmp.implicit_hydrogens(i) as usize;
+        if implicit > 0 {
+            chains.push(Self::KV(Atom::hydrogen(), implicit));
+        }
+        let branches = Self::heavy_branches(cmp, i);
+        for &j in cmp.side_chains.get(&i).into_iter().flatten() {
+            if !branches.contains(&j) {
                 chains.push(Self::KV(cmp.get_atom_unsafe(j).clone(), 1));
             }
         }
-        Self::Vec(chains, 1)
+        let branches: Vec<usize> =
+            branches.into_iter().filter(|&j| Some(j) != skip).collect();
+        for (k, &j) in branches.iter().enumerate() {
+            let branch = Self::from_atom(cmp, j);
+            // Inline, the branch would merge with an identical atom before
+            // it (CC instead of C-C), and it can't join identical branches
+            // (CH(CH3)2)
+            let keep_apart = match chains.last() {
+                Some(Self::KV(atom, _)) => atom == cmp.get_atom_unsafe(j),
+                Some(last) => last.custom_eq(&branch),
+                None => false,
+            };
+            if inline_last && k + 1 == branches.len() && !keep_apart {
+                chains.extend(Self::atom_parts(cmp, j, None, true));
+            } else {
+                chains.push(branch);
+            }
+        }
+        chains
+    }
+
+    fn from_atom(cmp: &Compound, i: usize) -> Self {
+        if Self::has_substituents(cmp, i) {
+            Self::Vec(Self::atom_parts(cmp, i, None, true), 1)
+        } else {
+            Self::KV(cmp.get_atom_unsafe(i).clone(), 1)
+        }
     }
 }
 
 impl From<&Compound> for Chain<Atom> {
+    /// Condensed formula along the backbone. A lone branch on the last
+    /// backbone atom is written after it (CH3CH2OH) and one on the first
+    /// atom is written before it, reversed (HOCH2CH3). Multiple bonds
+    /// along the backbone are drawn (CH2=CHCH3, HC≡CCH3).
     fn from(val: &Compound) -> Self {
         let mut chains = Vec::new();
-        for &i in val.backbone.iter() {
-            if val.has_side_chain(i) {
-                chains.push(Self::from_atom(val, i));
+        let last = val.backbone.len().saturating_sub(1);
+        for (pos, &i) in val.backbone.iter().enumerate() {
+            if pos > 0 {
+                let previous = val.backbone[pos - 1];
+                match val.bond_order(previous, i) {
+                    Some(BondOrder::Single) | None => {}
+                    Some(order) => chains.push(Self::Bond(order)),
+                }
+            }
+            let leads_multiple_bond = pos == 0
+                && pos != last
+                && val.bond_order(i, val.backbone[1]) > Some(BondOrder::Single);
+            let branches = Self::heavy_branches(val, i);
+            let parts = Self::atom_parts(val, i, None, false);
+            if leads_multiple_bond
+                && parts.len() == 2
+                && parts[1] == Self::KV(Atom::hydrogen(), 1)
+            {
+                // A lone hydrogen goes in front of a terminal multiple bond
+                chains.push(Self::Vec(parts.into_iter().rev().collect(), 1));
+            } else if pos == last && branches.len() == 1 {
+                let parts = Self::atom_parts(val, i, None, true);
+                chains.push(Self::Vec(parts, 1));
+            } else if pos == 0 && branches.len() == 1 {
+                let branch = branches[0];
+                let mut lead =
+                    Self::Vec(Self::atom_parts(val, branch, None, true), 1);
+                lead.reverse();
+                let mut parts = vec![lead];
+                parts.extend(Self::atom_parts(val, i, Some(branch), false));
+                chains.push(Self::Vec(parts, 1));
+            } else if Self::has_substituents(val, i) {
+                chains.push(Self::Vec(parts, 1));
             } else {
                 chains.push(Self::KV(val.get_atom_unsafe(i).clone(), 1));
             }
@@ -407,7 +541,8 @@ mod tests {
                 "Linear chain expected while evaluating
             Deserialize functionality",
             )
-            .build();
+            .build()
+            .expect("Valid compound expected");
         assert_eq!(Chain::from(&hexane).to_string(), "CH3(CH2)4CH3")
     }
 
@@ -0,0 +1,405 @@
+use crate::matter::compound::{
+    bond::{bond_key, BondOrder},
+    degree::CarbonDegree,
+    Compound, CompoundResult,
+};
+
+/// Number of carbons on the carbon (or nitrogen, for amines) carrying a
+/// group
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum Degree {
+    /// 1°, including methyl groups
+    Primary,
+    /// 2°
+    Secondary,
+    /// 3°
+    Tertiary,
+}
+
+impl Degree {
+    fn from_carbons(carbons: usize) -> Self {
+        match carbons {
+            0 | 1 => Self::Primary,
+            2 => Self::Secondary,
+            _ => Self::Tertiary,
+        }
+    }
+}
+
+impl From<CarbonDegree> for Degree {
+    fn from(degree: CarbonDegree) -> Self {
+        match degree {
+            CarbonDegree::Methyl | CarbonDegree::Primary => Self::Primary,
+            CarbonDegree::Secondary => Self::Secondary,
+            CarbonDegree::Tertiary | CarbonDegree::Quaternary => Self::Tertiary,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum FunctionalGroupKind {
+    /// C-OH on an sp³ carbon
+    Alcohol(Degree),
+    /// C-X on an sp³ carbon
+    AlkylHalide(Degree),
+    /// C-O-C
+    Ether,
+    /// H-C=O
+    Aldehyde,
+    /// C-C(=O)-C
+    Ketone,
+    /// C(=O)OH
+    CarboxylicAcid,
+    /// C(=O)O-C
+    Ester,
+    /// C(=O)OC(=O)
+    AcidAnhydride,
+    /// C(=O)N
+    Amide,
+    /// N bonded to carbons; the degree counts them
+    Amine(Degree),
+    /// C≡N
+    Nitrile,
+    /// C=C outside aromatic rings
+    Alkene,
+    /// C≡C
+    Alkyne,
+    /// Aromatic ring
+    Arene,
+}
+
+/// A recognized group along with the indices of its atoms (hydrogens
+/// aside)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionalGroup {
+    pub kind: FunctionalGroupKind,
+    pub atoms: Vec<usize>,
+}
+
+const HALOGENS: [u8; 4] = [9, 17, 35, 53];
+
+impl Compound {
+    /// Every functional group of the compound, ordered by their atoms.
+    ///
+    /// Carbonyl groups are claimed by the most specific group (the
+    /// hydroxyl of a carboxylic acid isn't also an alcohol, nor the oxygen
+    /// of an ester an ether), and groups on aromatic or unsaturated carbons
+    /// (phenols, vinyl halides) aren't reported as alcohols or alkyl
+    /// halides.
+    pub fn functional_groups(&self) -> CompoundResult<Vec<FunctionalGroup>> {
+        let aromatic_rings = self.aromatic_rings()?;
+        let aromatic_bonds = self.aromatic_bonds()?;
+        let orders = self.bond_orders();
+        let adjacency = self.adjacency();
+        let z = |i: usize| self.atoms[i].get_element_num();
+        let order = |a: usize, b: usize| orders[&bond_key(a, b)];
+        let heavy = |i: usize| -> Vec<usize> {
+            adjacency[i]
+                .iter()
+                .copied()
+                .filter(|&j| z(j) != 1)
+                .collect()
+        };
+        let carbons = |i: usize| -> Vec<usize> {
+            adjacency[i]
+                .iter()
+                .copied()
+                .filter(|&j| z(j) == 6)
+                .collect()
+        };
+        // sp³ carbon
+        let saturated = |i: usize| {
+            z(i) == 6
+                && adjacency[i]
+                    .iter()
+                    .all(|&j| order(i, j) == BondOrder::Single)
+        };
+        let degree_of = |c: usize| {
+            Degree::from(self.carbon_degree(c).expect("sp³ carbon expected"))
+        };
+        let carbonyl_oxygen = |c: usize| {
+            heavy(c).into_iter().find(|&o| {
+                z(o) == 8
+                    && self.atoms[o].charge() == 0
+                    && order(c, o) == BondOrder::Double
+            })
+        };
+        let is_carbonyl = |c: usize| z(c) == 6 && carbonyl_oxygen(c).is_some();
+
+        let mut groups = Vec::new();
+        let mut push =
+            |kind, atoms| groups.push(FunctionalGroup { kind, atoms });
+        for (i, neighbors) in adjacency.iter().enumerate() {
+            if self.atoms[i].charge() != 0 {
+                continue;
+            }
+            match z(i) {
+                6 => {
+                    let Some(o) = carbonyl_oxygen(i) else {
+                        continue;
+                    };
+                    let others: Vec<usize> =
+                        heavy(i).into_iter().filter(|&j| j != o).collect();
+                    let single = |element: u8| {
+                        others.iter().copied().find(|&j| {
+                            z(j) == element && order(i, j) == BondOrder::Single
+                        })
+                    };
+                    if let Some(n) = single(7) {
+                        push(FunctionalGroupKind::Amide, vec![i, o, n]);
+                    } else if let Some(oh) = single(8) {
+                        let other = carbons(oh).into_iter().find(|&c| c != i);
+                        match other {
+                            // Both carbonyls find it; the first reports it
+                            Some(c) if is_carbonyl(c) => {
+                                if i < c {
+                                    let o2 = carbonyl_oxygen(c)
+                                        .expect("Carbonyl oxygen");
+                                    push(
+                                        FunctionalGroupKind::AcidAnhydride,
+                                        vec![i, o, oh, c, o2],
+                                    );
+                                }
+                            }
+                            Some(c) => push(
+                                FunctionalGroupKind::Ester,
+                                vec![i, o, oh, c],
+                            ),
+                            None => push(
+                                FunctionalGroupKind::CarboxylicAcid,
+                                vec![i, o, oh],
+                            ),
+                        }
+                    } else if self.hydrogen_count(i) > 0 {
+                        push(FunctionalGroupKind::Aldehyde, vec![i, o]);
+                    } else if others.iter().all(|&j| z(j) == 6) {
+                        push(FunctionalGroupKind::Ketone, vec![i, o]);
+                    }
+                }
+                7 => {
+                    let bonded = carbons(i);
+                    let plain = neighbors
+                        .iter()
+                        .all(|&j| order(i, j) == BondOrder::Single);
+                    if plain
+                        && !bonded.is_empty()
+                        && !bonded.iter().any(|&c| is_carbonyl(c))
+                    {
+                        let degree = Degree::from_carbons(bonded.len());
+                        let mut atoms = vec![i];
+                        atoms.extend(bonded);
+                        push(FunctionalGroupKind::Amine(degree), atoms);
+                    }
+                }
+                8 => match carbons(i)[..] {
+                    [c] if heavy(i).len() == 1
+                        && self.hydrogen_count(i) == 1
+                        && saturated(c) =>
+                    {
+                        let degree = degree_of(c);
+                        push(FunctionalGroupKind::Alcohol(degree), vec![c, i]);
+                    }
+                    [a, b] if !is_carbonyl(a) && !is_carbonyl(b) => {
+                        push(FunctionalGroupKind::Ether, vec![a, i, b]);
+                    }
+                    _ => (),
+                },
+                element if HALOGENS.contains(&element) => {
+                    if let [c] = carbons(i)[..] {
+                        if saturated(c) {
+                            let degree = degree_of(c);
+                            push(
+                                FunctionalGroupKind::AlkylHalide(degree),
+                                vec![c, i],
+                            );
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        for (&(a, b), &bond) in orders.iter() {
+            let kind = match (z(a), z(b), bond) {
+                (6, 6, BondOrder::Double)
+                    if !aromatic_bonds.contains(&(a, b)) =>
+                {
+                    FunctionalGroupKind::Alkene
+                }
+                (6, 6, BondOrder::Triple) => FunctionalGroupKind::Alkyne,
+                (6, 7, BondOrder::Triple) | (7, 6, BondOrder::Triple) => {
+                    FunctionalGroupKind::Nitrile
+                }
+                _ => continue,
+            };
+            push(kind, vec![a, b]);
+        }
+        for ring in aromatic_rings {
+            push(FunctionalGroupKind::Arene, ring);
+        }
+        groups.sort_by(|a, b| a.atoms.cmp(&b.atoms));
+        Ok(groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::{
+        atom::Atom,
+        compound::{
+            builder::{BuildStep, CompoundBuilder},
+            substituent::{AlkylGroup, Substituent},
+        },
+    };
+    use Degree::*;
+    use FunctionalGroupKind::*;
+
+    fn kinds(steps: &[BuildStep]) -> Vec<FunctionalGroupKind> {
+        let mut builder = CompoundBuilder::new();
+        for step in steps {
+            step(&mut builder).expect("Valid step expected");
+        }
+        let compound = builder.build().expect("Valid compound expected");
+        compound
+            .functional_groups()
+            .expect("Valid structure expected")
+            .into_iter()
+            .map(|group| group.kind)
+            .collect()
+    }
+
+    #[test]
+    fn functional_groups_by_degree() {
+        let cases: [(&[BuildStep], FunctionalGroupKind); 9] = [
+            (
+                &[|b| b.linear_chain(6), |b| b.brominate(6)],
+                AlkylHalide(Primary),
+            ),
+            (
+                &[|b| b.linear_chain(4), |b| b.brominate(2)],
+                AlkylHalide(Secondary),
+            ),
+            (
+                &[
+                    |b| b.linear_chain(3),
+                    |b| b.add_alkyl(2, AlkylGroup::Methyl),
+                    |b| b.substitute(2, Substituent::Chloro),
+                ],
+                AlkylHalide(Tertiary),
+            ),
+            (
+                &[|b| b.linear_chain(1), |b| b.brominate(1)],
+                AlkylHalide(Primary),
+            ),
+            (
+                &[
+                    |b| b.linear_chain(3),
+                    |b| b.replace_atom(3, Atom::new_unchecked(8)),
+                ],
+                { Alcohol(Primary) },
+            ),
+            (
+                &[
+                    |b| b.linear_chain(3),
+                    |b| b.substitute(2, Substituent::Hydroxy),
+                ],
+                { Alcohol(Secondary) },
+            ),
+            (
+                &[
+                    |b| b.linear_chain(2),
+                    |b| b.substitute(2, Substituent::Amino),
+                ],
+                { Amine(Primary) },
+            ),
+            (
+                &[
+                    |b| b.linear_chain(3),
+                    |b| b.replace_atom(2, Atom::nitrogen()),
+                ],
+                { Amine(Secondary) },
+            ),
+            (
+                &[
+                    |b| b.linear_chain(3),
+                    |b| b.replace_atom(2, Atom::nitrogen()),
+                    |b| b.add_alkyl(2, AlkylGroup::Methyl),
+                ],
+                Amine(Tertiary),
+            ),
+        ];
+        for (steps, expected) in cases {
+            assert_eq!(kinds(steps), vec![expected]);
+        }
+    }
+
+    #[test]
+    fn functional_groups_carbonyls() {
+        // Backbone C-C=O, then a group on the carbonyl carbon
+        let cases: [(BuildStep, FunctionalGroupKind); 5] = [
+            (|b| Ok(b), Aldehyde),
+            (|b| b.add_alkyl(2, AlkylGroup::Methyl), Ketone),
+            (|b| b.substitute(2, Substituent::Hydroxy), CarboxylicAcid),
+            (|b| b.substitute(2, Substituent::Methoxy), Ester),
+            (|b| b.substitute(2, Substituent::Amino), Amide),
+        ];
+        for (step, expected) in cases {
+            let found = kinds(&[
+                |b| b.linear_chain(3),
+                |b| b.replace_atom(3, Atom::new_unchecked(8)),
+                |b| b.double_bond(2, 3),
+                step,
+            ]);
+            assert_eq!(found, vec![expected]);
+        }
+        // Acetic anhydride: one group, not two esters
+        let found = kinds(&[
+            |b| b.linear_chain(5),
+            |b| b.replace_atom(1, Atom::new_unchecked(8)),
+            |b| b.replace_atom(3, Atom::new_unchecked(8)),
+            |b| b.replace_atom(5, Atom::new_unchecked(8)),
+            |b| b.double_bond(1, 2),
+            |b| b.double_bond(4, 5),
+            |b| b.add_alkyl(2, AlkylGroup::Methyl),
+            |b| b.add_alkyl(4, AlkylGroup::Methyl),
+        ]);
+        assert_eq!(found, vec![AcidAnhydride]);
+    }
+
+    #[test]
+    fn functional_groups_unsaturated() {
+        let cases: [(&[BuildStep], Vec<FunctionalGroupKind>); 6] = [
+            (
+                &[|b| b.linear_chain(3), |b| b.double_bond(1, 2)],
+                vec![Alkene],
+            ),
+            (
+                &[|b| b.linear_chain(3), |b| b.triple_bond(2, 3)],
+                vec![Alkyne],
+            ),
+            (
+                &[
+                    |b| b.linear_chain(2),
+                    |b| b.substitute(1, Substituent::Cyano),
+                ],
+                { vec![Nitrile] },
+            ),
+            (&[|b| b.benzene()], vec![Arene]),
+            (
+                &[|b| b.benzene(), |b| b.substitute(1, Substituent::Hydroxy)],
+                { vec![Arene] },
+            ),
+            (
+                &[
+                    |b| b.linear_chain(5),
+                    |b| b.replace_atom(3, Atom::new_unchecked(8)),
+                    |b| b.double_bond(1, 2),
+                ],
+                vec![Alkene, Ether],
+            ),
+        ];
+        for (steps, expected) in cases {
+            assert_eq!(kinds(steps), expected);
+        }
+    }
+}
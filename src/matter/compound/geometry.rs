@@ -0,0 +1,373 @@
+use std::fmt;
+
+use crate::matter::compound::{
+    lewis::LewisStructure, Compound, CompoundError, CompoundResult,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hybridization {
+    Sp,
+    Sp2,
+    Sp3,
+    Sp3d,
+    Sp3d2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ElectronGeometry {
+    Linear,
+    TrigonalPlanar,
+    Tetrahedral,
+    TrigonalBipyramidal,
+    Octahedral,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MolecularGeometry {
+    Linear,
+    TrigonalPlanar,
+    Bent,
+    Tetrahedral,
+    TrigonalPyramidal,
+    TrigonalBipyramidal,
+    Seesaw,
+    TShaped,
+    Octahedral,
+    SquarePyramidal,
+    SquarePlanar,
+}
+
+/// Arrangement of the electron domains around an atom and of the atoms
+/// bonded to it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VseprGeometry {
+    pub electron: ElectronGeometry,
+    pub molecular: MolecularGeometry,
+    /// Ideal angles (degrees) between bonded neighbours
+    pub bond_angles: Vec<f64>,
+}
+
+impl Hybridization {
+    /// Hybrid orbitals needed for the given number of electron domains
+    pub fn from_steric_number(steric_number: u8) -> Option<Self> {
+        match steric_number {
+            2 => Some(Self::Sp),
+            3 => Some(Self::Sp2),
+            4 => Some(Self::Sp3),
+            5 => Some(Self::Sp3d),
+            6 => Some(Self::Sp3d2),
+            _ => None,
+        }
+    }
+
+    pub fn steric_number(&self) -> u8 {
+        match self {
+            Self::Sp => 2,
+            Self::Sp2 => 3,
+            Self::Sp3 => 4,
+            Self::Sp3d => 5,
+            Self::Sp3d2 => 6,
+        }
+    }
+}
+
+impl fmt::Display for Hybridization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sp => write!(f, "sp"),
+            Self::Sp2 => write!(f, "sp²"),
+            Self::Sp3 => write!(f, "sp³"),
+            Self::Sp3d => write!(f, "sp³d"),
+            Self::Sp3d2 => write!(f, "sp³d²"),
+        }
+    }
+}
+
+impl fmt::Display for ElectronGeometry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Linear => "linear",
+            Self::TrigonalPlanar => "trigonal planar",
+            Self::Tetrahedral => "tetrahedral",
+            Self::TrigonalBipyramidal => "trigonal bipyramidal",
+            Self::Octahedral => "octahedral",
+        };
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for MolecularGeometry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Linear => "linear",
+            Self::TrigonalPlanar => "trigonal planar",
+            Self::Bent => "bent",
+            Self::Tetrahedral => "tetrahedral",
+            Self::TrigonalPyramidal => "trigonal pyramidal",
+            Self::TrigonalBipyramidal => "trigonal bipyramidal",
+            Self::Seesaw => "seesaw",
+            Self::TShaped => "T-shaped",
+            Self::Octahedral => "octahedral",
+            Self::SquarePyramidal => "square pyramidal",
+            Self::SquarePlanar => "square planar",
+        };
+        f.write_str(name)
+    }
+}
+
+impl VseprGeometry {
+    /// Looks up the VSEPR shape for `bonded` neighbours and `lone_pairs`
+    /// non-bonding domains.
+    pub fn from_domains(bonded: u8, lone_pairs: u8) -> Option<Self> {
+        use ElectronGeometry as E;
+        use MolecularGeometry as M;
+        let (electron, molecular, bond_angles) =
+            match (bonded + lone_pairs, lone_pairs) {
+                (2, 0) => (E::Linear, M::Linear, vec![180.0]),
+                (3, 0) => (E::TrigonalPlanar, M::TrigonalPlanar, vec![120.0]),
+                (3, 1) => (E::TrigonalPlanar, M::Bent, vec![120.0]),
+                (4, 0) => (E::Tetrahedral, M::Tetrahedral, vec![109.5]),
+                (4, 1) => (E::Tetrahedral, M::TrigonalPyramidal, vec![109.5]),
+                (4, 2) => (E::Tetrahedral, M::Bent, vec![109.5]),
+                (5, 0) => (
+                    E::TrigonalBipyramidal,
+                    M::TrigonalBipyramidal,
+                    vec![90.0, 120.0, 180.0],
+                ),
+                (5, 1) => (
+                    E::TrigonalBipyramidal,
+                    M::Seesaw,
+                    vec![90.0, 120.0, 180.0],
+                ),
+                (5, 2) => {
+                    (E::TrigonalBipyramidal, M::TShaped, vec![90.0, 180.0])
+                }
+                (5, 3) => (E::TrigonalBipyramidal, M::Linear, vec![180.0]),
+                (6, 0) => (E::Octahedral, M::Octahedral, vec![90.0, 180.0]),
+                (6, 1) => {
+                    (E::Octahedral, M::SquarePyramidal, vec![90.0, 180.0])
+                }
+                (6, 2) => (E::Octahedral, M::SquarePlanar, vec![90.0, 180.0]),
+                (6, 3) => (E::Octahedral, M::TShaped, vec![90.0, 180.0]),
+                (6, 4) => (E::Octahedral, M::Linear, vec![180.0]),
+                (2..=6, _) if bonded == 1 => {
+                    // A single bond has no angle; the pair is trivially linear
+                    let electron = Self::from_domains(bonded + lone_pairs, 0)?;
+                    (electron.electron, M::Linear, Vec::new())
+                }
+                _ => return None,
+            };
+        Some(Self {
+            electron,
+            molecular,
+            bond_angles,
+        })
+    }
+}
+
+impl Compound {
+    /// Bonded neighbours and lone pairs around atom `i`
+    fn electron_domains(&self, i: usize) -> CompoundResult<(u8, u8)> {
+        if i >= self.atom_count() {
+            return Err(CompoundError::Geometry(format!(
+                "Invalid atom index: {} (length is {})",
+                i,
+                self.atom_count()
+            )));
+        }
+        Ok(self.electron_domains_in(&self.lewis_structure()?, i))
+    }
+
+    /// Bonded neighbours and lone pairs around atom `i` in a Lewis
+    /// structure already worked out for the Compound
+    fn electron_domains_in(
+        &self,
+        lewis: &LewisStructure,
+        i: usize,
+    ) -> (u8, u8) {
+        let bonded = self.neighbors(i).len() as u8 + self.implicit_hydrogens(i);
+        (bonded, lewis.lone_pairs[i])
+    }
+
+    /// VSEPR geometry of every atom, indexed by atom index, from a single
+    /// Lewis structure. Atoms without a shape (e.g. hydrogens) are `None`.
+    pub fn geometries(&self) -> CompoundResult<Vec<Option<VseprGeometry>>> {
+        let lewis = self.lewis_structure()?;
+        Ok((0..self.atom_count())
+            .map(|i| {
+                let (bonded, lone_pairs) = self.electron_domains_in(&lewis, i);
+                VseprGeometry::from_domains(bonded, lone_pairs)
+            })
+            .collect())
+    }
+
+    /// Hybridization of atom `i` from its steric number (σ bonds plus lone
+    /// pairs).
+    pub fn hybridization(&self, i: usize) -> CompoundResult<Hybridization> {
+        let (bonded, lone_pairs) = self.electron_domains(i)?;
+        Hybridization::from_steric_number(bonded + lone_pairs).ok_or_else(
+            || {
+                CompoundError::Geometry(format!(
+                    "Atom {} ({}) has {} electron domains",
+                    i,
+                    self.atoms()[i],
+                    bonded + lone_pairs
+                ))
+            },
+        )
+    }
+
+    /// Electron and molecular geometry around atom `i`, with the ideal bond
+    /// angles.
+    pub fn vsepr_geometry(&self, i: usize) -> CompoundResult<VseprGeometry> {
+        let (bonded, lone_pairs) = self.electron_domains(i)?;
+        VseprGeometry::from_domains(bonded, lone_pairs).ok_or_else(|| {
+            CompoundError::Geometry(format!(
+                "No VSEPR shape for atom {} ({}) with {} bonds and {} lone \
+                    pairs",
+                i,
+                self.atoms()[i],
+                bonded,
+                lone_pairs
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matter::{
+        atom::Atom,
+        compound::{bond::BondOrder, builder::CompoundBuilder},
+    };
+
+    use super::*;
+
+    fn star(center: Atom, ligands: Vec<Atom>) -> Compound {
+        let mut atoms = vec![center];
+        let mut edges = Vec::new();
+        for ligand in ligands {
+            edges.push((0, atoms.len(), BondOrder::Single));
+            atoms.push(ligand);
+        }
+        Compound::from_edges(atoms, &edges)
+    }
+
+    fn fluorine() -> Atom {
+        Atom::new_unchecked(9)
+    }
+
+    #[test]
+    fn geometry_second_period() {
+        let oxygen = Atom::new_unchecked(8);
+        let hexane = CompoundBuilder::new()
+            .linear_chain(6)
+            .expect("Linear chain expected")
+            .build()
+            .expect("Valid compound expected");
+        let water = star(oxygen.clone(), vec![Atom::hydrogen(); 2]);
+        let ammonia = star(Atom::nitrogen(), vec![Atom::hydrogen(); 3]);
+        let co2 = star(Atom::carbon(), vec![oxygen.clone(), oxygen.clone()]);
+        let formaldehyde = star(
+            Atom::carbon(),
+            vec![oxygen, Atom::hydrogen(), Atom::hydrogen()],
+        );
+        let cases = [
+            (hexane, Hybridization::Sp3, MolecularGeometry::Tetrahedral),
+            (water, Hybridization::Sp3, MolecularGeometry::Bent),
+            (
+                ammonia,
+                Hybridization::Sp3,
+                MolecularGeometry::TrigonalPyramidal,
+            ),
+            (co2, Hybridization::Sp, MolecularGeometry::Linear),
+            (
+                formaldehyde,
+                Hybridization::Sp2,
+                MolecularGeometry::TrigonalPlanar,
+            ),
+        ];
+        for (compound, hybridization, geometry) in cases {
+            assert_eq!(
+                compound.hybridization(0).expect("Hybridization"),
+                hybridization
+            );
+            assert_eq!(
+                compound.vsepr_geometry(0).expect("Geometry").molecular,
+                geometry
+            );
+        }
+    }
+
+    #[test]
+    fn geometry_expanded_octets() {
+        let sulfur = Atom::new_unchecked(16);
+        let cases = [
+            (
+                star(sulfur.clone(), vec![fluorine(); 6]),
+                Hybridization::Sp3d2,
+                MolecularGeometry::Octahedral,
+            ),
+            (
+                star(sulfur, vec![fluorine(); 4]),
+                Hybridization::Sp3d,
+                MolecularGeometry::Seesaw,
+            ),
+            (
+                star(Atom::new_unchecked(17), vec![fluorine(); 3]),
+                Hybridization::Sp3d,
+                MolecularGeometry::TShaped,
+            ),
+            (
+                star(Atom::new_unchecked(54), vec![fluorine(); 4]),
+                Hybridization::Sp3d2,
+                MolecularGeometry::SquarePlanar,
+            ),
+        ];
+        for (compound, hybridization, geometry) in cases {
+            assert_eq!(
+                compound.hybridization(0).expect("Hybridization"),
+                hybridization
+            );
+            assert_eq!(
+                compound.vsepr_geometry(0).expect("Geometry").molecular,
+                geometry
+            );
+        }
+    }
+
+    #[test]
+    fn geometry_bond_angles() {
+        let ammonia = star(Atom::nitrogen(), vec![Atom::hydrogen(); 3]);
+        let geometry = ammonia.vsepr_geometry(0).expect("Geometry");
+        assert_eq!(geometry.electron, ElectronGeometry::Tetrahedral);
+        assert_eq!(geometry.bond_angles, vec![109.5]);
+        assert_eq!(geometry.molecular.to_string(), "trigonal pyramidal");
+    }
+
+    #[test]
+    fn geometry_of_every_atom() {
+        let oxygen = Atom::new_unchecked(8);
+        let formaldehyde = star(
+            Atom::carbon(),
+            vec![oxygen, Atom::hydrogen(), Atom::hydrogen()],
+        );
+        let geometries = formaldehyde.geometries().expect("Geometries");
+        assert_eq!(geometries.len(), 4);
+        for (i, geometry) in geometries.into_iter().enumerate() {
+            assert_eq!(geometry, formaldehyde.vsepr_geometry(i).ok());
+        }
+    }
+
+    #[test]
+    fn geometry_terminal_and_invalid_atoms() {
+        let water = star(Atom::new_unchecked(8), vec![Atom::hydrogen(); 2]);
+        assert!(water.hybridization(1).is_err());
+        let terminal = star(Atom::carbon(), vec![Atom::new_unchecked(8); 2])
+            .vsepr_geometry(1)
+            .expect("Geometry");
+        assert_eq!(terminal.electron, ElectronGeometry::TrigonalPlanar);
+        assert!(terminal.bond_angles.is_empty());
+        assert!(water.vsepr_geometry(7).is_err());
+    }
+}
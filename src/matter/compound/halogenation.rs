@@ -0,0 +1,214 @@
+use crate::matter::{
+    atom::Atom,
+    compound::{degree::CarbonDegree, Compound, CompoundError, CompoundResult},
+};
+
+/// Halogen taking part in free-radical halogenation, as X₂
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum Halogen {
+    #[strum(serialize = "Cl2")]
+    Chlorine,
+    #[strum(serialize = "Br2")]
+    Bromine,
+}
+
+impl Halogen {
+    pub fn atom(&self) -> Atom {
+        match self {
+            Self::Chlorine => Atom::chlorine(),
+            Self::Bromine => Atom::bromine(),
+        }
+    }
+
+    /// Rate of abstraction per hydrogen, relative to a primary hydrogen
+    /// (1°:2°:3° of 1:3.8:5 for chlorine and 1:82:1600 for bromine at room
+    /// temperature). Methyl hydrogens count as primary.
+    pub fn relative_reactivity(&self, degree: CarbonDegree) -> f64 {
+        match (self, degree) {
+            (_, CarbonDegree::Methyl | CarbonDegree::Primary) => 1.0,
+            (Self::Chlorine, CarbonDegree::Secondary) => 3.8,
+            (Self::Chlorine, _) => 5.0,
+            (Self::Bromine, CarbonDegree::Secondary) => 82.0,
+            (Self::Bromine, _) => 1600.0,
+        }
+    }
+}
+
+/// One monohalogenation product, standing for every symmetry-equivalent
+/// position
+#[derive(Clone, Debug)]
+pub struct HalogenationProduct {
+    pub compound: Compound,
+    /// Lowest carbon (index in the alkane) giving this product
+    pub carbon: usize,
+    pub degree: CarbonDegree,
+    /// Hydrogens whose replacement gives this product
+    pub hydrogens: usize,
+    /// Share of the monohalogenated products, between 0 and 1
+    pub fraction: f64,
+}
+
+impl Compound {
+    /// Monohalogenation products of an alkane with X₂ under light or heat.
+    ///
+    /// Every hydrogen is replaced in turn (like [`brominate`] does on one
+    /// position); products with the same constitution are merged, and each
+    /// gets its share from its hydrogen count times the reactivity of its
+    /// hydrogens. Products are ordered by their carbon.
+    ///
+    /// [`brominate`]: crate::matter::compound::builder::CompoundBuilder::brominate
+    pub fn radical_halogenation(
+        &self,
+        halogen: Halogen,
+    ) -> CompoundResult<Vec<HalogenationProduct>> {
+        let is_alkane = self
+            .atoms
+            .iter()
+            .all(|atom| matches!(atom.get_element_num(), 1 | 6))
+            && self.bond_orders.is_empty();
+        if !is_alkane {
+            return Err(CompoundError::Reaction(
+                "Radical halogenation expects an alkane".into(),
+            ));
+        }
+        let compound = self.with_explicit_hydrogens();
+        let mut products: Vec<(String, HalogenationProduct)> = Vec::new();
+        for (h, degree) in compound.hydrogen_degrees() {
+            let carbon = compound.neighbors(h)[0];
+            let mut product = compound.clone();
+            product.atoms[h] = halogen.atom();
+            if self.has_implicit_hydrogens() {
+                product.make_hydrogens_implicit();
+            }
+            let key = product.canonical_key();
+            match products.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, existing)) => {
+                    existing.hydrogens += 1;
+                    existing.carbon = existing.carbon.min(carbon);
+                }
+                None => products.push((
+                    key,
+                    HalogenationProduct {
+                        compound: product,
+                        carbon,
+                        degree,
+                        hydrogens: 1,
+                        fraction: 0.0,
+                    },
+                )),
+            }
+        }
+        let mut products: Vec<HalogenationProduct> =
+            products.into_iter().map(|(_, product)| product).collect();
+        let rate = |product: &HalogenationProduct| {
+            product.hydrogens as f64
+                * halogen.relative_reactivity(product.degree)
+        };
+        let total: f64 = products.iter().map(rate).sum();
+        for product in products.iter_mut() {
+            product.fraction = rate(product) / total;
+        }
+        products.sort_by_key(|product| product.carbon);
+        Ok(products)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::compound::{
+        builder::CompoundBuilder, substituent::AlkylGroup,
+    };
+
+    fn percentages(products: &[HalogenationProduct]) -> Vec<(String, u32)> {
+        products
+            .iter()
+            .map(|product| {
+                (
+                    product.compound.iupac_name().expect("Named product"),
+                    (product.fraction * 100.0).round() as u32,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn halogenation_of_propane() {
+        let propane = CompoundBuilder::new()
+            .linear_chain(3)
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        let chlorination = propane
+            .radical_halogenation(Halogen::Chlorine)
+            .expect("Alkane expected");
+        assert_eq!(
+            percentages(&chlorination),
+            vec![
+                ("1-chloropropane".to_string(), 44),
+                ("2-chloropropane".to_string(), 56)
+            ]
+        );
+        assert_eq!(chlorination[0].hydrogens, 6);
+        assert_eq!(chlorination[1].degree, CarbonDegree::Secondary);
+        let bromination = propane
+            .radical_halogenation(Halogen::Bromine)
+            .expect("Alkane expected");
+        assert_eq!(
+            percentages(&bromination),
+            vec![
+                ("1-bromopropane".to_string(), 4),
+                ("2-bromopropane".to_string(), 96)
+            ]
+        );
+    }
+
+    #[test]
+    fn halogenation_merges_equivalent_positions() {
+        // 2-methylbutane: four distinct products
+        let compound = CompoundBuilder::new()
+            .linear_chain(4)
+            .and_then(|b| b.add_alkyl(2, AlkylGroup::Methyl))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        let products = compound
+            .radical_halogenation(Halogen::Chlorine)
+            .expect("Alkane expected")
+            .into_iter()
+            .map(|product| (product.hydrogens, product.degree))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            products,
+            vec![
+                (6, CarbonDegree::Primary),
+                (1, CarbonDegree::Tertiary),
+                (2, CarbonDegree::Secondary),
+                (3, CarbonDegree::Primary),
+            ]
+        );
+
+        let cyclohexane = CompoundBuilder::new()
+            .ring(6)
+            .and_then(|b| b.build())
+            .expect("Valid compound expected")
+            .with_implicit_hydrogens();
+        let products = cyclohexane
+            .radical_halogenation(Halogen::Bromine)
+            .expect("Alkane expected");
+        assert_eq!(products.len(), 1);
+        assert_eq!(products[0].fraction, 1.0);
+        assert_eq!(products[0].compound.molecular_formula(), "C6H11Br");
+    }
+
+    #[test]
+    fn halogenation_rejects_non_alkanes() {
+        let propene = CompoundBuilder::new()
+            .linear_chain(3)
+            .and_then(|b| b.double_bond(1, 2))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert!(matches!(
+            propene.radical_halogenation(Halogen::Chlorine),
+            Err(CompoundError::Reaction(_))
+        ));
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+
+use crate::matter::{atom::Atom, compound::Compound};
+
+impl Compound {
+    /// Hydrogens on atom `i` that aren't stored as atoms
+    pub fn implicit_hydrogens(&self, i: usize) -> u8 {
+        self.implicit_hydrogens.get(&i).copied().unwrap_or(0)
+    }
+
+    pub fn has_implicit_hydrogens(&self) -> bool {
+        self.implicit_hydrogens.values().any(|&count| count > 0)
+    }
+
+    /// Hydrogens bonded to atom `i`, whether implicit or explicit (isotopes
+    /// included).
+    pub fn hydrogen_count(&self, i: usize) -> u8 {
+        let explicit = self
+            .neighbors(i)
+            .into_iter()
+            .filter(|&j| self.atoms[j].get_element_num() == 1)
+            .count() as u8;
+        explicit + self.implicit_hydrogens(i)
+    }
+
+    /// Folds every plain hydrogen into the count of the heavy atom it's
+    /// bonded to.
+    ///
+    /// Isotopic hydrogens (deuterium, tritium), hydrogen ions and hydrogens
+    /// bonded only to other hydrogens stay explicit. Remaining atoms keep
+    /// their relative order, but their indices shift down.
+    pub fn make_hydrogens_implicit(&mut self) {
+        let removed = self.foldable_hydrogens();
+        for (&hydrogen, &parent) in removed.iter() {
+            *self.implicit_hydrogens.entry(parent).or_default() += 1;
+            // A hydrogen the rest of the molecule hangs off (H-Cl built from
+            // the hydrogen) hands its place in the backbone to its parent
+            if let Some(slot) =
+                self.backbone.iter_mut().find(|i| **i == hydrogen)
+            {
+                if self
+                    .side_chains
+                    .get(&hydrogen)
+                    .is_some_and(|side_chain| side_chain.contains(&parent))
+                {
+                    *slot = parent;
+                    self.side_chains.remove(&hydrogen);
+                }
+            }
+        }
+        self.remove_atoms(&removed.into_keys().collect());
+    }
+
+    /// Plain hydrogens which [`Self::make_hydrogens_implicit`] folds
+    /// away, along with the heavy atom each one is bonded to
+    pub(crate) fn foldable_hydrogens(&self) -> BTreeMap<usize, usize> {
+        self.adjacency()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, neighbors)| match neighbors[..] {
+                [parent]
+                    if self.atoms[i].is_plain_hydrogen()
+                        && self.atoms[parent].get_element_num() != 1 =>
+                {
+                    Some((i, parent))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Turns every implicit hydrogen into an atom hanging off its heavy atom.
+    ///
+    /// The new hydrogens are appended, so existing atoms keep their indices.
+    pub fn make_hydrogens_explicit(&mut self) {
+        let implicit = std::mem::take(&mut self.implicit_hydrogens);
+        let mut parents: Vec<(usize, u8)> = implicit.into_iter().collect();
+        parents.sort();
+        let placed = self.locations.len() == self.atoms.len();
+        for (parent, count) in parents {
+            for _ in 0..count {
+                let idx = self.atoms.len();
+                self.atoms.push(Atom::hydrogen());
+                self.side_chains.entry(parent).or_default().insert(idx);
+                if let Some(slot) =
+                    self.chirality.get_mut(&parent).and_then(|around| {
+                        around.iter_mut().find(|j| **j == parent)
+                    })
+                {
+                    *slot = idx;
+                }
+                if let Some((_, slot)) = self
+                    .double_bond_stereo
+                    .iter_mut()
+                    .flat_map(|(&(a, b), [x, y])| [(a, x), (b, y)])
+                    .find(|(end, slot)| *end == parent && **slot == parent)
+                {
+                    *slot = idx;
+                }
+                if placed {
+                    let loc = self.locations[parent]
+                        .nearest_free(&self.location_to_idx);
+                    self.locations.push(loc);
+                    self.location_to_idx.insert(loc, idx);
+                }
+            }
+        }
+    }
+
+    /// Copy of the Compound with every hydrogen explicit
+    pub fn with_explicit_hydrogens(&self) -> Self {
+        let mut compound = self.clone();
+        compound.make_hydrogens_explicit();
+        compound
+    }
+
+    /// Copy of the Compound with plain hydrogens implicit
+    pub fn with_implicit_hydrogens(&self) -> Self {
+        let mut compound = self.clone();
+        compound.make_hydrogens_implicit();
+        compound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::matter::compound::{bond::BondOrder, builder::CompoundBuilder};
+
+    use super::*;
+
+    fn hexane() -> Compound {
+        CompoundBuilder::new()
+            .linear_chain(6)
+            .expect("Linear chain expected")
+            .build()
+            .expect("Valid compound expected")
+    }
+
+    #[test]
+    fn hydrogens_round_trip() {
+        let explicit = hexane();
+        let mut implicit = explicit.with_implicit_hydrogens();
+        assert_eq!(implicit.atom_count(), 6);
+        assert_eq!(implicit.implicit_hydrogens(0), 3);
+        assert_eq!(implicit.implicit_hydrogens(2), 2);
+        assert_eq!(implicit.hydrogen_count(5), 3);
+        assert_eq!(implicit.to_string(), explicit.to_string());
+
+        implicit.make_hydrogens_explicit();
+        assert_eq!(implicit.atom_count(), 20);
+        assert!(!implicit.has_implicit_hydrogens());
+        assert_eq!(implicit.hydrogen_count(2), 2);
+        assert_eq!(implicit.to_string(), "CH3(CH2)4CH3");
+        let distinct: HashSet<_> = implicit.locations().iter().collect();
+        assert_eq!(distinct.len(), 20);
+    }
+
+    #[test]
+    fn hydrogens_keep_isotopes_explicit() {
+        // CH3D
+        let atoms = vec![
+            Atom::carbon(),
+            Atom::deuterium(),
+            Atom::hydrogen(),
+            Atom::hydrogen(),
+            Atom::hydrogen(),
+        ];
+        let edges: Vec<_> = (1..5).map(|j| (0, j, BondOrder::Single)).collect();
+        let mut compound = Compound::from_edges(atoms, &edges);
+        compound.make_hydrogens_implicit();
+        assert_eq!(compound.atom_count(), 2);
+        assert_eq!(compound.atoms()[1], Atom::deuterium());
+        assert_eq!(compound.atoms()[1].neutrons, 1);
+        assert_eq!(compound.implicit_hydrogens(0), 3);
+        assert_eq!(compound.hydrogen_count(0), 4);
+        compound.make_hydrogens_explicit();
+        assert_eq!(compound.atom_count(), 5);
+        assert!(compound.locations().is_empty());
+    }
+
+    #[test]
+    fn hydrogens_in_the_backbone() {
+        let chlorine = Atom::new_unchecked(17);
+        let edges = [(0, 1, BondOrder::Single)];
+        let mut hcl =
+            Compound::from_edges(vec![Atom::hydrogen(), chlorine], &edges);
+        hcl.make_hydrogens_implicit();
+        assert_eq!(hcl.atom_count(), 1);
+        assert_eq!(hcl.hydrogen_count(0), 1);
+        assert_eq!(hcl.to_string(), "ClH");
+        hcl.make_hydrogens_explicit();
+        assert_eq!(hcl.bonds().len(), 1);
+
+        let mut hydrogen = Compound::from_edges(
+            vec![Atom::hydrogen(), Atom::hydrogen()],
+            &edges,
+        );
+        hydrogen.make_hydrogens_implicit();
+        assert_eq!(hydrogen.atom_count(), 2);
+        assert_eq!(hydrogen.bonds().len(), 1);
+        assert_eq!(hydrogen.to_string(), "H2");
+    }
+
+    #[test]
+    fn hydrogens_implicit_lewis_structure() {
+        let implicit = hexane().with_implicit_hydrogens();
+        let lewis = implicit.lewis_structure().expect("Lewis structure");
+        assert_eq!(lewis.lone_pairs, vec![0; 6]);
+        assert_eq!(lewis.bonds.len(), 5);
+        assert_eq!(
+            implicit.vsepr_geometry(0).expect("Geometry").bond_angles,
+            vec![109.5]
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::matter::{
+    atom::Atom,
+    compound::{
+        bond::BondOrder, builder::CompoundBuilder, Compound, CompoundError,
+        CompoundResult,
+    },
+};
+
+/// Heavy atoms and the bonds between them; hydrogens fill whatever valence
+/// is left.
+#[derive(Clone, Debug)]
+struct Skeleton {
+    atoms: Vec<Atom>,
+    bonds: BTreeMap<(usize, usize), u8>,
+}
+
+/// What takes a skeleton one step closer to the isomers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    /// Bond one more of the formula's heavy atoms to the skeleton
+    AddAtom,
+    /// Raise a bond's order, or close a ring
+    Unsaturate,
+}
+
+impl Skeleton {
+    fn valence(atom: &Atom) -> u8 {
+        atom.valences().first().copied().unwrap_or(0)
+    }
+
+    fn free_valence(&self, i: usize) -> u8 {
+        let bonded: u8 = self
+            .bonds
+            .iter()
+            .filter(|(&(a, b), _)| a == i || b == i)
+            .map(|(_, &order)| order)
+            .sum();
+        Self::valence(&self.atoms[i]).saturating_sub(bonded)
+    }
+
+    /// Skeletons one step further along, duplicates included
+    fn children(&self, step: Step, heavy: &[Atom]) -> Vec<Skeleton> {
+        let free: Vec<usize> = (0..self.atoms.len())
+            .filter(|&i| self.free_valence(i) > 0)
+            .collect();
+        let mut children = Vec::new();
+        match step {
+            Step::AddAtom => {
+                let mut missing = heavy.to_vec();
+                for atom in self.atoms.iter() {
+                    let k = missing
+                        .iter()
+                        .position(|m| m == atom)
+                        .expect("Skeletons only hold the formula's atoms");
+                    missing.remove(k);
+                }
+                missing.dedup();
+                for atom in missing {
+                    for &parent in free.iter() {
+                        let mut child = self.clone();
+                        child.atoms.push(atom.clone());
+                        child.bonds.insert((parent, self.atoms.len()), 1);
+                        children.push(child);
+                    }
+                }
+            }
+            Step::Unsaturate => {
+                for (k, &a) in free.iter().enumerate() {
+                    for &b in free[k + 1..].iter() {
+                        let mut child = self.clone();
+                        let order = child.bonds.entry((a, b)).or_insert(0);
+                        if *order < 3 {
+                            *order += 1;
+                            children.push(child);
+                        }
+                    }
+                }
+            }
+        }
+        children
+    }
+
+    fn edges(&self) -> Vec<(usize, usize, BondOrder)> {
+        self.bonds
+            .iter()
+            .map(|(&(a, b), &order)| {
+                (a, b, BondOrder::from_u8(order).expect("Orders are 1-3"))
+            })
+            .collect()
+    }
+
+    /// Bare compound, hydrogens left implicit
+    fn compound(&self) -> Compound {
+        let mut compound =
+            Compound::from_edges(self.atoms.clone(), &self.edges());
+        compound.implicit_hydrogens = (0..self.atoms.len())
+            .map(|i| (i, self.free_valence(i)))
+            .filter(|&(_, h)| h > 0)
+            .collect();
+        compound
+    }
+}
+
+/// Lazily enumerates the constitutional isomers of a molecular formula,
+/// each one built once, up to the order of its atoms.
+///
+/// Skeletons are grown one heavy atom at a time (trees), then unsaturated
+/// one bond order or ring at a time, dropping duplicates at every level.
+/// Growth is depth-first, so every level is only generated as far as the
+/// iterator has advanced.
+pub struct Isomers {
+    heavy: Vec<Atom>,
+    /// What takes a skeleton from each level to the next
+    steps: Vec<Step>,
+    /// Skeletons still to grow, with their level
+    stack: Vec<(usize, Skeleton)>,
+    /// Keys of the skeletons reached at each level
+    seen: Vec<HashSet<String>>,
+}
+
+impl Isomers {
+    /// Prepares the isomers of `formula` (e.g. `C6H14`, `C₄H₉Br`).
+    ///
+    /// Every heavy atom takes its lowest standard valence; the formula's
+    /// hydrogens decide how many rings and multiple bonds there are.
+    pub fn new(formula: &str) -> CompoundResult<Self> {
+        let mut heavy = Vec::new();
+        let mut hydrogens = 0;
+        for (atom, count) in parse_formula(formula)? {
+            if atom.get_element_num() == 1 {
+                hydrogens += count;
+            } else if Skeleton::valence(&atom) == 0 {
+                return Err(CompoundError::Parsing(format!(
+                    "{} has no standard valence",
+                    atom
+                )));
+            } else {
+                heavy.extend(std::iter::repeat_n(atom, count));
+            }
+        }
+        if heavy.is_empty() {
+            return Err(CompoundError::Parsing(format!(
+                "{} has no heavy atoms",
+                formula
+            )));
+        }
+        heavy.sort_by_key(|atom| atom.get_element_num());
+        let valences: usize = heavy
+            .iter()
+            .map(|atom| Skeleton::valence(atom) as usize)
+            .sum();
+        let shared = valences as i64 - hydrogens as i64;
+        let unsaturation = shared / 2 - (heavy.len() as i64 - 1);
+        if shared % 2 != 0 || unsaturation < 0 {
+            return Err(CompoundError::Parsing(format!(
+                "No compound has the formula {}",
+                formula
+            )));
+        }
+
+        // Every skeleton holds the first (lowest numbered) heavy atom, so
+        // growing them all from it reaches every isomer
+        let mut steps = vec![Step::AddAtom; heavy.len() - 1];
+        steps.extend(vec![Step::Unsaturate; unsaturation as usize]);
+        let root = Skeleton {
+            atoms: vec![heavy[0].clone()],
+            bonds: BTreeMap::new(),
+        };
+        Ok(Self {
+            heavy,
+            seen: vec![HashSet::new(); steps.len() + 1],
+            steps,
+            stack: vec![(0, root)],
+        })
+    }
+
+    /// Number of isomers, without building them
+    pub fn count_only(mut self) -> usize {
+        std::iter::from_fn(|| self.next_skeleton()).count()
+    }
+
+    fn next_skeleton(&mut self) -> Option<Skeleton> {
+        loop {
+            let (level, skeleton) = self.stack.pop()?;
+            let Some(&step) = self.steps.get(level) else {
+                return Some(skeleton);
+            };
+            let last = level + 1 == self.steps.len();
+            let children = skeleton.children(step, &self.heavy);
+            for child in children.into_iter().rev() {
+                // Aromatic rings count once among the isomers, but their
+                // Kekulé structures still grow differently
+                let key = if last {
+                    child.compound().canonical_key()
+                } else {
+                    child.compound().kekule_key()
+                };
+                if self.seen[level + 1].insert(key) {
+                    self.stack.push((level + 1, child));
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for Isomers {
+    type Item = Compound;
+
+    fn next(&mut self) -> Option<Compound> {
+        let skeleton = self.next_skeleton()?;
+        let isomer = CompoundBuilder::new()
+            .graph(skeleton.atoms.clone(), &skeleton.edges())
+            .and_then(|builder| builder.build())
+            .expect("Skeletons respect every atom's valence");
+        Some(isomer)
+    }
+
+    fn count(self) -> usize {
+        self.count_only()
+    }
+}
+
+/// Element counts of a molecular formula, in order of appearance
+fn parse_formula(formula: &str) -> CompoundResult<Vec<(Atom, usize)>> {
+    let digit = |c: char| match c {
+        '0'..='9' => c.to_digit(10),
+        '₀'..='₉' => Some(c as u32 - '₀' as u32),
+        _ => None,
+    };
+    let mut counts: Vec<(Atom, usize)> = Vec::new();
+    let mut chars = formula.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !c.is_ascii_uppercase() {
+            return Err(CompoundError::Parsing(format!(
+                "Unexpected '{}' in formula {}",
+                c, formula
+            )));
+        }
+        let mut symbol = c.to_string();
+        while let Some(&lower) = chars.peek().filter(|c| c.is_ascii_lowercase())
+        {
+            symbol.push(lower);
+            chars.next();
+        }
+        let atom = Atom::from_str(&symbol).ok_or_else(|| {
+            CompoundError::Parsing(format!("Unknown element {}", symbol))
+        })?;
+        let mut count = None;
+        while let Some(d) = chars.peek().and_then(|&c| digit(c)) {
+            count = Some(count.unwrap_or(0) * 10 + d as usize);
+            chars.next();
+        }
+        let count = count.unwrap_or(1);
+        match counts.iter_mut().find(|(a, _)| *a == atom) {
+            Some((_, existing)) => *existing += count,
+            None => counts.push((atom, count)),
+        }
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(formula: &str) -> usize {
+        Isomers::new(formula).expect("Valid formula").count_only()
+    }
+
+    #[test]
+    fn isomers_of_alkanes() {
+        assert_eq!(count("CH4"), 1);
+        assert_eq!(count("C4H10"), 2);
+        assert_eq!(count("C5H12"), 3);
+        assert_eq!(count("C₆H₁₄"), 5);
+        assert_eq!(count("C7H16"), 9);
+        assert_eq!(count("C8H18"), 18);
+    }
+
+    #[test]
+    fn isomers_are_built_lazily() {
+        let mut hexanes = Isomers::new("C6H14").expect("Valid formula");
+        let first = hexanes.next().expect("Hexane isomer");
+        assert_eq!(first.molecular_formula(), "C6H14");
+        // Only the branch leading to the first octane is grown so far
+        let mut octanes = Isomers::new("C8H18").expect("Valid formula");
+        octanes.next().expect("Octane isomer");
+        let reached = octanes.seen.last().expect("Last level").len();
+        assert!(reached < 18, "{} octanes reached", reached);
+        let names: HashSet<String> = std::iter::once(first)
+            .chain(hexanes)
+            .map(|isomer| isomer.iupac_name().expect("Named alkane"))
+            .collect();
+        assert_eq!(
+            names,
+            HashSet::from(
+                [
+                    "hexane",
+                    "2-methylpentane",
+                    "3-methylpentane",
+                    "2,2-dimethylbutane",
+                    "2,3-dimethylbutane"
+                ]
+                .map(String::from)
+            )
+        );
+    }
+
+    #[test]
+    fn isomers_with_heteroatoms_and_unsaturation() {
+        let bromides: HashSet<String> = Isomers::new("C4H9Br")
+            .expect("Valid formula")
+            .map(|isomer| isomer.iupac_name().expect("Named haloalkane"))
+            .collect();
+        assert_eq!(
+            bromides,
+            HashSet::from(
+                [
+                    "1-bromobutane",
+                    "2-bromobutane",
+                    "1-bromo-2-methylpropane",
+                    "2-bromo-2-methylpropane"
+                ]
+                .map(String::from)
+            )
+        );
+        // Propyne, allene and cyclopropene
+        assert_eq!(count("C3H4"), 3);
+        // Ethanol and dimethyl ether
+        assert_eq!(count("C2H6O"), 2);
+        assert_eq!(count("C4H8"), 5);
+        assert_eq!(
+            Isomers::new("C6H6")
+                .expect("Valid formula")
+                .filter(|isomer| isomer.is_aromatic().unwrap_or(false))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn isomers_reject_impossible_formulas() {
+        assert!(Isomers::new("C2H8").is_err());
+        assert!(Isomers::new("C2H5").is_err());
+        assert!(Isomers::new("H2").is_err());
+        assert!(Isomers::new("c2h6").is_err());
+    }
+}
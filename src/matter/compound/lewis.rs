@@ -0,0 +1,675 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use crate::matter::{
+    compound::{bond::BondOrder, Compound, CompoundError, CompoundResult},
+    element::Element,
+};
+
+/// Electron bookkeeping for a Compound: bond orders, lone pairs and the
+/// formal charge left on every atom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LewisStructure {
+    /// Keyed by atom indices, lowest index first
+    pub bonds: BTreeMap<(usize, usize), BondOrder>,
+    /// Non-bonding electron pairs, indexed by atom
+    pub lone_pairs: Vec<u8>,
+    /// Formal charge, indexed by atom
+    pub formal_charges: Vec<i8>,
+}
+
+impl LewisStructure {
+    /// Sum of the bond orders around atom `i`
+    pub fn bond_order_sum(&self, i: usize) -> u8 {
+        self.bonds
+            .iter()
+            .filter(|(&(a, b), _)| a == i || b == i)
+            .map(|(_, order)| order.as_u8())
+            .sum()
+    }
+
+    /// Shared and non-bonding electrons around atom `i`
+    pub fn electrons_around(&self, i: usize) -> u8 {
+        2 * self.lone_pairs[i] + 2 * self.bond_order_sum(i)
+    }
+
+    pub fn bond_order(&self, a: usize, b: usize) -> Option<BondOrder> {
+        self.bonds.get(&(a.min(b), a.max(b))).copied()
+    }
+
+    /// Sum of the absolute formal charges, which the chosen structure
+    /// minimizes.
+    pub fn formal_charge_magnitude(&self) -> u32 {
+        self.formal_charges
+            .iter()
+            .map(|c| c.unsigned_abs() as u32)
+            .sum()
+    }
+
+    /// Draws the structure on the Compound's grid of locations.
+    ///
+    /// Bonds are drawn as `-`, `=` and `≡` (`|`, `‖` and `⦀` vertically),
+    /// lone pairs as `:` beside an atom or `..` above and below it, and
+    /// formal charges follow the element symbol.
+    pub fn render_ascii(&self, compound: &Compound) -> CompoundResult<String> {
+        let locations = compound.locations();
+        if locations.len() != self.lone_pairs.len() || locations.is_empty() {
+            return Err(CompoundError::Lewis(
+                "Compound has no location layout to render".into(),
+            ));
+        }
+        let min_x = locations.iter().map(|l| l.x).min().unwrap();
+        let max_x = locations.iter().map(|l| l.x).max().unwrap();
+        let min_y = locations.iter().map(|l| l.y).min().unwrap();
+        let max_y = locations.iter().map(|l| l.y).max().unwrap();
+        let width = (max_x - min_x) as usize * X_SCALE + X_SCALE + 2;
+        let height = (max_y - min_y) as usize * Y_SCALE + 3;
+        let mut grid = vec![vec![' '; width]; height];
+        let cell = |i: usize| {
+            let loc = locations[i];
+            (
+                (max_y - loc.y) as usize * Y_SCALE + 1,
+                (loc.x - min_x) as usize * X_SCALE + 1,
+            )
+        };
+        let labels: Vec<Vec<char>> = compound
+            .atoms()
+            .iter()
+            .zip(self.formal_charges.iter())
+            .map(|(atom, &charge)| {
+                format!("{}{}", atom, charge_label(charge))
+                    .chars()
+                    .collect()
+            })
+            .collect();
+
+        for (&(a, b), order) in self.bonds.iter() {
+            let (a, b) = if locations[a].x <= locations[b].x {
+                (a, b)
+            } else {
+                (b, a)
+            };
+            let dx = locations[b].x - locations[a].x;
+            let dy = locations[b].y - locations[a].y;
+            if dx > 1 || dy.abs() > 1 || (dx == 0 && dy == 0) {
+                continue; // Only neighbouring grid cells can be joined
+            }
+            let (row, col) = cell(a);
+            if dy == 0 {
+                let (_, end) = cell(b);
+                grid[row][(col + labels[a].len() + 1)..(end - 1)]
+                    .fill(horizontal_symbol(*order));
+            } else if dx == 0 {
+                let mid = if dy > 0 { row - 1 } else { row + 1 };
+                grid[mid][col] = vertical_symbol(*order);
+            } else {
+                let mid = if dy > 0 { row - 1 } else { row + 1 };
+                grid[mid][col + X_SCALE / 2] = if dy > 0 { '/' } else { '\\' };
+            }
+        }
+
+        let neighbors = compound.adjacency();
+        for (i, label) in labels.iter().enumerate() {
+            let (row, col) = cell(i);
+            for (offset, c) in label.iter().enumerate() {
+                grid[row][col + offset] = *c;
+            }
+            let loc = locations[i];
+            let taken = |dx: i16, dy: i16| {
+                neighbors[i].iter().any(|&j| {
+                    let other = locations[j];
+                    (other.x - loc.x).signum() == dx
+                        && (other.y - loc.y).signum() == dy
+                })
+            };
+            let free = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .into_iter()
+                .filter(|&(dx, dy)| !taken(dx, dy));
+            for (dx, dy) in free.take(self.lone_pairs[i] as usize) {
+                match (dx, dy) {
+                    (1, _) => grid[row][col + label.len()] = ':',
+                    (-1, _) => grid[row][col - 1] = ':',
+                    (_, 1) => {
+                        grid[row - 1][col] = '.';
+                        grid[row - 1][col + 1] = '.';
+                    }
+                    _ => {
+                        grid[row + 1][col] = '.';
+                        grid[row + 1][col + 1] = '.';
+                    }
+                }
+            }
+        }
+
+        let lines: Vec<String> = grid
+            .into_iter()
+            .map(|row| row.into_iter().collect::<String>().trim_end().into())
+            .skip_while(|line: &String| line.is_empty())
+            .collect();
+        let end = lines.iter().rposition(|l| !l.is_empty()).unwrap_or(0);
+        let indent = lines
+            .iter()
+            .filter(|l| !l.is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        Ok(lines[..=end]
+            .iter()
+            .map(|l| l.get(indent..).unwrap_or(""))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+const X_SCALE: usize = 6;
+const Y_SCALE: usize = 2;
+
+fn charge_label(charge: i8) -> String {
+    match charge {
+        0 => String::new(),
+        1 => "+".into(),
+        -1 => "-".into(),
+        c if c > 0 => format!("{}+", c),
+        c => format!("{}-", -c),
+    }
+}
+
+fn horizontal_symbol(order: BondOrder) -> char {
+    match order {
+        BondOrder::Single => '-',
+        BondOrder::Double => '=',
+        BondOrder::Triple => '≡',
+        BondOrder::Aromatic => ':',
+    }
+}
+
+fn vertical_symbol(order: BondOrder) -> char {
+    match order {
+        BondOrder::Single => '|',
+        BondOrder::Double => '‖',
+        BondOrder::Triple => '⦀',
+        BondOrder::Aromatic => '¦',
+    }
+}
+
+/// Electrons an atom wants around it for a full shell (2 or 8). Atoms with
+/// fewer than four valence electrons (B, Be, ...) aren't held to an octet.
+pub(crate) fn octet_target(element: &Element) -> Option<u8> {
+    match (element.period(), element.valence_electrons()) {
+        (1, _) => Some(2),
+        (_, 4..=7) => Some(8),
+        _ => None,
+    }
+}
+
+/// Ranks structures: complete octets first, then the smallest formal
+/// charges, then negative charge on the more electronegative atoms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Score {
+    octet_deficit: u32,
+    formal_charge: u32,
+    electronegativity: f64,
+}
+
+impl Score {
+    pub(crate) fn of(structure: &LewisStructure, compound: &Compound) -> Self {
+        let atoms = compound.atoms();
+        Self {
+            octet_deficit: atoms
+                .iter()
+                .enumerate()
+                .filter_map(|(i, atom)| {
+                    let target = octet_target(atom.get_element())?;
+                    let around = structure.electrons_around(i);
+                    Some(target.saturating_sub(around) as u32)
+                })
+                .sum(),
+            formal_charge: structure.formal_charge_magnitude(),
+            electronegativity: structure
+                .formal_charges
+                .iter()
+                .zip(atoms.iter())
+                .map(|(&c, atom)| {
+                    c as f64 * atom.get_element().electronegativity()
+                })
+                .sum(),
+        }
+    }
+}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(
+            self.octet_deficit
+                .cmp(&other.octet_deficit)
+                .then(self.formal_charge.cmp(&other.formal_charge))
+                .then(
+                    self.electronegativity.total_cmp(&other.electronegativity),
+                ),
+        )
+    }
+}
+
+struct LewisSearch<'a> {
+    compound: &'a Compound,
+    valence: Vec<u8>,
+    octet: Vec<Option<u8>>,
+    capacity: Vec<u8>,
+    max_bonds: Vec<u8>,
+    electronegativity: Vec<f64>,
+    degree: Vec<u8>,
+    expandable: Vec<bool>,
+    edges: Vec<(usize, usize)>,
+    /// Recorded bond orders are kept as lower bounds
+    min_orders: Vec<u8>,
+    electrons: u16,
+    best: Option<(Score, LewisStructure)>,
+}
+
+impl<'a> LewisSearch<'a> {
+    fn new(compound: &'a Compound) -> CompoundResult<Self> {
+        let atoms = compound.atoms();
+        let (edges, min_orders): (Vec<_>, Vec<_>) = compound
+            .bond_orders()
+            .into_iter()
+            .map(|(edge, order)| (edge, order.as_u8()))
+            .unzip();
+        let mut degree = vec![0; atoms.len()];
+        for &(a, b) in edges.iter() {
+            degree[a] += 1;
+            degree[b] += 1;
+        }
+        let total: i32 = atoms
+            .iter()
+            .map(|a| a.get_element().valence_electrons() as i32)
+            .sum::<i32>()
+            - compound.charge() as i32;
+        let bonded = 2 * min_orders.iter().map(|&o| o as i32).sum::<i32>();
+        if total < bonded {
+            return Err(CompoundError::Lewis(format!(
+                "{} valence electrons cannot form {} bonds",
+                total,
+                edges.len()
+            )));
+        } else if total % 2 != 0 {
+            return Err(CompoundError::Lewis(
+                "Odd electron counts (radicals) aren't supported".into(),
+            ));
+        }
+        Ok(Self {
+            compound,
+            valence: atoms
+                .iter()
+                .map(|a| a.get_element().valence_electrons())
+                .collect(),
+            octet: atoms
+                .iter()
+                .map(|a| octet_target(a.get_element()))
+                .collect(),
+            capacity: atoms
+                .iter()
+                .map(|a| a.get_element().valence_shell_capacity())
+                .collect(),
+            max_bonds: atoms
+                .iter()
+                .map(|a| a.get_element().valence_shell_capacity() / 2)
+                .collect(),
+            electronegativity: atoms
+                .iter()
+                .map(|a| a.get_element().electronegativity())
+                .collect(),
+            expandable: atoms
+                .iter()
+                .map(|a| a.get_element().can_expand_octet())
+                .collect(),
+            degree,
+            edges,
+            min_orders,
+            electrons: total as u16,
+            best: None,
+        })
+    }
+
+    fn search(
+        &mut self,
+        k: usize,
+        orders: &mut Vec<u8>,
+        bond_sums: &mut Vec<u8>,
+        spare: u16,
+    ) {
+        if k == self.edges.len() {
+            self.evaluate(orders, bond_sums, spare);
+            return;
+        }
+        let (a, b) = self.edges[k];
+        let base = self.min_orders[k];
+        for extra in 0..=(3 - base) {
+            if extra > 0
+                && (bond_sums[a] + extra > self.max_bonds[a]
+                    || bond_sums[b] + extra > self.max_bonds[b]
+                    || 2 * extra as u16 > spare)
+            {
+                break;
+            }
+            orders.push(base + extra);
+            bond_sums[a] += extra;
+            bond_sums[b] += extra;
+            self.search(k + 1, orders, bond_sums, spare - 2 * extra as u16);
+            bond_sums[a] -= extra;
+            bond_sums[b] -= extra;
+            orders.pop();
+        }
+    }
+
+    /// Places the remaining electrons as lone pairs: terminal atoms
+    /// are completed first (most electronegative first), then leftovers go
+    /// to atoms which can expand their octet.
+    fn evaluate(&mut self, orders: &[u8], bond_sums: &[u8], spare: u16) {
+        let n = self.valence.len();
+        let mut pairs_left = spare / 2;
+        let mut lone_pairs = vec![0u8; n];
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&i, &j| {
+            (self.degree[i] > 1).cmp(&(self.degree[j] > 1)).then(
+                self.electronegativity[j].total_cmp(&self.electronegativity[i]),
+            )
+        });
+        let around = |i: usize, lone: &[u8]| 2 * (lone[i] + bond_sums[i]);
+        for &i in order.iter() {
+            let target = self.octet[i].unwrap_or(8);
+            while pairs_left > 0 && around(i, &lone_pairs) + 2 <= target {
+                lone_pairs[i] += 1;
+                pairs_left -= 1;
+            }
+        }
+        for &i in order.iter().rev() {
+            while pairs_left > 0
+                && self.expandable[i]
+                && around(i, &lone_pairs) + 2 <= self.capacity[i]
+            {
+                lone_pairs[i] += 1;
+                pairs_left -= 1;
+            }
+        }
+        if pairs_left > 0 {
+            return; // Nowhere to place the remaining electrons
+        }
+
+        let formal_charges: Vec<i8> = (0..n)
+            .map(|i| {
+                self.valence[i] as i8
+                    - 2 * lone_pairs[i] as i8
+                    - bond_sums[i] as i8
+            })
+            .collect();
+        let bonds = self
+            .edges
+            .iter()
+            .zip(orders.iter())
+            .map(|(&edge, &order)| {
+                (edge, BondOrder::from_u8(order).expect("Orders are 1-3"))
+            })
+            .collect();
+        let structure = LewisStructure {
+            bonds,
+            lone_pairs,
+            formal_charges,
+        };
+        let score = Score::of(&structure, self.compound);
+        if matches!(&self.best, Some((best, _)) if *best <= score) {
+            return;
+        }
+        self.best = Some((score, structure));
+    }
+}
+
+impl LewisStructure {
+    /// Reads the structure off the bond orders and charges already recorded
+    /// on the Compound, without choosing new ones.
+    pub fn from_compound(compound: &Compound) -> CompoundResult<Self> {
+        let bonds = compound.bond_orders();
+        let mut bond_sums: Vec<i8> = (0..compound.atom_count())
+            .map(|i| compound.implicit_hydrogens(i) as i8)
+            .collect();
+        for (&(a, b), order) in bonds.iter() {
+            bond_sums[a] += order.as_u8() as i8;
+            bond_sums[b] += order.as_u8() as i8;
+        }
+        let mut lone_pairs = Vec::new();
+        for (i, atom) in compound.atoms().iter().enumerate() {
+            let element = atom.get_element();
+            let lone = element.valence_electrons() as i8
+                - atom.charge()
+                - bond_sums[i];
+            if lone < 0 || lone % 2 != 0 {
+                return Err(CompoundError::Lewis(format!(
+                    "Atom {} ({}) has {} non-bonding electrons",
+                    i, atom, lone
+                )));
+            } else if lone + 2 * bond_sums[i]
+                > element.valence_shell_capacity() as i8
+            {
+                return Err(CompoundError::Lewis(format!(
+                    "Atom {} ({}) exceeds its valence shell",
+                    i, atom
+                )));
+            }
+            lone_pairs.push(lone as u8 / 2);
+        }
+        Ok(Self {
+            bonds,
+            lone_pairs,
+            formal_charges: compound
+                .atoms()
+                .iter()
+                .map(|a| a.charge())
+                .collect(),
+        })
+    }
+}
+
+impl Compound {
+    /// Computes the Lewis structure minimizing formal charge.
+    ///
+    /// Valence electrons (less the net charge) are spread over the bond
+    /// graph; multiple bonds are formed where they complete octets or
+    /// reduce formal charges, and period 3+ atoms may expand their octet.
+    /// Bond orders already recorded on the Compound are never lowered.
+    /// Implicit hydrogens are accounted for but left out of the structure.
+    pub fn lewis_structure(&self) -> CompoundResult<LewisStructure> {
+        if self.has_implicit_hydrogens() {
+            // Explicit hydrogens are appended, so indices up to `n` agree
+            let n = self.atom_count();
+            let mut structure =
+                self.with_explicit_hydrogens().lewis_structure()?;
+            structure.bonds.retain(|&(_, b), _| b < n);
+            structure.lone_pairs.truncate(n);
+            structure.formal_charges.truncate(n);
+            return Ok(structure);
+        }
+        let mut search = LewisSearch::new(self)?;
+        let mut bond_sums = vec![0u8; self.atom_count()];
+        for (&(a, b), &order) in
+            search.edges.iter().zip(search.min_orders.iter())
+        {
+            bond_sums[a] += order;
+            bond_sums[b] += order;
+        }
+        if let Some(i) =
+            (0..bond_sums.len()).find(|&i| bond_sums[i] > search.max_bonds[i])
+        {
+            return Err(CompoundError::Lewis(format!(
+                "Atom {} ({}) has too many bonds",
+                i,
+                self.atoms()[i]
+            )));
+        }
+        let bonded: u16 = bond_sums.iter().map(|&s| s as u16).sum();
+        let spare = search.electrons - bonded;
+        search.search(0, &mut Vec::new(), &mut bond_sums, spare);
+        search.best.map(|(_, structure)| structure).ok_or_else(|| {
+            CompoundError::Lewis(
+                "No arrangement of the valence electrons fits".into(),
+            )
+        })
+    }
+
+    /// Returns a copy carrying the structure's bond orders, with each atom
+    /// charged to its formal charge.
+    pub fn with_lewis_structure(&self, structure: &LewisStructure) -> Self {
+        let mut compound = self.clone();
+        for (&(a, b), &order) in structure.bonds.iter() {
+            compound.set_bond_order(a, b, order);
+        }
+        for (atom, &charge) in compound
+            .atoms
+            .iter_mut()
+            .zip(structure.formal_charges.iter())
+        {
+            *atom = atom.ion(charge);
+        }
+        compound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::matter::{
+        atom::Atom,
+        compound::{builder::CompoundBuilder, Location},
+    };
+
+    use super::*;
+
+    /// Central atom with terminal ligands placed around it
+    fn star(center: Atom, ligands: Vec<Atom>) -> Compound {
+        let spots = [
+            (-1, 0),
+            (1, 0),
+            (0, 1),
+            (0, -1),
+            (-1, 1),
+            (1, 1),
+            (-1, -1),
+            (1, -1),
+        ];
+        let mut atoms = vec![center];
+        let mut locations = vec![Location::new(0, 0)];
+        for (ligand, (x, y)) in ligands.into_iter().zip(spots) {
+            atoms.push(ligand);
+            locations.push(Location::new(x, y));
+        }
+        let location_to_idx =
+            locations.iter().enumerate().map(|(i, &l)| (l, i)).collect();
+        let side_chains = HashMap::from([(0, (1..atoms.len()).collect())]);
+        Compound::new(
+            atoms,
+            locations,
+            location_to_idx,
+            vec![0],
+            side_chains,
+            HashMap::new(),
+        )
+    }
+
+    fn oxygen() -> Atom {
+        Atom::new_unchecked(8)
+    }
+
+    #[test]
+    fn lewis_hexane_has_no_lone_pairs() {
+        let hexane = CompoundBuilder::new()
+            .linear_chain(6)
+            .expect("Linear chain expected")
+            .build()
+            .expect("Valid compound expected");
+        let lewis = hexane.lewis_structure().expect("Lewis structure");
+        assert!(lewis.lone_pairs.iter().all(|&lp| lp == 0));
+        assert!(lewis.formal_charges.iter().all(|&c| c == 0));
+        assert!(lewis.bonds.values().all(|&o| o == BondOrder::Single));
+    }
+
+    #[test]
+    fn lewis_carbon_dioxide_double_bonds() {
+        let co2 = star(Atom::carbon(), vec![oxygen(), oxygen()]);
+        let lewis = co2.lewis_structure().expect("Lewis structure");
+        assert_eq!(lewis.bond_order(0, 1), Some(BondOrder::Double));
+        assert_eq!(lewis.bond_order(0, 2), Some(BondOrder::Double));
+        assert_eq!(lewis.lone_pairs, vec![0, 2, 2]);
+        assert_eq!(lewis.formal_charge_magnitude(), 0);
+    }
+
+    #[test]
+    fn lewis_carbon_monoxide_completes_octets() {
+        let co = star(Atom::carbon(), vec![oxygen()]);
+        let lewis = co.lewis_structure().expect("Lewis structure");
+        assert_eq!(lewis.bond_order(0, 1), Some(BondOrder::Triple));
+        assert_eq!(lewis.formal_charges, vec![-1, 1]);
+        assert_eq!(lewis.electrons_around(0), 8);
+        assert_eq!(lewis.electrons_around(1), 8);
+    }
+
+    #[test]
+    fn lewis_nitrate_charges() {
+        let nitrate =
+            star(Atom::nitrogen(), vec![oxygen().ion(-1), oxygen(), oxygen()]);
+        let lewis = nitrate.lewis_structure().expect("Lewis structure");
+        assert_eq!(lewis.formal_charges[0], 1);
+        assert_eq!(lewis.formal_charges[1..].iter().sum::<i8>(), -2);
+        assert_eq!(
+            lewis
+                .bonds
+                .values()
+                .filter(|&&o| o == BondOrder::Double)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn lewis_sulfate_expands_octet() {
+        let sulfate = star(
+            Atom::new_unchecked(16).ion(-2),
+            vec![oxygen(), oxygen(), oxygen(), oxygen()],
+        );
+        let lewis = sulfate.lewis_structure().expect("Lewis structure");
+        assert_eq!(lewis.formal_charges[0], 0);
+        assert_eq!(lewis.electrons_around(0), 12);
+        assert_eq!(lewis.formal_charge_magnitude(), 2);
+
+        let sf6 =
+            star(Atom::new_unchecked(16), vec![Atom::new_unchecked(9); 6]);
+        let lewis = sf6.lewis_structure().expect("Lewis structure");
+        assert_eq!(lewis.electrons_around(0), 12);
+        assert!(lewis.lone_pairs[1..].iter().all(|&lp| lp == 3));
+    }
+
+    #[test]
+    fn lewis_rejects_radicals() {
+        let no = star(Atom::nitrogen(), vec![oxygen()]);
+        assert!(no.lewis_structure().is_err());
+    }
+
+    #[test]
+    fn lewis_render_water() {
+        let water = star(oxygen(), vec![Atom::hydrogen(), Atom::hydrogen()]);
+        let rendered = water
+            .lewis_structure()
+            .expect("Lewis structure")
+            .render_ascii(&water)
+            .expect("Rendering");
+        assert_eq!(rendered, "      ..\nH --- O --- H\n      ..");
+    }
+
+    #[test]
+    fn lewis_render_hydroxide_charge() {
+        let hydroxide = star(oxygen().ion(-1), vec![Atom::hydrogen()]);
+        let rendered = hydroxide
+            .lewis_structure()
+            .expect("Lewis structure")
+            .render_ascii(&hydroxide)
+            .expect("Rendering");
+        assert_eq!(rendered, "      ..\nH --- O-:\n      ..");
+    }
+}
@@ -0,0 +1,756 @@
+use std::fmt;
+
+use crate::matter::compound::{
+    degree::CarbonDegree,
+    named_reaction::{NamedReaction, Prediction, Reagent},
+    smarts::Smarts,
+    Compound, CompoundError, CompoundResult,
+};
+
+/// Substitution and elimination mechanisms of alkyl halides
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum Mechanism {
+    #[strum(serialize = "SN1")]
+    Sn1,
+    #[strum(serialize = "SN2")]
+    Sn2,
+    #[strum(serialize = "E1")]
+    E1,
+    #[strum(serialize = "E2")]
+    E2,
+}
+
+impl Mechanism {
+    fn is_elimination(&self) -> bool {
+        matches!(self, Self::E1 | Self::E2)
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, strum_macros::Display,
+)]
+pub enum Strength {
+    #[strum(serialize = "weak")]
+    Weak,
+    #[strum(serialize = "strong")]
+    Strong,
+}
+
+/// How a reagent behaves towards an alkyl halide
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Nucleophile {
+    pub nucleophilicity: Strength,
+    pub basicity: Strength,
+    /// Too hindered to reach a carbon, though not a proton
+    pub bulky: bool,
+}
+
+impl Nucleophile {
+    /// Behaviour of the reagent; `None` for reagents which are neither
+    /// nucleophiles nor bases
+    pub fn of(reagent: Reagent) -> Option<Self> {
+        use Strength::*;
+        let (nucleophilicity, basicity) = match reagent {
+            Reagent::Hydroxide | Reagent::Alkoxide(_) => (Strong, Strong),
+            Reagent::Cyanide => (Strong, Weak),
+            Reagent::Water => (Weak, Weak),
+            _ => return None,
+        };
+        let bulky = reagent.is_bulky_base();
+        Some(Self {
+            nucleophilicity: match bulky {
+                true => Weak,
+                false => nucleophilicity,
+            },
+            basicity,
+            bulky,
+        })
+    }
+}
+
+impl fmt::Display for Nucleophile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} nucleophile, {} base",
+            self.nucleophilicity, self.basicity
+        )?;
+        if self.bulky {
+            f.write_str(", bulky")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum Solvent {
+    /// Water, alcohols, carboxylic acids
+    #[strum(serialize = "polar protic")]
+    PolarProtic,
+    /// DMSO, DMF, acetone, acetonitrile
+    #[strum(serialize = "polar aprotic")]
+    PolarAprotic,
+    /// Hexane, benzene
+    #[strum(serialize = "nonpolar")]
+    Nonpolar,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum Temperature {
+    #[strum(serialize = "room temperature")]
+    Room,
+    #[strum(serialize = "heat")]
+    Heated,
+}
+
+/// One step of the substitution/elimination flowchart
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reason {
+    /// Degree of the carbon bearing the leaving group
+    Substrate(CarbonDegree),
+    Reagent(Nucleophile),
+    /// Methyl and 1° carbocations don't form
+    UnstableCarbocation(CarbonDegree),
+    /// Backside attack on a 3° carbon is blocked
+    HinderedCarbon,
+    /// Backside attack is blocked by a 4° neighbouring carbon
+    HinderedNeighbor,
+    /// No hydrogen next to the carbon bearing the leaving group
+    NoBetaHydrogen,
+    /// The reagent pulls off a β-hydrogen rather than reach the carbon
+    BulkyBase,
+    /// A strong base on a 2° or 3° carbon eliminates more than it
+    /// substitutes
+    StrongBase,
+    /// A strong nucleophile that's a weak base substitutes
+    WeakBase,
+    /// A weak nucleophile waits for a carbocation
+    WeakNucleophile,
+    /// Hydrogen bonds from a protic solvent cage the nucleophile
+    SolvatedNucleophile,
+    Solvent(Solvent),
+    Temperature(Temperature),
+    /// No mechanism is fast enough
+    NoReaction,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Substrate(degree) => {
+                write!(f, "The leaving group is on a {} carbon", degree)
+            }
+            Self::Reagent(nucleophile) => {
+                write!(f, "The reagent is a {}", nucleophile)
+            }
+            Self::UnstableCarbocation(degree) => write!(
+                f,
+                "A {} carbocation is too unstable to form, ruling out SN1 \
+                 and E1",
+                degree
+            ),
+            Self::HinderedCarbon => f.write_str(
+                "A 3° carbon is too hindered for backside attack, ruling \
+                 out SN2",
+            ),
+            Self::HinderedNeighbor => f.write_str(
+                "The 4° neighbouring carbon blocks backside attack, ruling \
+                 out SN2",
+            ),
+            Self::NoBetaHydrogen => f.write_str(
+                "There's no β-hydrogen to remove, ruling out E1 and E2",
+            ),
+            Self::BulkyBase => f.write_str(
+                "A bulky base removes an exposed β-hydrogen instead of \
+                 attacking carbon: E2",
+            ),
+            Self::StrongBase => f.write_str(
+                "A strong base on a hindered carbon favours E2 over SN2",
+            ),
+            Self::WeakBase => f.write_str(
+                "A strong nucleophile that's a weak base substitutes rather \
+                 than eliminates",
+            ),
+            Self::WeakNucleophile => f.write_str(
+                "A weak nucleophile waits for the leaving group to leave: \
+                 SN1 and E1",
+            ),
+            Self::SolvatedNucleophile => f.write_str(
+                "A polar protic solvent cages the nucleophile, slowing SN2 \
+                 more than E2",
+            ),
+            Self::Solvent(Solvent::PolarProtic) => f.write_str(
+                "A polar protic solvent stabilises the carbocation and \
+                 leaving group, favouring SN1 and E1",
+            ),
+            Self::Solvent(Solvent::PolarAprotic) => f.write_str(
+                "A polar aprotic solvent leaves the nucleophile unsolvated, \
+                 favouring SN2",
+            ),
+            Self::Solvent(Solvent::Nonpolar) => f.write_str(
+                "A nonpolar solvent can't stabilise ions, so the leaving \
+                 group won't leave on its own",
+            ),
+            Self::Temperature(Temperature::Heated) => {
+                f.write_str("Heat favours elimination")
+            }
+            Self::Temperature(Temperature::Room) => {
+                f.write_str("Without heat, substitution wins over elimination")
+            }
+            Self::NoReaction => f.write_str(
+                "No mechanism is fast enough under these conditions",
+            ),
+        }
+    }
+}
+
+/// Outcome of the flowchart for an alkyl halide and its conditions
+#[derive(Clone, Debug)]
+pub struct MechanismPrediction {
+    /// Dominant mechanisms
+    pub major: Vec<Mechanism>,
+    /// Mechanisms competing to a lesser extent
+    pub minor: Vec<Mechanism>,
+    /// The flowchart's steps, in the order they were taken
+    pub reasons: Vec<Reason>,
+    /// Products of every mechanism, those of minor mechanisms never being
+    /// major
+    pub products: Vec<Prediction>,
+}
+
+/// The alkyl halide's carbon as the flowchart sees it
+#[derive(Clone, Copy, Debug)]
+struct Substrate {
+    degree: CarbonDegree,
+    beta_hydrogens: bool,
+    /// A 4° carbon next to the reacting one (neopentyl halides)
+    beta_branched: bool,
+}
+
+impl Substrate {
+    fn of(compound: &Compound) -> CompoundResult<Self> {
+        let pattern = Smarts::parse("[CX4][Cl,Br,I]")?;
+        let matches = compound.smarts_matches(&pattern)?;
+        let carbon = match matches.first() {
+            Some(mapping) if matches.len() == 1 => mapping[&0],
+            _ => {
+                return Err(CompoundError::Reaction(
+                    "Expected an alkyl halide with one leaving group"
+                        .to_string(),
+                ))
+            }
+        };
+        let degree = compound.carbon_degree(carbon).expect("Matched a carbon");
+        let neighbors: Vec<usize> = compound
+            .neighbors(carbon)
+            .into_iter()
+            .filter(|&j| compound.carbon_degree(j).is_some())
+            .collect();
+        Ok(Self {
+            degree,
+            beta_hydrogens: neighbors
+                .iter()
+                .any(|&j| compound.hydrogen_count(j) > 0),
+            beta_branched: neighbors.iter().any(|&j| {
+                compound.carbon_degree(j) == Some(CarbonDegree::Quaternary)
+            }),
+        })
+    }
+}
+
+/// The substitution/elimination flowchart: major and minor mechanisms,
+/// and the reasons for them
+fn flowchart(
+    substrate: Substrate,
+    nucleophile: Nucleophile,
+    solvent: Solvent,
+    temperature: Temperature,
+) -> (Vec<Mechanism>, Vec<Mechanism>, Vec<Reason>) {
+    use Mechanism::*;
+    let mut reasons = vec![
+        Reason::Substrate(substrate.degree),
+        Reason::Reagent(nucleophile),
+    ];
+    let strong_nucleophile = nucleophile.nucleophilicity == Strength::Strong;
+    let strong_base = nucleophile.basicity == Strength::Strong;
+    // Conditions are only given as reasons where they back the outcome
+    let aprotic = |reasons: &mut Vec<Reason>| {
+        if solvent == Solvent::PolarAprotic {
+            reasons.push(Reason::Solvent(solvent));
+        }
+    };
+    let heated = |reasons: &mut Vec<Reason>| {
+        if temperature == Temperature::Heated && substrate.beta_hydrogens {
+            reasons.push(Reason::Temperature(temperature));
+        }
+    };
+    // Carbocation pathways, when the solvent lets the leaving group leave
+    let ionization = |reasons: &mut Vec<Reason>| {
+        reasons.push(Reason::WeakNucleophile);
+        if solvent != Solvent::PolarProtic {
+            if solvent == Solvent::Nonpolar {
+                reasons.push(Reason::Solvent(solvent));
+            }
+            return (vec![], vec![]);
+        }
+        reasons.push(Reason::Solvent(solvent));
+        reasons.push(Reason::Temperature(temperature));
+        match temperature {
+            Temperature::Heated => (vec![E1], vec![Sn1]),
+            Temperature::Room => (vec![Sn1], vec![E1]),
+        }
+    };
+    let (mut major, mut minor) = match substrate.degree {
+        CarbonDegree::Methyl | CarbonDegree::Primary => {
+            reasons.push(Reason::UnstableCarbocation(substrate.degree));
+            if substrate.beta_branched {
+                reasons.push(Reason::HinderedNeighbor);
+                (vec![], vec![])
+            } else if nucleophile.bulky {
+                reasons.push(Reason::BulkyBase);
+                heated(&mut reasons);
+                (vec![E2], vec![Sn2])
+            } else if strong_nucleophile {
+                aprotic(&mut reasons);
+                match strong_base {
+                    true => {
+                        if temperature == Temperature::Room {
+                            reasons.push(Reason::Temperature(temperature));
+                        }
+                        (vec![Sn2], vec![E2])
+                    }
+                    false => (vec![Sn2], vec![]),
+                }
+            } else {
+                (vec![], vec![])
+            }
+        }
+        CarbonDegree::Secondary => {
+            if nucleophile.bulky {
+                reasons.push(Reason::BulkyBase);
+                heated(&mut reasons);
+                (vec![E2], vec![])
+            } else if strong_base {
+                reasons.push(Reason::StrongBase);
+                if solvent == Solvent::PolarProtic {
+                    reasons.push(Reason::SolvatedNucleophile);
+                }
+                heated(&mut reasons);
+                (vec![E2], vec![Sn2])
+            } else if strong_nucleophile {
+                reasons.push(Reason::WeakBase);
+                aprotic(&mut reasons);
+                (vec![Sn2], vec![])
+            } else {
+                ionization(&mut reasons)
+            }
+        }
+        CarbonDegree::Tertiary | CarbonDegree::Quaternary => {
+            reasons.push(Reason::HinderedCarbon);
+            if nucleophile.bulky {
+                reasons.push(Reason::BulkyBase);
+                heated(&mut reasons);
+                (vec![E2], vec![])
+            } else if strong_base {
+                reasons.push(Reason::StrongBase);
+                heated(&mut reasons);
+                (vec![E2], vec![])
+            } else {
+                ionization(&mut reasons)
+            }
+        }
+    };
+    if !substrate.beta_hydrogens {
+        reasons.push(Reason::NoBetaHydrogen);
+        major.retain(|m| !m.is_elimination());
+        minor.retain(|m| !m.is_elimination());
+        if major.is_empty() {
+            major = std::mem::take(&mut minor);
+        }
+    }
+    if major.is_empty() {
+        reasons.push(Reason::NoReaction);
+    }
+    (major, minor, reasons)
+}
+
+/// Predicts which of SN1, SN2, E1 and E2 an alkyl halide undergoes with
+/// the nucleophile or base, and the products of each, following the
+/// flowchart: substrate degree first, then the reagent's nucleophilicity,
+/// basicity and bulk, then solvent and temperature.
+pub fn predict_mechanism(
+    substrate: &Compound,
+    reagent: Reagent,
+    solvent: Solvent,
+    temperature: Temperature,
+) -> CompoundResult<MechanismPrediction> {
+    let nucleophile = Nucleophile::of(reagent).ok_or_else(|| {
+        CompoundError::Reaction(format!(
+            "{:?} is neither a nucleophile nor a base",
+            reagent
+        ))
+    })?;
+    let (major, minor, reasons) =
+        flowchart(Substrate::of(substrate)?, nucleophile, solvent, temperature);
+    let reagent_compound = reagent.compound();
+    let mut molecules = vec![substrate.clone()];
+    molecules.extend(reagent_compound.clone());
+    let mut products = Vec::new();
+    for (mechanism, is_major) in major
+        .iter()
+        .map(|&m| (m, true))
+        .chain(minor.iter().map(|&m| (m, false)))
+    {
+        let reaction = match mechanism {
+            Mechanism::Sn1 => NamedReaction::Sn1,
+            Mechanism::Sn2 => NamedReaction::Sn2,
+            Mechanism::E1 => NamedReaction::E1,
+            Mechanism::E2 if nucleophile.bulky => NamedReaction::BulkyBaseE2,
+            Mechanism::E2 => NamedReaction::E2,
+        };
+        // The flowchart has settled the mechanism, whatever the degree
+        let templates = reaction.any_degree_templates(&[reagent]);
+        for mut prediction in reaction.run_templates(&templates, &molecules)? {
+            prediction.major &= is_major;
+            products.push(prediction);
+        }
+    }
+    Ok(MechanismPrediction {
+        major,
+        minor,
+        reasons,
+        products,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::compound::{
+        builder::{BuildStep, CompoundBuilder},
+        stereo::CipDescriptor,
+        stereoisomers::StereoRelationship,
+        substituent::AlkylGroup,
+    };
+
+    fn build(step: BuildStep) -> Compound {
+        step(&mut CompoundBuilder::new())
+            .and_then(|b| b.build())
+            .expect("Valid compound expected")
+    }
+
+    fn major_names(prediction: &MechanismPrediction) -> Vec<String> {
+        prediction
+            .products
+            .iter()
+            .filter(|p| p.major)
+            .map(|p| p.products[0].iupac_name().expect("Name expected"))
+            .collect()
+    }
+
+    #[test]
+    fn mechanism_flowchart() {
+        use Mechanism::*;
+        let ethoxide = Reagent::Alkoxide(AlkylGroup::Ethyl);
+        let tert_butoxide = Reagent::Alkoxide(AlkylGroup::TertButyl);
+        let aprotic = Solvent::PolarAprotic;
+        let protic = Solvent::PolarProtic;
+        let room = Temperature::Room;
+        let mechanisms = |step: BuildStep, reagent, solvent, temperature| {
+            let prediction =
+                predict_mechanism(&build(step), reagent, solvent, temperature)
+                    .expect("Prediction expected");
+            (prediction.major, prediction.minor)
+        };
+        let primary: BuildStep = |b| b.linear_chain(3)?.brominate(1);
+        let secondary: BuildStep = |b| b.linear_chain(3)?.brominate(2);
+        let tertiary: BuildStep = |b| {
+            b.linear_chain(3)?
+                .brominate(2)?
+                .add_alkyl(2, AlkylGroup::Methyl)
+        };
+        assert_eq!(
+            mechanisms(primary, ethoxide, aprotic, room),
+            (vec![Sn2], vec![E2])
+        );
+        assert_eq!(
+            mechanisms(primary, tert_butoxide, aprotic, room),
+            (vec![E2], vec![Sn2])
+        );
+        assert_eq!(
+            mechanisms(primary, Reagent::Water, protic, room),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            mechanisms(secondary, ethoxide, protic, room),
+            (vec![E2], vec![Sn2])
+        );
+        assert_eq!(
+            mechanisms(secondary, Reagent::Cyanide, aprotic, room),
+            (vec![Sn2], vec![])
+        );
+        assert_eq!(
+            mechanisms(tertiary, ethoxide, protic, room),
+            (vec![E2], vec![])
+        );
+        assert_eq!(
+            mechanisms(tertiary, Reagent::Water, protic, room),
+            (vec![Sn1], vec![E1])
+        );
+        assert_eq!(
+            mechanisms(tertiary, Reagent::Water, protic, Temperature::Heated),
+            (vec![E1], vec![Sn1])
+        );
+        assert_eq!(
+            mechanisms(tertiary, Reagent::Water, Solvent::Nonpolar, room),
+            (vec![], vec![])
+        );
+        // Nothing to eliminate: the bulky base has to substitute
+        assert_eq!(
+            mechanisms(
+                |b| b.linear_chain(1)?.brominate(1),
+                tert_butoxide,
+                aprotic,
+                room
+            ),
+            (vec![Sn2], vec![])
+        );
+    }
+
+    #[test]
+    fn mechanism_reasons() {
+        let prediction = predict_mechanism(
+            &build(|b| {
+                b.linear_chain(3)?
+                    .brominate(2)?
+                    .add_alkyl(2, AlkylGroup::Methyl)
+            }),
+            Reagent::Water,
+            Solvent::PolarProtic,
+            Temperature::Heated,
+        )
+        .expect("Prediction expected");
+        assert_eq!(
+            prediction.reasons,
+            vec![
+                Reason::Substrate(CarbonDegree::Tertiary),
+                Reason::Reagent(Nucleophile {
+                    nucleophilicity: Strength::Weak,
+                    basicity: Strength::Weak,
+                    bulky: false,
+                }),
+                Reason::HinderedCarbon,
+                Reason::WeakNucleophile,
+                Reason::Solvent(Solvent::PolarProtic),
+                Reason::Temperature(Temperature::Heated),
+            ]
+        );
+        assert_eq!(
+            prediction.reasons[1].to_string(),
+            "The reagent is a weak nucleophile, weak base"
+        );
+        assert_eq!(major_names(&prediction), vec!["2-methylpropene"]);
+
+        let neopentyl = build(|b| {
+            b.linear_chain(3)?
+                .brominate(1)?
+                .add_alkyl(2, AlkylGroup::Methyl)?
+                .add_alkyl(2, AlkylGroup::Methyl)
+        });
+        let prediction = predict_mechanism(
+            &neopentyl,
+            Reagent::Hydroxide,
+            Solvent::PolarAprotic,
+            Temperature::Room,
+        )
+        .expect("Prediction expected");
+        assert!(prediction.products.is_empty());
+        assert_eq!(
+            &prediction.reasons[2..],
+            &[
+                Reason::UnstableCarbocation(CarbonDegree::Primary),
+                Reason::HinderedNeighbor,
+                Reason::NoBetaHydrogen,
+                Reason::NoReaction,
+            ]
+        );
+        assert!(predict_mechanism(
+            &build(|b| b.linear_chain(3)),
+            Reagent::Hydroxide,
+            Solvent::PolarAprotic,
+            Temperature::Room,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn mechanism_conditions_back_the_outcome() {
+        use Mechanism::*;
+        let ethoxide = Reagent::Alkoxide(AlkylGroup::Ethyl);
+        let reasons = |step: BuildStep, reagent, solvent, temperature| {
+            predict_mechanism(&build(step), reagent, solvent, temperature)
+                .expect("Prediction expected")
+                .reasons
+        };
+        let primary: BuildStep = |b| b.linear_chain(3)?.brominate(1);
+        let secondary: BuildStep = |b| b.linear_chain(3)?.brominate(2);
+        let protic = Solvent::PolarProtic;
+        let aprotic = Solvent::PolarAprotic;
+        let (room, heat) = (Temperature::Room, Temperature::Heated);
+
+        // SN2 on a primary carbon: an aprotic solvent and room temperature
+        // favour it, a protic solvent and heat don't
+        let solvent = Reason::Solvent(aprotic);
+        let cool = Reason::Temperature(room);
+        assert!(reasons(primary, ethoxide, aprotic, room).contains(&solvent));
+        assert!(!reasons(primary, ethoxide, protic, room)
+            .iter()
+            .any(|r| matches!(r, Reason::Solvent(_))));
+        assert!(reasons(primary, ethoxide, protic, room).contains(&cool));
+        assert!(!reasons(primary, ethoxide, aprotic, heat)
+            .iter()
+            .any(|r| matches!(r, Reason::Temperature(_))));
+
+        // E2 on a secondary carbon: a protic solvent and heat favour it
+        let caged = Reason::SolvatedNucleophile;
+        let hot = Reason::Temperature(heat);
+        let favoured = reasons(secondary, ethoxide, protic, heat);
+        assert!(favoured.contains(&caged) && favoured.contains(&hot));
+        let neutral = reasons(secondary, ethoxide, aprotic, room);
+        assert!(!neutral.contains(&caged) && !neutral.contains(&hot));
+
+        // No reason under any conditions argues against the outcome
+        let substrates: [BuildStep; 4] = [
+            |b| b.linear_chain(1)?.brominate(1),
+            primary,
+            secondary,
+            |b| {
+                b.linear_chain(3)?
+                    .brominate(2)?
+                    .add_alkyl(2, AlkylGroup::Methyl)
+            },
+        ];
+        let reagents = [
+            ethoxide,
+            Reagent::Alkoxide(AlkylGroup::TertButyl),
+            Reagent::Cyanide,
+            Reagent::Water,
+        ];
+        let solvents = [protic, aprotic, Solvent::Nonpolar];
+        for step in substrates {
+            for reagent in reagents {
+                for solvent in solvents {
+                    for temperature in [room, heat] {
+                        let prediction = predict_mechanism(
+                            &build(step),
+                            reagent,
+                            solvent,
+                            temperature,
+                        )
+                        .expect("Prediction expected");
+                        let major = |m: &[Mechanism]| {
+                            m.iter().any(|x| prediction.major.contains(x))
+                        };
+                        for reason in prediction.reasons.iter() {
+                            let backed = match reason {
+                                Reason::Solvent(Solvent::PolarProtic) => {
+                                    major(&[Sn1, E1])
+                                }
+                                Reason::Solvent(Solvent::PolarAprotic) => {
+                                    major(&[Sn2])
+                                }
+                                Reason::Solvent(Solvent::Nonpolar) => {
+                                    prediction.major.is_empty()
+                                }
+                                Reason::SolvatedNucleophile
+                                | Reason::Temperature(Temperature::Heated) => {
+                                    major(&[E1, E2])
+                                }
+                                Reason::Temperature(Temperature::Room) => {
+                                    major(&[Sn1, Sn2])
+                                }
+                                _ => true,
+                            };
+                            assert!(
+                                backed,
+                                "{} contradicts {:?}",
+                                reason, prediction.major
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mechanism_products() {
+        let bromobutane = |descriptor| {
+            CompoundBuilder::new()
+                .linear_chain(4)
+                .and_then(|b| b.brominate(2))
+                .and_then(|b| b.stereocenter(2, descriptor))
+                .and_then(|b| b.build())
+                .expect("Valid compound expected")
+        };
+        // SN2 inverts the stereocenter
+        let sn2 = predict_mechanism(
+            &bromobutane(CipDescriptor::S),
+            Reagent::Cyanide,
+            Solvent::PolarAprotic,
+            Temperature::Room,
+        )
+        .expect("Prediction expected");
+        let [nitrile] = &sn2.products[..] else {
+            panic!("One product set expected");
+        };
+        assert_eq!(
+            nitrile.products[0]
+                .cip_descriptors()
+                .into_values()
+                .collect::<Vec<_>>(),
+            vec![CipDescriptor::R]
+        );
+
+        // E2 dominates, the SN2 alcohol only forming in smaller amounts
+        let e2 = predict_mechanism(
+            &bromobutane(CipDescriptor::R),
+            Reagent::Hydroxide,
+            Solvent::PolarProtic,
+            Temperature::Heated,
+        )
+        .expect("Prediction expected");
+        assert_eq!(major_names(&e2), vec!["(E)-but-2-ene"]);
+        let alcohol = e2
+            .products
+            .iter()
+            .find(|p| p.reaction == NamedReaction::Sn2)
+            .expect("SN2 product expected");
+        assert!(!alcohol.major);
+
+        // SN1 through the planar carbocation gives both enantiomers
+        let tertiary = build(|b| {
+            b.linear_chain(6)?
+                .brominate(3)?
+                .add_alkyl(3, AlkylGroup::Methyl)
+        });
+        let sn1 = predict_mechanism(
+            &tertiary,
+            Reagent::Water,
+            Solvent::PolarProtic,
+            Temperature::Room,
+        )
+        .expect("Prediction expected");
+        let alcohols: Vec<&Compound> = sn1
+            .products
+            .iter()
+            .filter(|p| p.major)
+            .map(|p| &p.products[0])
+            .collect();
+        assert_eq!(alcohols.len(), 2);
+        assert_eq!(
+            alcohols[0].relationship(alcohols[1]).expect("Configured"),
+            StereoRelationship::Enantiomers
+        );
+    }
+}
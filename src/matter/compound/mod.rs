@@ -1,10 +1,34 @@
+pub mod aromaticity;
+pub mod arrow;
+pub mod bond;
 pub mod builder;
+pub mod canonical;
+pub mod degree;
 pub mod deserializer;
+pub mod functional_group;
+pub mod geometry;
+pub mod halogenation;
+pub mod hydrogens;
+pub mod isomers;
+pub mod lewis;
+pub mod mechanism;
+pub mod named_reaction;
+pub mod naming;
+pub mod reaction;
+pub(crate) mod reindex;
+pub mod resonance;
+pub mod retrosynthesis;
+pub mod rings;
+pub mod smarts;
+pub mod smiles;
+pub mod stereo;
+pub mod stereoisomers;
+pub mod substituent;
+pub mod substructure;
 pub mod types;
 
-use itertools::sorted;
 use std::{
-    collections::{BTreeSet, HashMap, LinkedList},
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
     fmt,
     iter::Peekable,
     str::{Chars, FromStr},
@@ -12,7 +36,14 @@ use std::{
 
 use crate::{
     constants::ELEMENTS,
-    matter::{atom::Atom, compound::deserializer::Chain},
+    matter::{
+        atom::Atom,
+        compound::{
+            bond::{bond_key, BondOrder},
+            deserializer::Chain,
+            reindex::Reindex,
+        },
+    },
 };
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
@@ -21,7 +52,6 @@ pub struct Location {
     pub y: i16,
 }
 
-#[allow(dead_code)]
 impl Location {
     pub fn new(x: i16, y: i16) -> Self {
         Self { x, y }
@@ -33,6 +63,32 @@ impl Location {
             y: self.y + dy,
         }
     }
+
+    /// Closest untaken grid cell around this one: left, right, up and down
+    /// first, then outwards ring by ring.
+    pub(crate) fn nearest_free(
+        &self,
+        taken: &HashMap<Location, usize>,
+    ) -> Location {
+        let free = |loc: &Location| !taken.contains_key(loc);
+        let orthogonal = [(-1, 0), (1, 0), (0, 1), (0, -1)];
+        if let Some(loc) = orthogonal
+            .iter()
+            .map(|&(dx, dy)| self.shift(dx, dy))
+            .find(free)
+        {
+            return loc;
+        }
+        (1..)
+            .find_map(|radius: i16| {
+                (-radius..=radius)
+                    .flat_map(|dx| (-radius..=radius).map(move |dy| (dx, dy)))
+                    .filter(|(dx, dy)| dx.abs().max(dy.abs()) == radius)
+                    .map(|(dx, dy)| self.shift(dx, dy))
+                    .find(free)
+            })
+            .expect("The grid is unbounded")
+    }
 }
 
 pub struct SimpleCompound {}
@@ -53,6 +109,20 @@ pub struct Compound {
     /// ^ sort of like an undirected graph
     side_chains: HashMap<usize, BTreeSet<usize>>,
     // TODO: Ensure values != key or backbone idx
+    /// Orders of the bonds which aren't single bonds
+    bond_orders: HashMap<(usize, usize), BondOrder>,
+    /// Hydrogens folded into their heavy atom rather than stored as atoms
+    implicit_hydrogens: HashMap<usize, u8>,
+    /// Bonds closing rings, beyond the backbone and its side chains
+    ring_closures: BTreeSet<(usize, usize)>,
+    /// Arrangement around tetrahedral stereocenters: seen from the first
+    /// neighbour, the other three turn anticlockwise. A stereocenter's own
+    /// index stands for its implicit hydrogen.
+    chirality: HashMap<usize, [usize; 4]>,
+    /// Configuration of stereogenic double bonds: a neighbour of each atom
+    /// (lowest index first) lying on the same side. An atom's own index
+    /// stands for its implicit hydrogen.
+    double_bond_stereo: HashMap<(usize, usize), [usize; 2]>,
 }
 // TODO: Pseudo-Dijkstra's longest chain implementation (using largest distance)
 
@@ -63,6 +133,7 @@ impl Compound {
         location_to_idx: HashMap<Location, usize>,
         backbone: Vec<usize>,
         side_chains: HashMap<usize, BTreeSet<usize>>,
+        bond_orders: HashMap<(usize, usize), BondOrder>,
     ) -> Self {
         Self {
             atoms,
@@ -70,6 +141,11 @@ impl Compound {
             location_to_idx,
             backbone,
             side_chains,
+            bond_orders,
+            implicit_hydrogens: HashMap::new(),
+            ring_closures: BTreeSet::new(),
+            chirality: HashMap::new(),
+            double_bond_stereo: HashMap::new(),
         }
     }
 
@@ -96,30 +172,327 @@ impl Compound {
             false
         }
     }
+}
+
+impl Compound {
+    pub fn atom_count(&self) -> usize {
+        self.atoms.len()
+    }
+
+    pub fn atoms(&self) -> &[Atom] {
+        &self.atoms
+    }
+
+    pub fn locations(&self) -> &[Location] {
+        &self.locations
+    }
+
+    /// Net charge of the compound
+    pub fn charge(&self) -> i8 {
+        self.atoms.iter().map(|atom| atom.charge()).sum()
+    }
+
+    /// Every bond in the compound (lowest atom index first), following the
+    /// backbone and its side chains, then the ring closures.
+    pub fn bonds(&self) -> BTreeSet<(usize, usize)> {
+        let mut bonds = tree_bonds(&self.backbone, &self.side_chains);
+        bonds.extend(self.ring_closures.iter().copied());
+        bonds
+    }
+
+    /// Whether the compound contains a ring
+    pub fn is_cyclic(&self) -> bool {
+        !self.ring_closures.is_empty()
+    }
+
+    /// Molecular formula in Hill order: carbon, hydrogen, then the other
+    /// elements alphabetically (all alphabetically without carbon).
+    pub fn molecular_formula(&self) -> String {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for atom in self.atoms.iter() {
+            *counts.entry(atom.to_string()).or_default() += 1;
+        }
+        let implicit: usize =
+            self.implicit_hydrogens.values().map(|&h| h as usize).sum();
+        if implicit > 0 {
+            *counts.entry("H".into()).or_default() += implicit;
+        }
+        let mut order: Vec<String> = Vec::new();
+        if counts.contains_key("C") {
+            order.push("C".into());
+            if counts.contains_key("H") {
+                order.push("H".into());
+            }
+        }
+        let rest: Vec<String> = counts
+            .keys()
+            .filter(|k| !order.contains(k))
+            .cloned()
+            .collect();
+        order.extend(rest);
+        order
+            .into_iter()
+            .map(|symbol| match counts[&symbol] {
+                1 => symbol,
+                count => format!("{}{}", symbol, count),
+            })
+            .collect()
+    }
+
+    /// Every bond in the compound along with its order
+    pub fn bond_orders(&self) -> BTreeMap<(usize, usize), BondOrder> {
+        self.bonds()
+            .into_iter()
+            .map(|key| {
+                let order = self
+                    .bond_orders
+                    .get(&key)
+                    .copied()
+                    .unwrap_or(BondOrder::Single);
+                (key, order)
+            })
+            .collect()
+    }
+
+    /// Order of the bond between atoms `a` and `b`, if they're bonded
+    pub fn bond_order(&self, a: usize, b: usize) -> Option<BondOrder> {
+        let key = bond_key(a, b);
+        if !self.is_bonded(a, b) {
+            return None;
+        }
+        Some(
+            self.bond_orders
+                .get(&key)
+                .copied()
+                .unwrap_or(BondOrder::Single),
+        )
+    }
+
+    /// Records the order of the (existing) bond between atoms `a` and `b`.
+    pub fn set_bond_order(&mut self, a: usize, b: usize, order: BondOrder) {
+        let key = bond_key(a, b);
+        if order == BondOrder::Single {
+            self.bond_orders.remove(&key);
+        } else {
+            self.bond_orders.insert(key, order);
+        }
+    }
+
+    /// Drops the given atoms, shifting the remaining indices down.
+    pub(crate) fn remove_atoms(&mut self, removed: &BTreeSet<usize>) {
+        let reindex = Reindex::removing(self.atoms.len(), removed);
+        self.atoms = reindex.items(std::mem::take(&mut self.atoms));
+        if !self.locations.is_empty() {
+            self.locations = reindex.items(std::mem::take(&mut self.locations));
+        }
+        self.location_to_idx = self
+            .location_to_idx
+            .iter()
+            .filter_map(|(&loc, &i)| Some((loc, reindex.get(i)?)))
+            .collect();
+        self.backbone = reindex.indices(&self.backbone);
+        self.side_chains = reindex.side_chains(&self.side_chains);
+        self.bond_orders = reindex.bond_map(&self.bond_orders);
+        self.implicit_hydrogens = reindex.atom_map(&self.implicit_hydrogens);
+        self.ring_closures = reindex.bonds(&self.ring_closures);
+        // A removed neighbour of a stereocenter or a stereogenic double bond
+        // is a hydrogen folded into its atom
+        self.chirality = self
+            .chirality
+            .iter()
+            .filter_map(|(&i, around)| {
+                let center = reindex.get(i)?;
+                Some((center, around.map(|j| reindex.get(j).unwrap_or(center))))
+            })
+            .collect();
+        self.double_bond_stereo = self
+            .double_bond_stereo
+            .iter()
+            .filter_map(|(&(a, b), &[x, y])| {
+                let (a, b) = (reindex.get(a)?, reindex.get(b)?);
+                let cis =
+                    [reindex.get(x).unwrap_or(a), reindex.get(y).unwrap_or(b)];
+                Some(((a, b), cis))
+            })
+            .collect();
+    }
+
+    /// Atoms directly bonded to atom `i` (implicit hydrogens aside)
+    pub fn neighbors(&self, i: usize) -> Vec<usize> {
+        let mut neighbors = Vec::new();
+        if let Some(position) = self.backbone.iter().position(|&j| j == i) {
+            if position > 0 {
+                neighbors.push(self.backbone[position - 1]);
+            }
+            neighbors.extend(self.backbone.get(position + 1));
+        }
+        for (&parent, side_chain) in self.side_chains.iter() {
+            if parent == i {
+                neighbors.extend(side_chain.iter().copied());
+            } else if side_chain.contains(&i) {
+                neighbors.push(parent);
+            }
+        }
+        for &(a, b) in self.ring_closures.iter() {
+            if a == i {
+                neighbors.push(b);
+            } else if b == i {
+                neighbors.push(a);
+            }
+        }
+        // Side chains sit in a HashMap, whose order changes between runs
+        neighbors.sort_unstable();
+        neighbors
+    }
+
+    /// Whether atoms `a` and `b` share a bond, looked up in the backbone,
+    /// side chains and ring closures without building the bond set
+    fn is_bonded(&self, a: usize, b: usize) -> bool {
+        let in_side_chain = |parent: usize, child: usize| {
+            self.side_chains
+                .get(&parent)
+                .is_some_and(|side_chain| side_chain.contains(&child))
+        };
+        self.ring_closures.contains(&bond_key(a, b))
+            || in_side_chain(a, b)
+            || in_side_chain(b, a)
+            || self
+                .backbone
+                .windows(2)
+                .any(|pair| pair == [a, b] || pair == [b, a])
+    }
+
+    /// Neighbour list for every atom, indexed by atom index
+    pub fn adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![Vec::new(); self.atoms.len()];
+        for (a, b) in self.bonds() {
+            adjacency[a].push(b);
+            adjacency[b].push(a);
+        }
+        adjacency
+    }
+}
 
-    fn get_sidechain_unsafe(&self, i: usize) -> &BTreeSet<usize> {
-        self.side_chains.get(&i).expect("Side chain not found")
+/// Bonds implied by a backbone (consecutive atoms) and its side chains
+pub(crate) fn tree_bonds(
+    backbone: &[usize],
+    side_chains: &HashMap<usize, BTreeSet<usize>>,
+) -> BTreeSet<(usize, usize)> {
+    let mut bonds = BTreeSet::new();
+    for pair in backbone.windows(2) {
+        bonds.insert(bond_key(pair[0], pair[1]));
+    }
+    for (&i, side_chain) in side_chains.iter() {
+        for &j in side_chain {
+            bonds.insert(bond_key(i, j));
+        }
+    }
+    bonds
+}
+
+impl Compound {
+    /// Bare graph of the given atoms and bonds: atom 0 forms the backbone
+    /// and every edge `(a, b, _)` hangs `b` off `a` as a side chain. No
+    /// locations are generated and no hydrogens added.
+    pub(crate) fn from_edges(
+        atoms: Vec<Atom>,
+        edges: &[(usize, usize, BondOrder)],
+    ) -> Self {
+        let mut compound = Compound::new(
+            atoms,
+            Vec::new(),
+            HashMap::new(),
+            vec![0],
+            HashMap::new(),
+            HashMap::new(),
+        );
+        for &(a, b, order) in edges {
+            compound.side_chains.entry(a).or_default().insert(b);
+            compound.set_bond_order(a, b, order);
+        }
+        compound
+    }
+
+    /// Bare graph of the given atoms and bonds, which may fall apart into
+    /// several fragments. The bonds of a breadth-first spanning forest form
+    /// the side chains, the rest close rings. No locations are generated
+    /// and no hydrogens added.
+    pub(crate) fn from_bonds(
+        atoms: Vec<Atom>,
+        bonds: &BTreeMap<(usize, usize), BondOrder>,
+    ) -> Self {
+        let mut adjacency = vec![Vec::new(); atoms.len()];
+        for &(a, b) in bonds.keys() {
+            adjacency[a].push(b);
+            adjacency[b].push(a);
+        }
+        let mut compound = Compound::from_edges(atoms, &[]);
+        let mut seen = vec![false; adjacency.len()];
+        for start in 0..adjacency.len() {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut queue = VecDeque::from([start]);
+            while let Some(a) = queue.pop_front() {
+                for &b in adjacency[a].iter() {
+                    if !seen[b] {
+                        seen[b] = true;
+                        compound.side_chains.entry(a).or_default().insert(b);
+                        queue.push_back(b);
+                    }
+                }
+            }
+        }
+        let tree = compound.bonds();
+        compound.ring_closures = bonds
+            .keys()
+            .filter(|bond| !tree.contains(bond))
+            .copied()
+            .collect();
+        for (&(a, b), &order) in bonds.iter() {
+            compound.set_bond_order(a, b, order);
+        }
+        compound
     }
 }
 
 impl fmt::Display for Compound {
+    /// Condensed formula, or the molecular formula for rings (which a
+    /// condensed formula can't show).
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(&Chain::from(self).to_string())
+        if self.is_cyclic() {
+            f.write_str(&self.molecular_formula())
+        } else {
+            f.write_str(&Chain::from(self).to_string())
+        }
     }
 }
 
 pub type CompoundResult<T> = Result<T, CompoundError>;
 
-#[allow(dead_code)]
 #[derive(thiserror::Error, Debug)]
 pub enum CompoundError {
     #[error("Compound Parsing Error: {0}")]
     Parsing(String),
+    #[error("Lewis Structure Error: {0}")]
+    Lewis(String),
+    #[error("Electron Pushing Error: {0}")]
+    Arrow(String),
+    #[error("Geometry Error: {0}")]
+    Geometry(String),
+    #[error("Naming Error: {0}")]
+    Naming(String),
+    #[error("Reaction Error: {0}")]
+    Reaction(String),
+    #[error("Stereochemistry Error: {0}")]
+    Stereo(String),
     #[error("Unknown Error: {0}")]
     Unknown(String),
 }
 
 #[inline]
+#[allow(dead_code)]
 fn is_delimiter(c: char) -> bool {
     is_open_delimiter(c) || is_close_delimiter(c)
 }
@@ -247,7 +620,28 @@ mod tests {
             ]),
             backbone: vec![0, 2],
             side_chains: HashMap::from([(0, BTreeSet::from([1, 3]))]),
+            bond_orders: HashMap::new(),
+            implicit_hydrogens: HashMap::new(),
+            ring_closures: BTreeSet::new(),
+            chirality: HashMap::new(),
+            double_bond_stereo: HashMap::new(),
         };
         assert_eq!(comp.to_string(), "HHeLiHe");
     }
+
+    #[test]
+    fn compound_neighbors_in_index_order() {
+        let compound = builder::CompoundBuilder::new()
+            .linear_chain(4)
+            .and_then(|b| b.add_alkyl(2, substituent::AlkylGroup::Methyl))
+            .and_then(|b| b.add_alkyl(2, substituent::AlkylGroup::Methyl))
+            .and_then(|b| b.ring_closure(1, 4))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        for i in 0..compound.atom_count() {
+            let neighbors = compound.neighbors(i);
+            assert!(neighbors.is_sorted(), "{:?}", neighbors);
+        }
+        assert_eq!(compound.neighbors(1).len(), 4);
+    }
 }
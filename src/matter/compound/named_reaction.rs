@@ -0,0 +1,1171 @@
+use std::collections::BTreeSet;
+
+use crate::matter::{
+    atom::Atom,
+    compound::{
+        bond::{bond_key, BondOrder},
+        halogenation::Halogen,
+        reaction::{Reaction, ReactionOutcome},
+        stereo::{CipDescriptor, EzDescriptor},
+        substituent::AlkylGroup,
+        Compound, CompoundResult,
+    },
+};
+
+/// Reagents and conditions a reaction is run with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reagent {
+    /// HX
+    HydrogenHalide(Halogen),
+    /// X₂
+    Halogen(Halogen),
+    /// ROOR, starting radical chains
+    Peroxide,
+    /// H₂O with H₂SO₄ (H₃O⁺)
+    AqueousAcid,
+    /// BH₃·THF
+    Borane,
+    /// H₂O₂, NaOH (oxidative workup)
+    BasicPeroxide,
+    /// O₃
+    Ozone,
+    /// (CH₃)₂S (reductive workup)
+    DimethylSulfide,
+    /// RMgBr, followed by an acidic workup
+    Grignard(AlkylGroup),
+    /// Pyridinium chlorochromate
+    Pcc,
+    /// H₂CrO₄ (Jones reagent)
+    ChromicAcid,
+    /// NaOH
+    Hydroxide,
+    /// NaOR; tert-butoxide is bulky
+    Alkoxide(AlkylGroup),
+    /// NaCN
+    Cyanide,
+    /// H₂O as solvent and nucleophile
+    Water,
+    /// Δ
+    Heat,
+    /// hν, for radical reactions
+    Light,
+}
+
+/// Compound of the given heavy atoms and bonds, hydrogens filled up to
+/// each atom's lowest fitting valence
+fn molecule(atoms: Vec<Atom>, bonds: &[(usize, usize, BondOrder)]) -> Compound {
+    let mut compound = Compound::from_edges(atoms, bonds);
+    compound.implicit_hydrogens = (0..compound.atom_count())
+        .filter_map(|i| {
+            let bonded: u8 = compound
+                .neighbors(i)
+                .into_iter()
+                .filter_map(|j| compound.bond_order(i, j))
+                .map(|order| order.as_u8())
+                .sum();
+            let valence = compound.atoms[i]
+                .valences()
+                .into_iter()
+                .find(|&v| v >= bonded)?;
+            Some((i, valence - bonded)).filter(|&(_, h)| h > 0)
+        })
+        .collect();
+    compound.with_explicit_hydrogens()
+}
+
+/// Alkyl group bonded through its first carbon to the atoms before it
+fn with_alkyl(
+    mut atoms: Vec<Atom>,
+    mut bonds: Vec<(usize, usize, BondOrder)>,
+    at: usize,
+    group: AlkylGroup,
+) -> Compound {
+    let offset = atoms.len();
+    atoms.extend(group.atoms());
+    bonds.push((at, offset, BondOrder::Single));
+    bonds.extend(
+        group
+            .bonds()
+            .into_iter()
+            .map(|(a, b)| (a + offset, b + offset, BondOrder::Single)),
+    );
+    molecule(atoms, &bonds)
+}
+
+impl Reagent {
+    /// Strong base too hindered to act as a nucleophile
+    pub fn is_bulky_base(&self) -> bool {
+        matches!(self, Self::Alkoxide(AlkylGroup::TertButyl))
+    }
+
+    /// Strong, unhindered base which is also a strong nucleophile
+    fn is_small_strong_base(&self) -> bool {
+        matches!(self, Self::Hydroxide)
+            || matches!(self, Self::Alkoxide(_)) && !self.is_bulky_base()
+    }
+
+    /// The reagent as a molecule taking part in the reaction, for those
+    /// whose atoms end up in the products
+    pub fn compound(&self) -> Option<Compound> {
+        let oxide = || vec![Atom::new_unchecked(8).ion(-1)];
+        match *self {
+            Self::Hydroxide => Some(molecule(oxide(), &[])),
+            Self::Alkoxide(group) => {
+                Some(with_alkyl(oxide(), vec![], 0, group))
+            }
+            Self::Cyanide => Some(molecule(
+                vec![Atom::carbon().ion(-1), Atom::nitrogen()],
+                &[(0, 1, BondOrder::Triple)],
+            )),
+            Self::Grignard(group) => Some(with_alkyl(
+                vec![Atom::bromine(), Atom::new_unchecked(12)],
+                vec![(0, 1, BondOrder::Single)],
+                1,
+                group,
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Which of several positions a reaction favours
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum Regiochemistry {
+    /// The new group goes to the more substituted carbon
+    Markovnikov,
+    /// The new group goes to the less substituted carbon
+    AntiMarkovnikov,
+    /// The more substituted alkene forms
+    Zaitsev,
+    /// The less substituted alkene forms
+    Hofmann,
+}
+
+impl Regiochemistry {
+    /// Preference for an outcome where the deciding carbon (the one taking
+    /// the new group, or giving up its hydrogen in an elimination) had
+    /// `hydrogens` hydrogens; higher is better
+    fn score(&self, hydrogens: u8) -> i32 {
+        match self {
+            Self::Markovnikov | Self::Zaitsev => -(hydrogens as i32),
+            Self::AntiMarkovnikov | Self::Hofmann => hydrogens as i32,
+        }
+    }
+}
+
+/// Spatial outcome of a reaction at the atoms it changes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum Stereochemistry {
+    /// Both groups add to opposite faces of the double bond
+    AntiAddition,
+    /// Both groups add to the same face of the double bond
+    SynAddition,
+    /// Backside attack inverts the stereocenter
+    Inversion,
+    /// A planar intermediate is attacked from either face
+    Racemization,
+    /// The more stable E alkene is the major product
+    EAlkene,
+}
+
+/// Textbook reactions [`predict_products`] knows about
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum NamedReaction {
+    #[strum(serialize = "Markovnikov hydrohalogenation")]
+    MarkovnikovHydrohalogenation,
+    #[strum(serialize = "Anti-Markovnikov hydrobromination")]
+    AntiMarkovnikovHydrobromination,
+    #[strum(serialize = "Acid-catalysed hydration")]
+    AcidCatalysedHydration,
+    #[strum(serialize = "Hydroboration-oxidation")]
+    HydroborationOxidation,
+    #[strum(serialize = "Halogenation of alkenes")]
+    AlkeneHalogenation,
+    #[strum(serialize = "Radical halogenation")]
+    RadicalHalogenation,
+    #[strum(serialize = "Ozonolysis")]
+    Ozonolysis,
+    #[strum(serialize = "SN2")]
+    Sn2,
+    #[strum(serialize = "SN1")]
+    Sn1,
+    #[strum(serialize = "E2")]
+    E2,
+    #[strum(serialize = "E2 with a bulky base")]
+    BulkyBaseE2,
+    #[strum(serialize = "E1")]
+    E1,
+    #[strum(serialize = "Grignard addition")]
+    GrignardAddition,
+    #[strum(serialize = "PCC oxidation")]
+    PccOxidation,
+    #[strum(serialize = "Chromic acid oxidation")]
+    ChromicAcidOxidation,
+}
+
+/// One set of products [`predict_products`] expects
+#[derive(Clone, Debug)]
+pub struct Prediction {
+    pub reaction: NamedReaction,
+    /// Every fragment coming out of the reaction, by-products (halide
+    /// ions, water) included
+    pub products: Vec<Compound>,
+    /// Whether the reaction's regio- and stereochemistry rules favour
+    /// these products
+    pub major: bool,
+}
+
+/// Products with the stereochemistry of one outcome applied, and whether
+/// the stereochemistry favours them
+type StereoOutcome = (Vec<Compound>, bool);
+
+impl NamedReaction {
+    pub const ALL: [Self; 15] = [
+        Self::MarkovnikovHydrohalogenation,
+        Self::AntiMarkovnikovHydrobromination,
+        Self::AcidCatalysedHydration,
+        Self::HydroborationOxidation,
+        Self::AlkeneHalogenation,
+        Self::RadicalHalogenation,
+        Self::Ozonolysis,
+        Self::Sn2,
+        Self::Sn1,
+        Self::E2,
+        Self::BulkyBaseE2,
+        Self::E1,
+        Self::GrignardAddition,
+        Self::PccOxidation,
+        Self::ChromicAcidOxidation,
+    ];
+
+    pub fn regiochemistry(&self) -> Option<Regiochemistry> {
+        match self {
+            Self::MarkovnikovHydrohalogenation
+            | Self::AcidCatalysedHydration => Some(Regiochemistry::Markovnikov),
+            Self::AntiMarkovnikovHydrobromination
+            | Self::HydroborationOxidation => {
+                Some(Regiochemistry::AntiMarkovnikov)
+            }
+            Self::E2 | Self::E1 => Some(Regiochemistry::Zaitsev),
+            Self::BulkyBaseE2 => Some(Regiochemistry::Hofmann),
+            _ => None,
+        }
+    }
+
+    pub fn stereochemistry(&self) -> Option<Stereochemistry> {
+        match self {
+            Self::AlkeneHalogenation => Some(Stereochemistry::AntiAddition),
+            Self::HydroborationOxidation => Some(Stereochemistry::SynAddition),
+            Self::Sn2 => Some(Stereochemistry::Inversion),
+            Self::MarkovnikovHydrohalogenation
+            | Self::AntiMarkovnikovHydrobromination
+            | Self::AcidCatalysedHydration
+            | Self::Sn1
+            | Self::GrignardAddition => Some(Stereochemistry::Racemization),
+            Self::E2 | Self::BulkyBaseE2 | Self::E1 => {
+                Some(Stereochemistry::EAlkene)
+            }
+            Self::RadicalHalogenation
+            | Self::Ozonolysis
+            | Self::PccOxidation
+            | Self::ChromicAcidOxidation => None,
+        }
+    }
+
+    /// Whether the reagents bring about the reaction
+    pub fn runs_with(&self, reagents: &[Reagent]) -> bool {
+        let has = |reagent: Reagent| reagents.contains(&reagent);
+        let any = |f: fn(&Reagent) -> bool| reagents.iter().any(f);
+        match self {
+            Self::MarkovnikovHydrohalogenation => {
+                any(|r| matches!(r, Reagent::HydrogenHalide(_)))
+                    && !has(Reagent::Peroxide)
+            }
+            Self::AntiMarkovnikovHydrobromination => {
+                has(Reagent::HydrogenHalide(Halogen::Bromine))
+                    && has(Reagent::Peroxide)
+            }
+            Self::AcidCatalysedHydration => has(Reagent::AqueousAcid),
+            Self::HydroborationOxidation => {
+                has(Reagent::Borane) && has(Reagent::BasicPeroxide)
+            }
+            Self::AlkeneHalogenation => {
+                any(|r| matches!(r, Reagent::Halogen(_)))
+                    && !has(Reagent::Light)
+            }
+            Self::RadicalHalogenation => {
+                any(|r| matches!(r, Reagent::Halogen(_))) && has(Reagent::Light)
+            }
+            Self::Ozonolysis => {
+                has(Reagent::Ozone) && has(Reagent::DimethylSulfide)
+            }
+            Self::Sn2 => {
+                any(Reagent::is_small_strong_base) || has(Reagent::Cyanide)
+            }
+            Self::E2 => any(Reagent::is_small_strong_base),
+            Self::BulkyBaseE2 => any(Reagent::is_bulky_base),
+            Self::Sn1 => has(Reagent::Water) && !has(Reagent::Heat),
+            Self::E1 => has(Reagent::Water) && has(Reagent::Heat),
+            Self::GrignardAddition => {
+                any(|r| matches!(r, Reagent::Grignard(_)))
+            }
+            Self::PccOxidation => has(Reagent::Pcc),
+            Self::ChromicAcidOxidation => has(Reagent::ChromicAcid),
+        }
+    }
+
+    /// Reaction SMARTS for the reaction with the given reagents. Alkene
+    /// carbons are mapped 1 and 2, the one taking the new group (or the
+    /// carbon bearing the leaving group) being 2; in substitutions the
+    /// carbon is 1 and the leaving group 2.
+    fn templates(&self, reagents: &[Reagent]) -> Vec<String> {
+        self.templates_with(reagents, true)
+    }
+
+    /// [`Self::templates`] without restricting substitutions and
+    /// eliminations to the alkyl halides whose degree usually takes that
+    /// path, for callers which have already settled the mechanism
+    pub(crate) fn any_degree_templates(
+        &self,
+        reagents: &[Reagent],
+    ) -> Vec<String> {
+        self.templates_with(reagents, false)
+    }
+
+    fn templates_with(
+        &self,
+        reagents: &[Reagent],
+        by_degree: bool,
+    ) -> Vec<String> {
+        let halogens = |f: fn(&Reagent) -> Option<Halogen>| {
+            reagents
+                .iter()
+                .filter_map(f)
+                .map(|halogen| halogen.atom().to_string())
+                .collect::<Vec<String>>()
+        };
+        const LEAVING_GROUP: &str = "[Cl,Br,I";
+        const TERTIARY: &str = ";H0;$(C(C)(C)C)";
+        // Restriction on the carbon bearing the leaving group
+        let degree = |filter: &'static str| match by_degree {
+            true => filter,
+            false => "",
+        };
+        match self {
+            Self::MarkovnikovHydrohalogenation => halogens(|r| match r {
+                Reagent::HydrogenHalide(halogen) => Some(*halogen),
+                _ => None,
+            })
+            .iter()
+            .map(|x| format!("[C:1]=[C:2]>>[C:1][C:2]{}", x))
+            .collect(),
+            Self::AntiMarkovnikovHydrobromination => {
+                vec!["[C:1]=[C:2]>>[C:1][C:2]Br".into()]
+            }
+            Self::AcidCatalysedHydration | Self::HydroborationOxidation => {
+                vec!["[C:1]=[C:2]>>[C:1][C:2]O".into()]
+            }
+            Self::AlkeneHalogenation => halogens(|r| match r {
+                Reagent::Halogen(halogen) => Some(*halogen),
+                _ => None,
+            })
+            .iter()
+            .map(|x| format!("[C:1]=[C:2]>>{x}[C:1][C:2]{x}", x = x))
+            .collect(),
+            // Run by Compound::radical_halogenation instead
+            Self::RadicalHalogenation => Vec::new(),
+            Self::Ozonolysis => vec!["[C:1]=[C:2]>>[C:1]=O.[C:2]=O".into()],
+            Self::Sn2 => {
+                // Cyanide is a weak base, so secondary carbons substitute
+                // rather than eliminate
+                let carbon = match reagents.contains(&Reagent::Cyanide) {
+                    true => degree(";H1,H2,H3"),
+                    false => degree(";H2,H3"),
+                };
+                vec![format!(
+                    "[CX4{}:1]{}:2].[#6-,#8-:3]>>[C:1][*+0:3].[*-:2]",
+                    carbon, LEAVING_GROUP
+                )]
+            }
+            Self::E2 => vec![format!(
+                "[CX4;!H0:1][CX4{}:2]{}:3].[O-:4]>>[C:1]=[C:2].[*-:3].[O+0:4]",
+                degree(";H0,H1"),
+                LEAVING_GROUP
+            )],
+            Self::BulkyBaseE2 => vec![format!(
+                "[CX4;!H0:1][CX4:2]{}:3].[O-:4]>>[C:1]=[C:2].[*-:3].[O+0:4]",
+                LEAVING_GROUP
+            )],
+            Self::Sn1 => vec![format!(
+                "[CX4{}:1]{}:2]>>[C:1]O.[*-:2]",
+                degree(TERTIARY),
+                LEAVING_GROUP
+            )],
+            Self::E1 => vec![format!(
+                "[CX4;!H0:1][CX4{}:2]{}:3]>>[C:1]=[C:2].[*-:3]",
+                degree(TERTIARY),
+                LEAVING_GROUP
+            )],
+            Self::GrignardAddition => vec![
+                "[CX3;!$(C[O,N]):1]=[O:2].[#6:3][Mg]Br>>[C:1]([#6:3])[O:2]"
+                    .into(),
+            ],
+            Self::PccOxidation => {
+                vec!["[CX4;!H0:1][OX2H1:2]>>[C:1]=[O:2]".into()]
+            }
+            Self::ChromicAcidOxidation => vec![
+                "[CX4;H2,H3:1][OX2H1:2]>>[C:1](=[O:2])O".into(),
+                "[CX4;H1:1][OX2H1:2]>>[C:1]=[O:2]".into(),
+            ],
+        }
+    }
+
+    /// Map number of the carbon whose hydrogens decide the
+    /// regiochemistry
+    fn regio_map(&self) -> u16 {
+        match self {
+            Self::E2 | Self::BulkyBaseE2 | Self::E1 => 1,
+            _ => 2,
+        }
+    }
+
+    /// Every product set of the reaction, those its regio- and
+    /// stereochemistry rules favour marked as major. Reagents with atoms
+    /// of their own (nucleophiles, bases, Grignard reagents) join the
+    /// reactants.
+    pub fn run(
+        &self,
+        reactants: &[Compound],
+        reagents: &[Reagent],
+    ) -> CompoundResult<Vec<Prediction>> {
+        if !self.runs_with(reagents) {
+            return Ok(Vec::new());
+        }
+        if *self == Self::RadicalHalogenation {
+            return Ok(radical_halogenation(reactants, reagents));
+        }
+        let mut molecules = reactants.to_vec();
+        if matches!(
+            self,
+            Self::Sn2 | Self::E2 | Self::BulkyBaseE2 | Self::GrignardAddition
+        ) {
+            molecules.extend(reagents.iter().filter_map(Reagent::compound));
+        }
+        self.run_templates(&self.templates(reagents), &molecules)
+    }
+
+    /// Products of the given reaction SMARTS, mapped the way
+    /// [`Self::templates`] maps them, judged by this reaction's regio- and
+    /// stereochemistry rules
+    pub(crate) fn run_templates(
+        &self,
+        templates: &[String],
+        molecules: &[Compound],
+    ) -> CompoundResult<Vec<Prediction>> {
+        let mut candidates = Vec::new();
+        for template in templates {
+            let reaction = Reaction::parse(template)?;
+            for outcome in reaction.outcomes(molecules)? {
+                let score = self.regiochemistry().map_or(0, |regio| {
+                    let (r, i) = outcome.mapped[&self.regio_map()];
+                    regio.score(molecules[r].hydrogen_count(i))
+                });
+                let outcome = without_spectators(outcome);
+                for (products, favoured) in
+                    self.stereo_outcomes(molecules, &outcome)?
+                {
+                    candidates.push((score, favoured, products));
+                }
+            }
+        }
+        let best = candidates.iter().map(|(score, _, _)| *score).max();
+        let mut seen = BTreeSet::new();
+        let mut predictions = Vec::new();
+        for (score, favoured, products) in candidates {
+            let mut keys: Vec<String> =
+                products.iter().map(|p| p.stereo_key()).collect();
+            keys.sort();
+            if seen.insert(keys) {
+                predictions.push(Prediction {
+                    reaction: *self,
+                    products,
+                    major: favoured && Some(score) == best,
+                });
+            }
+        }
+        predictions.sort_by_key(|prediction| !prediction.major);
+        Ok(predictions)
+    }
+
+    /// The outcome's products with the reaction's stereochemistry applied
+    fn stereo_outcomes(
+        &self,
+        reactants: &[Compound],
+        outcome: &ReactionOutcome,
+    ) -> CompoundResult<Vec<StereoOutcome>> {
+        let unchanged = vec![(outcome.products.clone(), true)];
+        let Some(stereochemistry) = self.stereochemistry() else {
+            return Ok(unchanged);
+        };
+        let landed = |map: u16| outcome.atoms.get(outcome.mapped.get(&map)?);
+        let outcomes = match stereochemistry {
+            Stereochemistry::AntiAddition | Stereochemistry::SynAddition => {
+                addition(
+                    reactants,
+                    outcome,
+                    stereochemistry == Stereochemistry::AntiAddition,
+                )?
+            }
+            Stereochemistry::Inversion => inversion(reactants, outcome)?,
+            Stereochemistry::Racemization => {
+                let centers: Vec<(usize, usize)> =
+                    [1, 2].into_iter().filter_map(landed).copied().collect();
+                racemization(outcome, &centers)?
+            }
+            Stereochemistry::EAlkene => match (landed(1), landed(2)) {
+                (Some(&(k, a)), Some(&(_, b))) => {
+                    e_alkene(&outcome.products, k, (a, b))?
+                }
+                _ => Vec::new(),
+            },
+        };
+        Ok(if outcomes.is_empty() {
+            unchanged
+        } else {
+            outcomes
+        })
+    }
+}
+
+/// Monohalogenation products of the alkanes among the reactants, the
+/// most abundant for each halogen being major
+fn radical_halogenation(
+    reactants: &[Compound],
+    reagents: &[Reagent],
+) -> Vec<Prediction> {
+    let mut predictions = Vec::new();
+    for reactant in reactants {
+        for reagent in reagents {
+            let Reagent::Halogen(halogen) = *reagent else {
+                continue;
+            };
+            let Ok(products) = reactant.radical_halogenation(halogen) else {
+                continue;
+            };
+            let most = products
+                .iter()
+                .map(|product| product.fraction)
+                .fold(0.0, f64::max);
+            predictions.extend(products.into_iter().map(|product| {
+                Prediction {
+                    reaction: NamedReaction::RadicalHalogenation,
+                    major: product.fraction == most,
+                    products: vec![product.compound],
+                }
+            }));
+        }
+    }
+    predictions.sort_by_key(|prediction| !prediction.major);
+    predictions
+}
+
+/// Drops the products which are reactants the reaction left untouched
+fn without_spectators(mut outcome: ReactionOutcome) -> ReactionOutcome {
+    let involved: BTreeSet<usize> =
+        outcome.mapped.values().map(|&(r, _)| r).collect();
+    let spectators: BTreeSet<usize> = (0..outcome.products.len())
+        .filter(|&k| {
+            let mut origins = outcome
+                .atoms
+                .iter()
+                .filter(|(_, &(product, _))| product == k)
+                .map(|(&(r, _), _)| r)
+                .peekable();
+            origins.peek().is_some() && origins.all(|r| !involved.contains(&r))
+        })
+        .collect();
+    if spectators.is_empty() {
+        return outcome;
+    }
+    let kept: Vec<usize> = (0..outcome.products.len())
+        .filter(|k| !spectators.contains(k))
+        .collect();
+    outcome.products =
+        kept.iter().map(|&k| outcome.products[k].clone()).collect();
+    outcome.atoms = outcome
+        .atoms
+        .into_iter()
+        .filter_map(|(origin, (k, i))| {
+            let k = kept.iter().position(|&kept| kept == k)?;
+            Some((origin, (k, i)))
+        })
+        .collect();
+    outcome
+}
+
+/// Applies `edit` to product `k` with its hydrogens implicit (which keeps
+/// the indices of its other atoms), putting hydrogens back afterwards
+fn edited(
+    products: &[Compound],
+    k: usize,
+    edit: impl FnOnce(&mut Compound) -> CompoundResult<()>,
+) -> CompoundResult<Vec<Compound>> {
+    let mut products = products.to_vec();
+    let explicit = !products[k].has_implicit_hydrogens();
+    let mut view = products[k].with_implicit_hydrogens();
+    edit(&mut view)?;
+    if explicit {
+        view.make_hydrogens_explicit();
+    }
+    products[k] = view;
+    Ok(products)
+}
+
+/// Atoms bonded to `i` in the product which don't come from a reactant
+/// neighbour of the original atom; an implicit hydrogen written as `i`
+fn new_neighbors(view: &Compound, i: usize, old: &[usize]) -> Vec<usize> {
+    let mut new: Vec<usize> = view
+        .neighbors(i)
+        .into_iter()
+        .filter(|j| !old.contains(j))
+        .collect();
+    if new.is_empty() {
+        new.push(i);
+    }
+    new
+}
+
+/// Arrangement (as [`Compound::chirality`]) around a former alkene carbon
+/// which took group `added` on face `face` (±1), seen with its double bond
+/// partner `other` to the right (or left, for the `right` carbon), `cis`
+/// above and `trans` below it.
+fn added_arrangement(
+    [other, cis, trans, added]: [usize; 4],
+    face: f64,
+    right: bool,
+) -> [usize; 4] {
+    let side = if right { -1.0 } else { 1.0 };
+    // The three old groups bend away from the new one
+    let h = -0.3 * face;
+    let positions = [
+        [side, 0.0, h],
+        [-0.5 * side, 0.87, h],
+        [-0.5 * side, -0.87, h],
+        [0.0, 0.0, face],
+    ];
+    let minus =
+        |a: [f64; 3], b: [f64; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    let [u, v, w] = [1, 2, 3].map(|k| minus(positions[k], positions[0]));
+    let volume = u[0] * (v[1] * w[2] - v[2] * w[1])
+        - u[1] * (v[0] * w[2] - v[2] * w[0])
+        + u[2] * (v[0] * w[1] - v[1] * w[0]);
+    // Anticlockwise seen from the first group comes out negative
+    if volume < 0.0 {
+        [other, cis, trans, added]
+    } else {
+        [other, cis, added, trans]
+    }
+}
+
+/// Stereoisomers from adding a group to each alkene carbon, on opposite
+/// faces (`anti`) or the same face. Both faces of the alkene are attacked
+/// equally; a double bond whose configuration isn't recorded is taken as
+/// both E and Z, unless a small ring holds it cis.
+fn addition(
+    reactants: &[Compound],
+    outcome: &ReactionOutcome,
+    anti: bool,
+) -> CompoundResult<Vec<StereoOutcome>> {
+    let (Some(&(r, c1)), Some(&(_, c2))) =
+        (outcome.mapped.get(&1), outcome.mapped.get(&2))
+    else {
+        return Ok(Vec::new());
+    };
+    let reactant = &reactants[r];
+    let (Some(&(k, p1)), Some(&(_, p2))) =
+        (outcome.atoms.get(&(r, c1)), outcome.atoms.get(&(r, c2)))
+    else {
+        return Ok(Vec::new());
+    };
+    let (around1, around2) = (
+        reactant.double_bond_substituents(c1, c2),
+        reactant.double_bond_substituents(c2, c1),
+    );
+    if around1.len() != 2 || around2.len() != 2 {
+        return Ok(Vec::new());
+    }
+    let cis_pairs: Vec<[usize; 2]> =
+        match reactant.double_bond_configuration(c1, c2) {
+            Some(cis) => vec![cis],
+            None => {
+                let rings = reactant.rings();
+                let ring = rings
+                    .bond_rings(c1, c2)
+                    .iter()
+                    .map(|&ring| &rings.sssr[ring])
+                    .min_by_key(|ring| ring.len());
+                match ring {
+                    Some(ring) => {
+                        let in_ring = |around: &[usize]| {
+                            around.iter().copied().find(|j| ring.contains(j))
+                        };
+                        match (in_ring(&around1), in_ring(&around2)) {
+                            (Some(x), Some(y)) => vec![[x, y]],
+                            _ => return Ok(Vec::new()),
+                        }
+                    }
+                    None => {
+                        vec![[around1[0], around2[0]], [around1[0], around2[1]]]
+                    }
+                }
+            }
+        };
+    // Product atom of a reactant substituent of `c` (landing at `p`)
+    let image = |c: usize, p: usize, j: usize| -> Option<usize> {
+        if j == c || reactant.atoms()[j].is_plain_hydrogen() {
+            return Some(p);
+        }
+        match outcome.atoms.get(&(r, j)) {
+            Some(&(product, i)) if product == k => Some(i),
+            _ => None,
+        }
+    };
+    let view = outcome.products[k].with_implicit_hydrogens();
+    let stereocenters = view.stereocenters();
+    let mut outcomes = Vec::new();
+    for [x, y] in cis_pairs {
+        let other = |around: &[usize], cis: usize| {
+            *around.iter().find(|&&j| j != cis).unwrap_or(&cis)
+        };
+        let ends = [
+            (c1, p1, p2, x, other(&around1, x), false),
+            (c2, p2, p1, y, other(&around2, y), true),
+        ];
+        let mut groups = Vec::new();
+        for (c, p, partner, cis, trans, right) in ends {
+            let (Some(cis), Some(trans)) =
+                (image(c, p, cis), image(c, p, trans))
+            else {
+                return Ok(Vec::new());
+            };
+            let added = new_neighbors(&view, p, &[partner, cis, trans]);
+            if added.len() != 1 {
+                return Ok(Vec::new());
+            }
+            groups.push((p, [partner, cis, trans, added[0]], right));
+        }
+        for face in [1.0, -1.0] {
+            let products = edited(&outcome.products, k, |product| {
+                for (p, group, right) in groups.iter() {
+                    if !stereocenters.contains(p) {
+                        continue;
+                    }
+                    let face = match (right, anti) {
+                        (true, true) => -face,
+                        _ => face,
+                    };
+                    product.set_chirality(
+                        *p,
+                        added_arrangement(*group, face, *right),
+                    )?;
+                }
+                Ok(())
+            })?;
+            outcomes.push((products, true));
+        }
+    }
+    Ok(outcomes)
+}
+
+/// The substitution product with the configuration of the carbon
+/// (mapped 1) inverted, the nucleophile taking the leaving group's
+/// (mapped 2) place
+fn inversion(
+    reactants: &[Compound],
+    outcome: &ReactionOutcome,
+) -> CompoundResult<Vec<StereoOutcome>> {
+    let (Some(&(r, c)), Some(&(_, leaving))) =
+        (outcome.mapped.get(&1), outcome.mapped.get(&2))
+    else {
+        return Ok(Vec::new());
+    };
+    let reactant = &reactants[r];
+    let (Some(around), Some(&(k, p))) =
+        (reactant.chirality(c), outcome.atoms.get(&(r, c)))
+    else {
+        return Ok(Vec::new());
+    };
+    let view = outcome.products[k].with_implicit_hydrogens();
+    let old: Vec<usize> = around
+        .iter()
+        .filter_map(|j| match outcome.atoms.get(&(r, *j)) {
+            Some(&(product, i)) if product == k => Some(i),
+            _ => None,
+        })
+        .collect();
+    let [nucleophile] = new_neighbors(&view, p, &old)[..] else {
+        return Ok(Vec::new());
+    };
+    let mut arrangement = [p; 4];
+    for (slot, &j) in arrangement.iter_mut().zip(around.iter()) {
+        if j == leaving {
+            *slot = nucleophile;
+        } else if j != c && !reactant.atoms()[j].is_plain_hydrogen() {
+            *slot = outcome.atoms[&(r, j)].1;
+        }
+    }
+    arrangement.swap(2, 3);
+    if !view.stereocenters().contains(&p) {
+        return Ok(Vec::new());
+    }
+    let products = edited(&outcome.products, k, |product| {
+        product.set_chirality(p, arrangement)
+    })?;
+    Ok(vec![(products, true)])
+}
+
+/// Both configurations of every stereocenter among `centers` (product and
+/// atom index), in all combinations
+fn racemization(
+    outcome: &ReactionOutcome,
+    centers: &[(usize, usize)],
+) -> CompoundResult<Vec<StereoOutcome>> {
+    let centers: Vec<(usize, usize)> = centers
+        .iter()
+        .copied()
+        .filter(|&(k, p)| {
+            outcome.products[k]
+                .with_implicit_hydrogens()
+                .stereocenters()
+                .contains(&p)
+        })
+        .collect();
+    if centers.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut outcomes = Vec::new();
+    for mask in 0..1usize << centers.len() {
+        let mut products = outcome.products.clone();
+        for (bit, &(k, p)) in centers.iter().enumerate() {
+            let descriptor = match mask >> bit & 1 {
+                0 => CipDescriptor::R,
+                _ => CipDescriptor::S,
+            };
+            products = edited(&products, k, |product| {
+                product.set_cip_descriptor(p, descriptor)
+            })?;
+        }
+        outcomes.push((products, true));
+    }
+    Ok(outcomes)
+}
+
+/// E and Z forms of the new double bond in product `k`, E favoured
+fn e_alkene(
+    products: &[Compound],
+    k: usize,
+    (a, b): (usize, usize),
+) -> CompoundResult<Vec<StereoOutcome>> {
+    let view = products[k].with_implicit_hydrogens();
+    if !view.stereogenic_double_bonds().contains(&bond_key(a, b)) {
+        return Ok(Vec::new());
+    }
+    [EzDescriptor::E, EzDescriptor::Z]
+        .into_iter()
+        .map(|descriptor| {
+            let products = edited(products, k, |product| {
+                product.set_ez_descriptor(a, b, descriptor)
+            })?;
+            Ok((products, descriptor == EzDescriptor::E))
+        })
+        .collect()
+}
+
+/// Products of every known reaction the reagents bring about, major
+/// products first within each reaction.
+///
+/// Carbocation rearrangements aren't considered, E2 isn't held to an
+/// anti-periplanar geometry, and reactions with nothing to react with give
+/// nothing.
+pub fn predict_products(
+    reactants: &[Compound],
+    reagents: &[Reagent],
+) -> CompoundResult<Vec<Prediction>> {
+    let mut predictions = Vec::new();
+    for reaction in NamedReaction::ALL {
+        predictions.extend(reaction.run(reactants, reagents)?);
+    }
+    Ok(predictions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::compound::{
+        builder::{BuildStep, CompoundBuilder},
+        stereoisomers::StereoRelationship,
+        substituent::Substituent,
+    };
+
+    fn build(step: BuildStep) -> Compound {
+        step(&mut CompoundBuilder::new())
+            .and_then(|b| b.build())
+            .expect("Valid compound expected")
+    }
+
+    /// Compound from heavy atoms (by atomic number) and bonds
+    fn graph(atoms: &[u8], bonds: &[(usize, usize, u8)]) -> Compound {
+        let atoms = atoms.iter().map(|&z| Atom::new_unchecked(z)).collect();
+        let bonds: Vec<(usize, usize, BondOrder)> = bonds
+            .iter()
+            .map(|&(a, b, order)| {
+                (a, b, BondOrder::from_u8(order).expect("Bond order"))
+            })
+            .collect();
+        CompoundBuilder::new()
+            .graph(atoms, &bonds)
+            .and_then(|b| b.build())
+            .expect("Valid compound expected")
+    }
+
+    fn predict(reactant: &Compound, reagents: &[Reagent]) -> Vec<Prediction> {
+        predict_products(std::slice::from_ref(reactant), reagents)
+            .expect("Predictions expected")
+    }
+
+    /// Names of the first product of every prediction, major ones first
+    fn names(predictions: &[Prediction]) -> (Vec<String>, Vec<String>) {
+        let name =
+            |p: &Prediction| p.products[0].iupac_name().expect("Name expected");
+        (
+            predictions.iter().filter(|p| p.major).map(name).collect(),
+            predictions.iter().filter(|p| !p.major).map(name).collect(),
+        )
+    }
+
+    fn major(predictions: &[Prediction]) -> Vec<Compound> {
+        predictions
+            .iter()
+            .filter(|p| p.major)
+            .map(|p| p.products[0].clone())
+            .collect()
+    }
+
+    #[test]
+    fn named_reactions_regiochemistry() {
+        use Reagent::*;
+        let propene = build(|b| b.linear_chain(3)?.double_bond(1, 2));
+        let hbr = HydrogenHalide(super::Halogen::Bromine);
+        assert_eq!(
+            names(&predict(&propene, &[hbr])),
+            (vec!["2-bromopropane".into()], vec!["1-bromopropane".into()])
+        );
+        let radical = predict(&propene, &[hbr, Peroxide]);
+        assert!(radical
+            .iter()
+            .all(|p| p.reaction
+                == NamedReaction::AntiMarkovnikovHydrobromination));
+        assert_eq!(names(&radical).0, vec!["1-bromopropane"]);
+        let propane = build(|b| b.linear_chain(3));
+        let bromine = Halogen(super::Halogen::Bromine);
+        assert!(predict(&propane, &[bromine]).is_empty());
+        assert_eq!(
+            names(&predict(&propane, &[bromine, Light])),
+            (vec!["2-bromopropane".into()], vec!["1-bromopropane".into()])
+        );
+
+        // 2-methylpropene
+        let isobutylene =
+            graph(&[6, 6, 6, 6], &[(0, 1, 2), (1, 2, 1), (1, 3, 1)]);
+        let tert_butanol = graph(
+            &[6, 6, 6, 6, 8],
+            &[(0, 1, 1), (0, 2, 1), (0, 3, 1), (0, 4, 1)],
+        );
+        let isobutanol = graph(
+            &[6, 6, 6, 6, 8],
+            &[(0, 1, 1), (1, 2, 1), (1, 3, 1), (0, 4, 1)],
+        );
+        let hydration = major(&predict(&isobutylene, &[AqueousAcid]));
+        assert_eq!(hydration.len(), 1);
+        assert!(hydration[0].is_isomorphic(&tert_butanol));
+        let hydroboration =
+            major(&predict(&isobutylene, &[Borane, BasicPeroxide]));
+        assert_eq!(hydroboration.len(), 1);
+        assert!(hydroboration[0].is_isomorphic(&isobutanol));
+
+        let bromobutane = build(|b| b.linear_chain(4)?.brominate(2));
+        let zaitsev = predict(&bromobutane, &[Alkoxide(AlkylGroup::Ethyl)]);
+        assert!(zaitsev.iter().all(|p| p.reaction == NamedReaction::E2));
+        assert_eq!(
+            names(&zaitsev),
+            (
+                vec!["(E)-but-2-ene".into()],
+                vec!["but-1-ene".into(), "(Z)-but-2-ene".into()]
+            )
+        );
+        let hofmann = predict(&bromobutane, &[Alkoxide(AlkylGroup::TertButyl)]);
+        assert_eq!(names(&hofmann).0, vec!["but-1-ene"]);
+    }
+
+    #[test]
+    fn named_reactions_addition_stereochemistry() {
+        use Reagent::*;
+        let but_2_ene = |descriptor| {
+            CompoundBuilder::new()
+                .linear_chain(4)
+                .and_then(|b| b.stereo_double_bond(2, 3, descriptor))
+                .and_then(|b| b.build())
+                .expect("Valid compound expected")
+        };
+        let bromine = [Halogen(super::Halogen::Bromine)];
+        // Anti addition: E gives the meso compound, Z the racemate
+        let from_e = major(&predict(&but_2_ene(EzDescriptor::E), &bromine));
+        assert_eq!(from_e.len(), 1);
+        assert!(from_e[0].is_meso());
+        let from_z = major(&predict(&but_2_ene(EzDescriptor::Z), &bromine));
+        assert_eq!(from_z.len(), 2);
+        assert_eq!(
+            from_z[0].relationship(&from_z[1]).expect("Configured"),
+            StereoRelationship::Enantiomers
+        );
+        let mut names: Vec<String> = from_z
+            .iter()
+            .map(|p| p.iupac_name().expect("Name"))
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["(2R,3R)-2,3-dibromobutane", "(2S,3S)-2,3-dibromobutane"]
+        );
+
+        // trans-1,2-Dibromocyclohexane, a racemate
+        let cyclohexene = build(|b| b.ring(6)?.double_bond(1, 2));
+        let dibromides = major(&predict(&cyclohexene, &bromine));
+        assert_eq!(dibromides.len(), 2);
+        assert!(dibromides.iter().all(|p| p.is_chiral()));
+
+        // Syn addition gives trans-2-methylcyclohexan-1-ol: (1R,2R)/(1S,2S)
+        let methylcyclohexene = build(|b| {
+            b.ring(6)?
+                .double_bond(1, 2)?
+                .add_alkyl(1, AlkylGroup::Methyl)
+        });
+        let alcohols =
+            major(&predict(&methylcyclohexene, &[Borane, BasicPeroxide]));
+        assert_eq!(alcohols.len(), 2);
+        for alcohol in alcohols.iter() {
+            let descriptors: BTreeSet<CipDescriptor> =
+                alcohol.cip_descriptors().into_values().collect();
+            assert_eq!(descriptors.len(), 1);
+        }
+    }
+
+    #[test]
+    fn named_reactions_substitution() {
+        use Reagent::*;
+        let bromobutane = |descriptor| {
+            CompoundBuilder::new()
+                .linear_chain(4)
+                .and_then(|b| b.brominate(2))
+                .and_then(|b| b.stereocenter(2, descriptor))
+                .and_then(|b| b.build())
+                .expect("Valid compound expected")
+        };
+        // The nitrile keeps the priorities of the bromide, so R becomes S
+        let nitriles = predict(&bromobutane(CipDescriptor::R), &[Cyanide]);
+        assert_eq!(nitriles.len(), 1);
+        let [nitrile, bromide] = &nitriles[0].products[..] else {
+            panic!("Nitrile and bromide expected");
+        };
+        assert_eq!(
+            nitrile.cip_descriptors().into_values().collect::<Vec<_>>(),
+            vec![CipDescriptor::S]
+        );
+        assert_eq!(bromide.charge(), -1);
+
+        let ethoxide = predict(
+            &build(|b| b.linear_chain(3)?.brominate(1)),
+            &[Alkoxide(AlkylGroup::Ethyl)],
+        );
+        let ether = graph(
+            &[6, 6, 6, 8, 6, 6],
+            &[(0, 1, 1), (1, 2, 1), (2, 3, 1), (3, 4, 1), (4, 5, 1)],
+        );
+        assert_eq!(ethoxide[0].reaction, NamedReaction::Sn2);
+        assert!(ethoxide[0].products[0].is_isomorphic(&ether));
+
+        // 3-Bromo-3-methylhexane solvolyses through a planar carbocation
+        let tertiary = build(|b| {
+            b.linear_chain(6)?
+                .brominate(3)?
+                .add_alkyl(3, AlkylGroup::Methyl)
+        });
+        let alcohols = major(&predict(&tertiary, &[Water]));
+        assert_eq!(alcohols.len(), 2);
+        assert_eq!(
+            alcohols[0].relationship(&alcohols[1]).expect("Configured"),
+            StereoRelationship::Enantiomers
+        );
+        let alkenes = predict(&tertiary, &[Water, Heat]);
+        assert!(alkenes.iter().all(|p| p.reaction == NamedReaction::E1));
+        // Both trisubstituted alkenes are Zaitsev products
+        assert_eq!(
+            names(&alkenes).0,
+            vec!["(E)-3-methylhex-2-ene", "(E)-3-methylhex-3-ene"]
+        );
+    }
+
+    #[test]
+    fn named_reactions_carbonyl_chemistry() {
+        use Reagent::*;
+        let propanol =
+            build(|b| b.linear_chain(3)?.substitute(1, Substituent::Hydroxy));
+        let isopropanol =
+            build(|b| b.linear_chain(3)?.substitute(2, Substituent::Hydroxy));
+        let propanal = graph(&[6, 6, 6, 8], &[(0, 1, 1), (1, 2, 1), (2, 3, 2)]);
+        let propanoic_acid = graph(
+            &[6, 6, 6, 8, 8],
+            &[(0, 1, 1), (1, 2, 1), (2, 3, 2), (2, 4, 1)],
+        );
+        let acetone = graph(&[6, 6, 6, 8], &[(0, 1, 1), (1, 2, 1), (1, 3, 2)]);
+        assert!(major(&predict(&propanol, &[Pcc]))[0].is_isomorphic(&propanal));
+        assert!(major(&predict(&propanol, &[ChromicAcid]))[0]
+            .is_isomorphic(&propanoic_acid));
+        for reagent in [Pcc, ChromicAcid] {
+            assert!(major(&predict(&isopropanol, &[reagent]))[0]
+                .is_isomorphic(&acetone));
+        }
+        let tert_butanol = graph(
+            &[6, 6, 6, 6, 8],
+            &[(0, 1, 1), (0, 2, 1), (0, 3, 1), (0, 4, 1)],
+        );
+        assert!(predict(&tert_butanol, &[ChromicAcid]).is_empty());
+
+        let grignard = predict(&acetone, &[Grignard(AlkylGroup::Methyl)]);
+        assert_eq!(grignard.len(), 1);
+        assert!(grignard[0].products[0].is_isomorphic(&tert_butanol));
+        // A new stereocenter forms as a racemate
+        let butanone = graph(
+            &[6, 6, 6, 6, 8],
+            &[(0, 1, 1), (1, 2, 1), (2, 3, 1), (1, 4, 2)],
+        );
+        assert_eq!(
+            predict(&butanone, &[Grignard(AlkylGroup::Propyl)]).len(),
+            2
+        );
+
+        // 2-Methylbut-2-ene gives acetone and acetaldehyde
+        let alkene = build(|b| {
+            b.linear_chain(4)?
+                .double_bond(2, 3)?
+                .add_alkyl(2, AlkylGroup::Methyl)
+        });
+        let ozonolysis = predict(&alkene, &[Ozone, DimethylSulfide]);
+        assert_eq!(ozonolysis.len(), 1);
+        let acetaldehyde = graph(&[6, 6, 8], &[(0, 1, 1), (1, 2, 2)]);
+        let products = &ozonolysis[0].products;
+        assert_eq!(products.len(), 2);
+        assert!(products.iter().any(|p| p.is_isomorphic(&acetone)));
+        assert!(products.iter().any(|p| p.is_isomorphic(&acetaldehyde)));
+    }
+}
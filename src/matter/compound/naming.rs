@@ -0,0 +1,747 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::matter::compound::{
+    bond::BondOrder,
+    stereo::{CipDescriptor, EzDescriptor},
+    Compound, CompoundError, CompoundResult,
+};
+
+/// Stems for parent chains of 1 to 20 carbons
+const STEMS: [&str; 20] = [
+    "meth", "eth", "prop", "but", "pent", "hex", "hept", "oct", "non", "dec",
+    "undec", "dodec", "tridec", "tetradec", "pentadec", "hexadec", "heptadec",
+    "octadec", "nonadec", "icos",
+];
+
+/// Multiplying prefixes for simple substituents (2 to 10 of them)
+const MULTIPLIERS: [&str; 9] = [
+    "di", "tri", "tetra", "penta", "hexa", "hepta", "octa", "nona", "deca",
+];
+
+/// Multiplying prefixes for complex (parenthesized) substituents
+const COMPLEX_MULTIPLIERS: [&str; 9] = [
+    "bis", "tris", "tetrakis", "pentakis", "hexakis", "heptakis", "octakis",
+    "nonakis", "decakis",
+];
+
+/// Substituent prefix and the locant of the parent atom carrying it
+type Prefix = (usize, String);
+
+fn stem(carbons: usize) -> CompoundResult<&'static str> {
+    STEMS.get(carbons.wrapping_sub(1)).copied().ok_or_else(|| {
+        CompoundError::Naming(format!(
+            "No stem for a chain of {} carbons",
+            carbons
+        ))
+    })
+}
+
+fn halo_prefix(element_num: u8) -> Option<&'static str> {
+    match element_num {
+        9 => Some("fluoro"),
+        17 => Some("chloro"),
+        35 => Some("bromo"),
+        53 => Some("iodo"),
+        _ => None,
+    }
+}
+
+/// Letters a prefix is alphabetized by: multiplying prefixes and the
+/// italic `sec-`/`tert-` are ignored, except inside complex substituents.
+fn alphabetical_key(prefix: &str) -> String {
+    let prefix = prefix
+        .strip_prefix("tert-")
+        .or_else(|| prefix.strip_prefix("sec-"))
+        .unwrap_or(prefix);
+    prefix.chars().filter(|c| c.is_ascii_alphabetic()).collect()
+}
+
+/// Writes prefixes alphabetically, grouping repeated ones behind a
+/// multiplying prefix (`2,2-dimethyl`). Without locants the prefixes run
+/// together (`bromochloro`).
+fn assemble_prefixes(
+    prefixes: &[Prefix],
+    locants: bool,
+) -> CompoundResult<String> {
+    let mut grouped: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (locant, name) in prefixes {
+        grouped.entry(name.clone()).or_default().push(*locant);
+    }
+    let mut groups: Vec<(String, Vec<usize>)> = grouped.into_iter().collect();
+    groups.sort_by_key(|(name, _)| alphabetical_key(name));
+    let mut pieces = Vec::new();
+    for (name, mut positions) in groups {
+        positions.sort();
+        let multiplier = match positions.len() {
+            1 => "",
+            n => {
+                let table = if name.starts_with('(') {
+                    &COMPLEX_MULTIPLIERS
+                } else {
+                    &MULTIPLIERS
+                };
+                table.get(n - 2).copied().ok_or_else(|| {
+                    CompoundError::Naming(format!(
+                        "Too many {} substituents",
+                        name
+                    ))
+                })?
+            }
+        };
+        if locants {
+            let positions: Vec<String> =
+                positions.iter().map(|p| p.to_string()).collect();
+            pieces.push(format!(
+                "{}-{}{}",
+                positions.join(","),
+                multiplier,
+                name
+            ));
+        } else {
+            pieces.push(format!("{}{}", multiplier, name));
+        }
+    }
+    Ok(pieces.join(if locants { "-" } else { "" }))
+}
+
+/// How well a numbering of the parent fits the IUPAC rules; lower is better.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct NumberingRank {
+    /// More substituents first
+    fewer_substituents: usize,
+    /// Lowest locants to the double bonds
+    double_bonds: Vec<usize>,
+    /// Lowest locants
+    locants: Vec<usize>,
+    /// Lowest locants to the substituents cited first
+    alphabetical_locants: Vec<usize>,
+}
+
+impl NumberingRank {
+    fn of(prefixes: &[Prefix], double_bonds: Vec<usize>) -> Self {
+        let mut locants: Vec<usize> = prefixes.iter().map(|p| p.0).collect();
+        locants.sort();
+        let mut alphabetical: Vec<&Prefix> = prefixes.iter().collect();
+        alphabetical
+            .sort_by_key(|(locant, name)| (alphabetical_key(name), *locant));
+        Self {
+            fewer_substituents: usize::MAX - prefixes.len(),
+            double_bonds,
+            locants,
+            alphabetical_locants: alphabetical.iter().map(|p| p.0).collect(),
+        }
+    }
+}
+
+struct Namer<'a> {
+    compound: &'a Compound,
+    adjacency: Vec<Vec<usize>>,
+    double_bonds: Vec<(usize, usize)>,
+}
+
+impl<'a> Namer<'a> {
+    fn new(compound: &'a Compound) -> CompoundResult<Self> {
+        let unsupported = |reason: String| Err(CompoundError::Naming(reason));
+        for (i, atom) in compound.atoms().iter().enumerate() {
+            let z = atom.get_element_num();
+            if atom.charge() != 0 {
+                return unsupported(format!(
+                    "Atom {} ({}) is charged",
+                    i, atom
+                ));
+            } else if z != 1 && z != 6 && halo_prefix(z).is_none() {
+                return unsupported(format!(
+                    "Compounds with {} can't be named yet",
+                    atom
+                ));
+            }
+        }
+        for (&(a, b), &order) in compound.bond_orders().iter() {
+            let carbons = [a, b]
+                .iter()
+                .all(|&i| compound.atoms()[i].get_element_num() == 6);
+            match order {
+                BondOrder::Single => (),
+                BondOrder::Double if carbons => (),
+                _ => {
+                    return unsupported(
+                        "Only C=C double bonds can be named yet".into(),
+                    )
+                }
+            }
+        }
+        if compound.is_aromatic()? {
+            return unsupported("Aromatic compounds can't be named yet".into());
+        }
+        let double_bonds = compound
+            .bond_orders()
+            .into_iter()
+            .filter(|&(_, order)| order == BondOrder::Double)
+            .map(|(bond, _)| bond)
+            .collect();
+        Ok(Self {
+            compound,
+            adjacency: compound.adjacency(),
+            double_bonds,
+        })
+    }
+
+    fn is_carbon(&self, i: usize) -> bool {
+        self.compound.atoms()[i].get_element_num() == 6
+    }
+
+    fn carbon_neighbors(&self, i: usize) -> Vec<usize> {
+        self.adjacency[i]
+            .iter()
+            .copied()
+            .filter(|&j| self.is_carbon(j))
+            .collect()
+    }
+
+    /// Substituents on the given chain, numbered from 1 along it. A
+    /// branch's chain leaves out the atom `from` it hangs off.
+    fn prefixes(
+        &self,
+        chain: &[usize],
+        from: Option<usize>,
+    ) -> CompoundResult<Vec<Prefix>> {
+        let mut prefixes = Vec::new();
+        for (k, &i) in chain.iter().enumerate() {
+            for &j in self.adjacency[i].iter() {
+                if chain.contains(&j) || Some(j) == from {
+                    continue;
+                }
+                let z = self.compound.atoms()[j].get_element_num();
+                if let Some(halo) = halo_prefix(z) {
+                    prefixes.push((k + 1, halo.to_string()));
+                } else if z == 6 {
+                    prefixes.push((k + 1, self.alkyl_name(j, i)?));
+                }
+            }
+        }
+        Ok(prefixes)
+    }
+
+    /// Carbons of the branch hanging off `from` at `root`
+    fn branch_carbons(&self, root: usize, from: usize) -> BTreeSet<usize> {
+        let mut seen = BTreeSet::from([from, root]);
+        let mut queue = VecDeque::from([root]);
+        while let Some(i) = queue.pop_front() {
+            for j in self.carbon_neighbors(i) {
+                if seen.insert(j) {
+                    queue.push_back(j);
+                }
+            }
+        }
+        seen.remove(&from);
+        seen
+    }
+
+    /// Chains running from `root` to the far ends of its branch
+    fn chains_from(&self, root: usize, from: usize) -> Vec<Vec<usize>> {
+        let children: Vec<usize> = self
+            .carbon_neighbors(root)
+            .into_iter()
+            .filter(|&j| j != from)
+            .collect();
+        if children.is_empty() {
+            return vec![vec![root]];
+        }
+        children
+            .into_iter()
+            .flat_map(|child| self.chains_from(child, root))
+            .map(|mut chain| {
+                chain.insert(0, root);
+                chain
+            })
+            .collect()
+    }
+
+    /// Prefix for the alkyl branch at `root`, attached to `from`
+    fn alkyl_name(&self, root: usize, from: usize) -> CompoundResult<String> {
+        let carbons = self.branch_carbons(root, from);
+        if self
+            .double_bonds
+            .iter()
+            .any(|(a, b)| carbons.contains(a) || carbons.contains(b))
+        {
+            return Err(CompoundError::Naming(
+                "Unsaturated substituents can't be named yet".into(),
+            ));
+        }
+        let halogenated = carbons.iter().any(|&i| {
+            self.adjacency[i].iter().any(|&j| {
+                halo_prefix(self.compound.atoms()[j].get_element_num())
+                    .is_some()
+            })
+        });
+        let children = |i: usize, parent: usize| -> Vec<usize> {
+            self.carbon_neighbors(i)
+                .into_iter()
+                .filter(|&j| j != parent)
+                .collect()
+        };
+        let is_methyl =
+            |i: usize, parent: usize| children(i, parent).is_empty();
+        let top = children(root, from);
+        if !halogenated {
+            let common = match (carbons.len(), top.len()) {
+                (3, 2) => Some("isopropyl"),
+                (4, 3) => Some("tert-butyl"),
+                (4, 1) if children(top[0], root).len() == 2 => Some("isobutyl"),
+                (4, 2) if top.iter().any(|&c| is_methyl(c, root)) => {
+                    Some("sec-butyl")
+                }
+                _ => None,
+            };
+            if let Some(common) = common {
+                return Ok(common.into());
+            }
+        }
+
+        let mut best: Option<(NumberingRank, Vec<Prefix>, usize)> = None;
+        let longest = self
+            .chains_from(root, from)
+            .into_iter()
+            .map(|c| c.len())
+            .max()
+            .unwrap_or(1);
+        for chain in self.chains_from(root, from) {
+            if chain.len() != longest {
+                continue;
+            }
+            let prefixes = self.prefixes(&chain, Some(from))?;
+            let rank = NumberingRank::of(&prefixes, Vec::new());
+            if best.as_ref().is_none_or(|(b, _, _)| rank < *b) {
+                best = Some((rank, prefixes, chain.len()));
+            }
+        }
+        let (_, prefixes, length) = best.expect("Every branch has a chain");
+        let name = format!("{}yl", stem(length)?);
+        if prefixes.is_empty() {
+            Ok(name)
+        } else {
+            Ok(format!("({}{})", assemble_prefixes(&prefixes, true)?, name))
+        }
+    }
+
+    /// Atoms of the only ring, in order around it
+    fn ring(&self) -> CompoundResult<Option<Vec<usize>>> {
+        let mut rings = self.compound.rings().sssr;
+        if rings.len() > 1 {
+            return Err(CompoundError::Naming(
+                "Polycyclic compounds can't be named yet".into(),
+            ));
+        }
+        Ok(rings.pop())
+    }
+
+    /// Locants of the double bonds along a chain (or ring)
+    fn double_bond_locants(&self, chain: &[usize]) -> Vec<usize> {
+        let mut locants: Vec<usize> = self
+            .double_bonds
+            .iter()
+            .filter_map(|&(a, b)| bond_locant(chain, a, b))
+            .collect();
+        locants.sort();
+        locants
+    }
+
+    /// Chains holding the most double bonds, then the longest ones, each
+    /// in both directions
+    fn parent_chains(&self, carbons: &[usize]) -> Vec<Vec<usize>> {
+        let ends: Vec<usize> = carbons
+            .iter()
+            .copied()
+            .filter(|&i| self.carbon_neighbors(i).len() <= 1)
+            .collect();
+        let chains: Vec<Vec<usize>> = ends
+            .iter()
+            .flat_map(|&end| self.chains_from(end, usize::MAX))
+            .collect();
+        let size = |chain: &Vec<usize>| {
+            (self.double_bond_locants(chain).len(), chain.len())
+        };
+        let largest = chains.iter().map(size).max().unwrap_or((0, 0));
+        chains.into_iter().filter(|c| size(c) == largest).collect()
+    }
+
+    fn name(&self) -> CompoundResult<String> {
+        let carbons: Vec<usize> = (0..self.compound.atom_count())
+            .filter(|&i| self.is_carbon(i))
+            .collect();
+        let Some(&first) = carbons.first() else {
+            return Err(CompoundError::Naming("No carbon to name from".into()));
+        };
+        if self.branch_carbons(first, usize::MAX).len() != carbons.len() {
+            return Err(CompoundError::Naming(
+                "Carbons don't form a single skeleton".into(),
+            ));
+        }
+
+        let (candidates, cyclic) = match self.ring()? {
+            Some(ring) => {
+                let n = ring.len();
+                let numberings = (0..n)
+                    .flat_map(|start| {
+                        let ring = &ring;
+                        [
+                            (0..n).map(|k| ring[(start + k) % n]).collect(),
+                            (0..n).map(|k| ring[(start + n - k) % n]).collect(),
+                        ]
+                    })
+                    .collect::<Vec<Vec<usize>>>();
+                (numberings, true)
+            }
+            None => (self.parent_chains(&carbons), false),
+        };
+        let mut best: Option<(_, Vec<Prefix>, Vec<Stereo>, Vec<usize>)> = None;
+        for chain in candidates {
+            let prefixes = self.prefixes(&chain, None)?;
+            let double_bonds = self.double_bond_locants(&chain);
+            if double_bonds.len() != self.double_bonds.len() {
+                return Err(CompoundError::Naming(
+                    "Double bonds off the parent can't be named yet".into(),
+                ));
+            }
+            let stereo = self.stereo_locants(&chain)?;
+            // Left with a choice, Z takes the lower locant over E, and R
+            // over S
+            let rank = (
+                NumberingRank::of(&prefixes, double_bonds.clone()),
+                stereo.iter().map(|&(_, d)| d).collect::<Vec<_>>(),
+            );
+            if best.as_ref().is_none_or(|(b, _, _, _)| rank < *b) {
+                best = Some((rank, prefixes, stereo, chain));
+            }
+        }
+        let (rank, prefixes, stereo, chain) =
+            best.expect("Skeleton has a parent");
+        let (length, double_bonds) = (chain.len(), rank.0.double_bonds);
+        let cyclo = if cyclic { "cyclo" } else { "" };
+        let parent = match double_bonds.len() {
+            0 => format!("{}{}ane", cyclo, stem(length)?),
+            // The double bond can only be numbered 1
+            1 if cyclic || length <= 3 => {
+                format!("{}{}ene", cyclo, stem(length)?)
+            }
+            1 => format!("{}-{}-ene", stem(length)?, double_bonds[0]),
+            n => {
+                let locants: Vec<String> =
+                    double_bonds.iter().map(|l| l.to_string()).collect();
+                format!(
+                    "{}{}a-{}-{}ene",
+                    cyclo,
+                    stem(length)?,
+                    locants.join(","),
+                    MULTIPLIERS[n - 2]
+                )
+            }
+        };
+        // Locants are dropped where the position is never in doubt
+        let ring_of_one = cyclic && double_bonds.is_empty();
+        let locants = !(length == 1
+            || (prefixes.len() == 1 && (ring_of_one || length == 2)));
+        Ok(format!(
+            "{}{}{}",
+            stereo_prefix(&stereo),
+            assemble_prefixes(&prefixes, locants)?,
+            parent
+        ))
+    }
+}
+
+/// Locant of the bond between `a` and `b` along a chain (or ring),
+/// numbered from its lower end
+fn bond_locant(chain: &[usize], a: usize, b: usize) -> Option<usize> {
+    let p = chain.iter().position(|&i| i == a)?;
+    let q = chain.iter().position(|&i| i == b)?;
+    match p.abs_diff(q) {
+        1 => Some(p.min(q) + 1),
+        // Closing a ring from its last atom
+        d if d == chain.len() - 1 => Some(chain.len()),
+        _ => None,
+    }
+}
+
+/// Stereodescriptor cited in front of a name
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, strum_macros::Display,
+)]
+enum Descriptor {
+    #[strum(to_string = "{0}")]
+    DoubleBond(EzDescriptor),
+    #[strum(to_string = "{0}")]
+    Stereocenter(CipDescriptor),
+}
+
+/// Locant of a stereo element on the parent and its descriptor
+type Stereo = (usize, Descriptor);
+
+impl Namer<'_> {
+    /// Locants of the stereocenters and stereogenic double bonds along the
+    /// given parent, lowest first
+    fn stereo_locants(&self, chain: &[usize]) -> CompoundResult<Vec<Stereo>> {
+        let off_parent = || {
+            CompoundError::Naming(
+                "Stereocenters off the parent can't be named yet".into(),
+            )
+        };
+        let mut stereo = Vec::new();
+        for (i, descriptor) in self.compound.cip_descriptors() {
+            let k =
+                chain.iter().position(|&j| j == i).ok_or_else(off_parent)?;
+            stereo.push((k + 1, Descriptor::Stereocenter(descriptor)));
+        }
+        for ((a, b), descriptor) in self.compound.ez_descriptors() {
+            let locant = bond_locant(chain, a, b).ok_or_else(off_parent)?;
+            stereo.push((locant, Descriptor::DoubleBond(descriptor)));
+        }
+        stereo.sort();
+        Ok(stereo)
+    }
+}
+
+/// `(R)-` for a lone stereocenter, `(2R,3S)-` for several
+fn stereo_prefix(stereo: &[Stereo]) -> String {
+    match stereo {
+        [] => String::new(),
+        [(_, descriptor)] => format!("({})-", descriptor),
+        _ => {
+            let descriptors: Vec<String> = stereo
+                .iter()
+                .map(|(locant, descriptor)| format!("{}{}", locant, descriptor))
+                .collect();
+            format!("({})-", descriptors.join(","))
+        }
+    }
+}
+
+impl Compound {
+    /// IUPAC name of the compound.
+    ///
+    /// Covers alkanes and alkenes with a chain or a single ring as the
+    /// parent, carrying alkyl and halogen substituents. Recorded
+    /// configurations are cited in front, as in `(E)-but-2-ene` or
+    /// `(R)-2-bromobutane`.
+    pub fn iupac_name(&self) -> CompoundResult<String> {
+        Namer::new(self)?.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matter::compound::{
+        builder::{BuildStep, CompoundBuilder},
+        substituent::{AlkylGroup, Substituent},
+    };
+
+    use super::*;
+
+    fn name(build: BuildStep) -> CompoundResult<String> {
+        let mut builder = CompoundBuilder::new();
+        build(&mut builder)
+            .and_then(|b| b.build())
+            .expect("Valid compound expected")
+            .iupac_name()
+    }
+
+    #[test]
+    fn naming_alkanes() {
+        use AlkylGroup::*;
+        let cases: [(BuildStep, &str); 7] = [
+            (|b| b.linear_chain(1), "methane"),
+            (|b| b.linear_chain(6), "hexane"),
+            (
+                |b| b.linear_chain(4)?.add_alkyl(3, Methyl),
+                "2-methylbutane",
+            ),
+            (
+                |b| b.linear_chain(4)?.add_alkyl(2, Ethyl),
+                "3-methylpentane",
+            ),
+            (
+                |b| {
+                    b.linear_chain(3)?
+                        .add_alkyl(2, Methyl)?
+                        .add_alkyl(2, Methyl)
+                },
+                "2,2-dimethylpropane",
+            ),
+            (
+                |b| b.linear_chain(7)?.add_alkyl(4, Isopropyl),
+                "4-isopropylheptane",
+            ),
+            (
+                |b| b.linear_chain(9)?.add_alkyl(5, Isobutyl),
+                "5-isobutylnonane",
+            ),
+        ];
+        for (build, expected) in cases {
+            assert_eq!(name(build).expect("Name expected"), expected);
+        }
+    }
+
+    #[test]
+    fn naming_haloalkanes() {
+        assert_eq!(
+            name(|b| b.linear_chain(4)?.brominate(2)).expect("Name"),
+            "2-bromobutane"
+        );
+        assert_eq!(
+            name(|b| b
+                .linear_chain(3)?
+                .add_alkyl(2, AlkylGroup::Methyl)?
+                .brominate(1))
+            .expect("Name"),
+            "1-bromo-2-methylpropane"
+        );
+        assert_eq!(
+            name(|b| b
+                .linear_chain(1)?
+                .substitute(1, Substituent::Chloro)?
+                .substitute(1, Substituent::Chloro))
+            .expect("Name"),
+            "dichloromethane"
+        );
+    }
+
+    #[test]
+    fn naming_cycloalkanes() {
+        use AlkylGroup::Methyl;
+        assert_eq!(name(|b| b.ring(6)).expect("Name"), "cyclohexane");
+        assert_eq!(
+            name(|b| b.ring(5)?.add_alkyl(1, Methyl)).expect("Name"),
+            "methylcyclopentane"
+        );
+        assert_eq!(
+            name(|b| b.ring(6)?.add_alkyl(2, Methyl)?.add_alkyl(6, Methyl))
+                .expect("Name"),
+            "1,3-dimethylcyclohexane"
+        );
+        assert_eq!(
+            name(|b| b.ring(6)?.add_alkyl(4, Methyl)?.add_alkyl(4, Methyl))
+                .expect("Name"),
+            "1,1-dimethylcyclohexane"
+        );
+    }
+
+    #[test]
+    fn naming_stereocenters() {
+        use CipDescriptor::*;
+        assert_eq!(
+            name(|b| b.linear_chain(4)?.brominate(2)?.stereocenter(2, R))
+                .expect("Name"),
+            "(R)-2-bromobutane"
+        );
+        assert_eq!(
+            name(|b| b.linear_chain(4)?.brominate(3)?.stereocenter(3, S))
+                .expect("Name"),
+            "(S)-2-bromobutane"
+        );
+        // meso: numbered from the end giving R the lower locant
+        for (first, second) in [(R, S), (S, R)] {
+            assert_eq!(
+                CompoundBuilder::new()
+                    .linear_chain(4)
+                    .and_then(|b| b.brominate(2))
+                    .and_then(|b| b.brominate(3))
+                    .and_then(|b| b.stereocenter(2, first))
+                    .and_then(|b| b.stereocenter(3, second))
+                    .and_then(|b| b.build())
+                    .expect("Valid compound expected")
+                    .iupac_name()
+                    .expect("Name"),
+                "(2R,3S)-2,3-dibromobutane"
+            );
+        }
+        assert_eq!(
+            name(|b| b
+                .linear_chain(4)?
+                .brominate(2)?
+                .brominate(3)?
+                .stereocenter(2, S)?
+                .stereocenter(3, S))
+            .expect("Name"),
+            "(2S,3S)-2,3-dibromobutane"
+        );
+    }
+
+    #[test]
+    fn naming_alkenes() {
+        use AlkylGroup::{Ethyl, Methyl};
+        let cases: [(BuildStep, &str); 9] = [
+            (|b| b.linear_chain(2)?.double_bond(1, 2), "ethene"),
+            (|b| b.linear_chain(3)?.double_bond(2, 3), "propene"),
+            (|b| b.linear_chain(4)?.double_bond(3, 4), "but-1-ene"),
+            (
+                |b| b.linear_chain(4)?.double_bond(1, 2)?.double_bond(3, 4),
+                "buta-1,3-diene",
+            ),
+            (
+                |b| b.linear_chain(4)?.double_bond(2, 3)?.add_alkyl(2, Methyl),
+                "2-methylbut-2-ene",
+            ),
+            // The parent holds the double bond, though not the longest
+            (
+                |b| b.linear_chain(4)?.double_bond(1, 2)?.add_alkyl(2, Ethyl),
+                "2-ethylbut-1-ene",
+            ),
+            (|b| b.ring(6)?.double_bond(3, 4), "cyclohexene"),
+            (
+                |b| b.ring(6)?.double_bond(1, 2)?.add_alkyl(4, Methyl),
+                "4-methylcyclohexene",
+            ),
+            (
+                |b| b.ring(6)?.double_bond(1, 2)?.double_bond(3, 4),
+                "cyclohexa-1,3-diene",
+            ),
+        ];
+        for (build, expected) in cases {
+            assert_eq!(name(build).expect("Name expected"), expected);
+        }
+    }
+
+    #[test]
+    fn naming_double_bond_stereo() {
+        use EzDescriptor::*;
+        assert_eq!(
+            name(|b| b.linear_chain(4)?.stereo_double_bond(2, 3, E))
+                .expect("Name"),
+            "(E)-but-2-ene"
+        );
+        assert_eq!(
+            name(|b| b.linear_chain(4)?.stereo_double_bond(2, 3, Z))
+                .expect("Name"),
+            "(Z)-but-2-ene"
+        );
+        // Z takes the lower locant
+        assert_eq!(
+            name(|b| b
+                .linear_chain(6)?
+                .stereo_double_bond(2, 3, E)?
+                .stereo_double_bond(4, 5, Z))
+            .expect("Name"),
+            "(2Z,4E)-hexa-2,4-diene"
+        );
+        // The double bond takes the lower locants
+        assert_eq!(
+            name(|b| b
+                .linear_chain(5)?
+                .stereo_double_bond(3, 4, E)?
+                .brominate(2)?
+                .stereocenter(2, CipDescriptor::R))
+            .expect("Name"),
+            "(2E,4R)-4-bromopent-2-ene"
+        );
+    }
+
+    #[test]
+    fn naming_unsupported_compounds() {
+        let decalin = name(|b| b.ring(10)?.ring_closure(1, 6));
+        assert!(matches!(decalin, Err(CompoundError::Naming(_))));
+        let ethanol =
+            name(|b| b.linear_chain(2)?.substitute(2, Substituent::Hydroxy));
+        assert!(matches!(ethanol, Err(CompoundError::Naming(_))));
+    }
+}
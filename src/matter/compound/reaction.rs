@@ -0,0 +1,645 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    str::FromStr,
+};
+
+use crate::matter::{
+    atom::Atom,
+    compound::{
+        bond::{bond_key, BondOrder},
+        reindex::Reindex,
+        smarts::{
+            AtomExpr, AtomPrimitive, BondExpr, BondPrimitive, Expr, Smarts,
+        },
+        substructure::AtomMapping,
+        Compound, CompoundError, CompoundResult,
+    },
+};
+
+/// Transformation written as reactant and product templates whose atoms
+/// are matched up by atom map numbers, as in reaction SMARTS
+/// (`[C:1]=[C:2]>>[C:1][C:2]Br`).
+///
+/// Applying it follows the usual template rules:
+/// - mapped atoms carry over, taking any element, charge or hydrogen count
+///   written on their product atom
+/// - reactant atoms without a map number in the products are deleted
+/// - product atoms without a map number are created
+/// - bonds between matched atoms are replaced by the product's bonds, and
+///   everything outside the match is left alone
+///
+/// Atoms whose bonds change get their implicit hydrogens refilled up to
+/// their valence, so swapping a hydrogen for a bromine is just
+/// `[C;!H0:1]>>[C:1]Br`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reaction {
+    reactants: Smarts,
+    products: Smarts,
+}
+
+/// One set of products of a [`Reaction`], and how the reactants' atoms
+/// map onto them
+#[derive(Clone, Debug)]
+pub struct ReactionOutcome {
+    pub products: Vec<Compound>,
+    /// Product and atom index each surviving reactant atom ended up at,
+    /// keyed by reactant and atom index (plain hydrogens aside)
+    pub atoms: BTreeMap<(usize, usize), (usize, usize)>,
+    /// Reactant and atom index matched by each mapped reactant template
+    /// atom, by map number
+    pub mapped: BTreeMap<u16, (usize, usize)>,
+}
+
+/// What a product template atom sets on the atom it gives
+#[derive(Clone, Copy, Debug, Default)]
+struct AtomSpec {
+    number: Option<u8>,
+    isotope: Option<u16>,
+    charge: Option<i8>,
+    hydrogens: Option<u8>,
+}
+
+impl AtomSpec {
+    /// Primitives anded together at the top of the expression
+    fn of(expr: &AtomExpr) -> Self {
+        let mut spec = Self::default();
+        let mut stack = vec![expr];
+        while let Some(expr) = stack.pop() {
+            match expr {
+                Expr::And(parts) => stack.extend(parts.iter()),
+                Expr::Primitive(primitive) => match *primitive {
+                    AtomPrimitive::Symbol { number, .. }
+                    | AtomPrimitive::AtomicNumber(number) => {
+                        spec.number = Some(number)
+                    }
+                    AtomPrimitive::Isotope(mass) => spec.isotope = Some(mass),
+                    AtomPrimitive::Charge(charge) => spec.charge = Some(charge),
+                    AtomPrimitive::Hydrogens(h) => spec.hydrogens = Some(h),
+                    _ => {}
+                },
+                Expr::Not(_) | Expr::Or(_) => {}
+            }
+        }
+        spec
+    }
+
+    /// `atom` as the product template describes it
+    fn apply(&self, atom: &Atom) -> Atom {
+        let mut atom = match self.number {
+            Some(number) if number != atom.get_element_num() => {
+                Atom::new_unchecked(number).ion(atom.charge())
+            }
+            _ => atom.clone(),
+        };
+        if let Some(charge) = self.charge {
+            atom = atom.ion(charge);
+        }
+        if let Some(mass) = self.isotope {
+            let neutrons = mass.saturating_sub(atom.get_element_num() as u16);
+            atom = atom.isotope(neutrons as u8);
+        }
+        atom
+    }
+}
+
+/// Order a product template bond sets. Bonds written without a definite
+/// order on both sides keep the order they had; otherwise they're single.
+fn bond_order(expr: &BondExpr) -> Option<BondOrder> {
+    match expr {
+        Expr::Primitive(BondPrimitive::Single) => Some(BondOrder::Single),
+        Expr::Primitive(BondPrimitive::Double) => Some(BondOrder::Double),
+        Expr::Primitive(BondPrimitive::Triple) => Some(BondOrder::Triple),
+        _ => None,
+    }
+}
+
+/// Reactants pooled into one graph, hydrogens implicit and bonds in their
+/// Kekulé orders
+#[derive(Clone)]
+struct ReactionGraph {
+    atoms: Vec<Atom>,
+    bonds: BTreeMap<(usize, usize), BondOrder>,
+    hydrogens: Vec<u8>,
+    chirality: HashMap<usize, [usize; 4]>,
+    double_bond_stereo: HashMap<(usize, usize), [usize; 2]>,
+    /// Reactant and atom index each atom comes from; `None` for atoms the
+    /// reaction creates
+    origins: Vec<Option<(usize, usize)>>,
+}
+
+impl ReactionGraph {
+    fn of(compounds: &[Compound]) -> Self {
+        let mut graph = Self {
+            atoms: Vec::new(),
+            bonds: BTreeMap::new(),
+            hydrogens: Vec::new(),
+            chirality: HashMap::new(),
+            double_bond_stereo: HashMap::new(),
+            origins: Vec::new(),
+        };
+        for (r, compound) in compounds.iter().enumerate() {
+            let folded = compound.foldable_hydrogens();
+            graph.origins.extend(
+                (0..compound.atom_count())
+                    .filter(|i| !folded.contains_key(i))
+                    .map(|i| Some((r, i))),
+            );
+            let compound = compound.with_implicit_hydrogens();
+            let offset = graph.atoms.len();
+            graph.hydrogens.extend(
+                (0..compound.atom_count())
+                    .map(|i| compound.implicit_hydrogens(i)),
+            );
+            graph.atoms.extend(compound.atoms.iter().cloned());
+            graph.bonds.extend(
+                compound
+                    .bond_orders()
+                    .into_iter()
+                    .map(|((a, b), order)| ((a + offset, b + offset), order)),
+            );
+            graph.chirality.extend(
+                compound.chirality.iter().map(|(&i, around)| {
+                    (i + offset, around.map(|j| j + offset))
+                }),
+            );
+            graph.double_bond_stereo.extend(
+                compound.double_bond_stereo.iter().map(|(&(a, b), cis)| {
+                    ((a + offset, b + offset), cis.map(|j| j + offset))
+                }),
+            );
+        }
+        graph
+    }
+
+    fn neighbors(&self, i: usize) -> BTreeMap<usize, BondOrder> {
+        self.bonds
+            .iter()
+            .filter_map(|(&(a, b), &order)| match i {
+                _ if a == i => Some((b, order)),
+                _ if b == i => Some((a, order)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Atoms grouped into connected fragments, each listed in order
+    fn fragments(&self) -> Vec<Vec<usize>> {
+        let mut parent: Vec<usize> = (0..self.atoms.len()).collect();
+        fn root(parent: &mut [usize], i: usize) -> usize {
+            let mut i = i;
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        for &(a, b) in self.bonds.keys() {
+            let (ra, rb) = (root(&mut parent, a), root(&mut parent, b));
+            parent[ra.max(rb)] = ra.min(rb);
+        }
+        let mut fragments: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for i in 0..self.atoms.len() {
+            let r = root(&mut parent, i);
+            fragments.entry(r).or_default().push(i);
+        }
+        fragments.into_values().collect()
+    }
+
+    /// Compound made of the given atoms, bonded as in the graph
+    fn compound(&self, atoms: &[usize]) -> Compound {
+        let local: HashMap<usize, usize> =
+            atoms.iter().enumerate().map(|(k, &i)| (i, k)).collect();
+        let bonds: BTreeMap<(usize, usize), BondOrder> = self
+            .bonds
+            .iter()
+            .filter_map(|(&(a, b), &order)| {
+                Some((bond_key(*local.get(&a)?, *local.get(&b)?), order))
+            })
+            .collect();
+        let mut compound = Compound::from_bonds(
+            atoms.iter().map(|&i| self.atoms[i].clone()).collect(),
+            &bonds,
+        );
+        compound.implicit_hydrogens = atoms
+            .iter()
+            .enumerate()
+            .map(|(k, &i)| (k, self.hydrogens[i]))
+            .filter(|&(_, h)| h > 0)
+            .collect();
+        compound.chirality = self
+            .chirality
+            .iter()
+            .filter_map(|(i, around)| {
+                let around = around.map(|j| local.get(&j).copied());
+                Some((
+                    *local.get(i)?,
+                    around.map(|j| j.expect("Same fragment")),
+                ))
+            })
+            .collect();
+        compound.double_bond_stereo = self
+            .double_bond_stereo
+            .iter()
+            .filter_map(|((a, b), cis)| {
+                let key = (*local.get(a)?, *local.get(b)?);
+                Some((key, cis.map(|j| local[&j])))
+            })
+            .collect();
+        compound
+    }
+
+    /// Drops the given atoms along with their bonds and stereo records
+    fn remove(&mut self, removed: &BTreeSet<usize>) {
+        let reindex = Reindex::removing(self.atoms.len(), removed);
+        self.atoms = reindex.items(std::mem::take(&mut self.atoms));
+        self.hydrogens = reindex.items(std::mem::take(&mut self.hydrogens));
+        self.origins = reindex.items(std::mem::take(&mut self.origins));
+        self.bonds = self
+            .bonds
+            .iter()
+            .filter_map(|(&(a, b), &order)| {
+                Some((bond_key(reindex.get(a)?, reindex.get(b)?), order))
+            })
+            .collect();
+        self.chirality = self
+            .chirality
+            .iter()
+            .filter_map(|(&i, around)| {
+                let mut mapped = [0; 4];
+                for (slot, &j) in mapped.iter_mut().zip(around.iter()) {
+                    *slot = reindex.get(j)?;
+                }
+                Some((reindex.get(i)?, mapped))
+            })
+            .collect();
+        self.double_bond_stereo = self
+            .double_bond_stereo
+            .iter()
+            .filter_map(|(&(a, b), &[x, y])| {
+                Some((
+                    (reindex.get(a)?, reindex.get(b)?),
+                    [reindex.get(x)?, reindex.get(y)?],
+                ))
+            })
+            .collect();
+    }
+}
+
+impl Reaction {
+    /// Reaction from its reactant and product templates.
+    ///
+    /// A map number may appear once on each side, and atoms created by
+    /// the reaction need an element.
+    pub fn new(reactants: Smarts, products: Smarts) -> CompoundResult<Self> {
+        for (side, template) in
+            [("reactant", &reactants), ("product", &products)]
+        {
+            let mut seen = BTreeSet::new();
+            for map in (0..template.atom_count())
+                .filter_map(|i| template.map_number(i))
+            {
+                if !seen.insert(map) {
+                    return Err(CompoundError::Reaction(format!(
+                        "Map number {} appears twice in the {} template",
+                        map, side
+                    )));
+                }
+            }
+        }
+        let reaction = Self {
+            reactants,
+            products,
+        };
+        for q in 0..reaction.products.atom_count() {
+            if reaction.reactant_of(q).is_none()
+                && AtomSpec::of(&reaction.products.atoms()[q]).number.is_none()
+            {
+                return Err(CompoundError::Reaction(format!(
+                    "Product atom {} is created without an element",
+                    q
+                )));
+            }
+        }
+        Ok(reaction)
+    }
+
+    /// Reaction SMARTS, `reactants>>products` (agents between the arrows
+    /// are ignored)
+    pub fn parse(reaction: &str) -> CompoundResult<Self> {
+        let parts: Vec<&str> = reaction.split('>').collect();
+        let [reactants, _, products] = parts[..] else {
+            return Err(CompoundError::Parsing(format!(
+                "Reaction SMARTS `{}` expects `reactants>>products`",
+                reaction
+            )));
+        };
+        Self::new(Smarts::parse(reactants)?, Smarts::parse(products)?)
+    }
+
+    pub fn reactants(&self) -> &Smarts {
+        &self.reactants
+    }
+
+    pub fn products(&self) -> &Smarts {
+        &self.products
+    }
+
+    /// Reactant template atom with the same map number as product atom `q`
+    fn reactant_of(&self, q: usize) -> Option<usize> {
+        let map = self.products.map_number(q)?;
+        (0..self.reactants.atom_count())
+            .find(|&p| self.reactants.map_number(p) == Some(map))
+    }
+
+    /// Every distinct set of products the reaction gives, one per way the
+    /// reactant templates match (a match may span several reactants).
+    /// Products of a set are listed fragment by fragment.
+    ///
+    /// Matches which would leave an atom beyond its valence are skipped.
+    /// Products keep the reactants' stereochemistry on the atoms the
+    /// reaction doesn't touch, and have their hydrogens explicit when
+    /// every reactant did.
+    pub fn apply(
+        &self,
+        reactants: &[Compound],
+    ) -> CompoundResult<Vec<Vec<Compound>>> {
+        Ok(self
+            .outcomes(reactants)?
+            .into_iter()
+            .map(|outcome| outcome.products)
+            .collect())
+    }
+
+    /// Like [`Self::apply`], along with where the reactants' atoms went
+    pub fn outcomes(
+        &self,
+        reactants: &[Compound],
+    ) -> CompoundResult<Vec<ReactionOutcome>> {
+        let graph = ReactionGraph::of(reactants);
+        let pooled =
+            graph.compound(&(0..graph.atoms.len()).collect::<Vec<_>>());
+        let explicit = reactants.iter().all(|c| !c.has_implicit_hydrogens());
+        let mut seen = BTreeSet::new();
+        let mut outcomes = Vec::new();
+        for mapping in pooled.smarts_matches(&self.reactants)? {
+            let Some(after) = self.transform(&graph, &mapping) else {
+                continue;
+            };
+            let mut atoms = BTreeMap::new();
+            let products: Vec<Compound> = after
+                .fragments()
+                .iter()
+                .enumerate()
+                .map(|(k, fragment)| {
+                    for (local, &i) in fragment.iter().enumerate() {
+                        if let Some(origin) = after.origins[i] {
+                            atoms.insert(origin, (k, local));
+                        }
+                    }
+                    let product = after.compound(fragment);
+                    match explicit {
+                        true => product.with_explicit_hydrogens(),
+                        false => product,
+                    }
+                })
+                .collect();
+            let mut keys: Vec<String> =
+                products.iter().map(|p| p.stereo_key()).collect();
+            keys.sort();
+            if !seen.insert(keys) {
+                continue;
+            }
+            let mapped = mapping
+                .iter()
+                .filter_map(|(&p, &i)| {
+                    Some((self.reactants.map_number(p)?, graph.origins[i]?))
+                })
+                .collect();
+            outcomes.push(ReactionOutcome {
+                products,
+                atoms,
+                mapped,
+            });
+        }
+        Ok(outcomes)
+    }
+
+    /// The graph after the reaction at one match, unless an atom would
+    /// exceed its valence
+    fn transform(
+        &self,
+        before: &ReactionGraph,
+        mapping: &AtomMapping,
+    ) -> Option<ReactionGraph> {
+        let mut graph = before.clone();
+        let mut removed = BTreeSet::new();
+        // Product template atom to graph atom
+        let mut placed = Vec::with_capacity(self.products.atom_count());
+        let mut fixed_hydrogens = BTreeMap::new();
+        for &(p, q) in self.reactants.bonds().keys() {
+            graph.bonds.remove(&bond_key(mapping[&p], mapping[&q]));
+        }
+        for q in 0..self.products.atom_count() {
+            let spec = AtomSpec::of(&self.products.atoms()[q]);
+            let i = match self.reactant_of(q) {
+                Some(p) => {
+                    let i = mapping[&p];
+                    graph.atoms[i] = spec.apply(&graph.atoms[i]);
+                    i
+                }
+                None => {
+                    let number = spec.number.expect("Checked on creation");
+                    graph.atoms.push(spec.apply(&Atom::new_unchecked(number)));
+                    graph.hydrogens.push(0);
+                    graph.origins.push(None);
+                    graph.atoms.len() - 1
+                }
+            };
+            if let Some(h) = spec.hydrogens {
+                fixed_hydrogens.insert(i, h);
+            }
+            placed.push(i);
+        }
+        for p in 0..self.reactants.atom_count() {
+            let kept = self.reactants.map_number(p).is_some_and(|map| {
+                (0..self.products.atom_count())
+                    .any(|q| self.products.map_number(q) == Some(map))
+            });
+            if !kept {
+                removed.insert(mapping[&p]);
+            }
+        }
+        for (&(q, r), expr) in self.products.bonds() {
+            let key = bond_key(placed[q], placed[r]);
+            let order = bond_order(expr)
+                .or_else(|| {
+                    let (p, r) = (self.reactant_of(q)?, self.reactant_of(r)?);
+                    let written = self.reactants.bonds().get(&bond_key(p, r));
+                    match written.map(bond_order) {
+                        Some(None) => before.bonds.get(&key).copied(),
+                        _ => None,
+                    }
+                })
+                .unwrap_or(BondOrder::Single);
+            graph.bonds.insert(key, order);
+        }
+        graph.bonds.retain(|&(a, b), _| {
+            !removed.contains(&a) && !removed.contains(&b)
+        });
+
+        // Atoms the reaction changed lose their stereo records and have
+        // their hydrogens refilled
+        for i in 0..graph.atoms.len() {
+            if removed.contains(&i) {
+                continue;
+            }
+            let changed = i >= before.atoms.len()
+                || graph.atoms[i] != before.atoms[i]
+                || graph.atoms[i].neutrons != before.atoms[i].neutrons
+                || graph.atoms[i].charge() != before.atoms[i].charge()
+                || graph.neighbors(i) != before.neighbors(i);
+            if !changed && !fixed_hydrogens.contains_key(&i) {
+                continue;
+            }
+            graph.chirality.remove(&i);
+            graph
+                .double_bond_stereo
+                .retain(|&(a, b), _| a != i && b != i);
+            graph.hydrogens[i] = match fixed_hydrogens.get(&i) {
+                Some(&h) => h,
+                None => {
+                    let bonded: u8 = graph
+                        .neighbors(i)
+                        .values()
+                        .map(|order| order.as_u8())
+                        .sum();
+                    let valence = graph.atoms[i]
+                        .valences()
+                        .into_iter()
+                        .find(|&v| v >= bonded)?;
+                    valence - bonded
+                }
+            };
+        }
+        graph.remove(&removed);
+        Some(graph)
+    }
+}
+
+impl FromStr for Reaction {
+    type Err = CompoundError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Reaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}>>{}", self.reactants, self.products)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::compound::{
+        builder::{BuildStep, CompoundBuilder},
+        substituent::Substituent,
+    };
+
+    fn build(step: BuildStep) -> Compound {
+        step(&mut CompoundBuilder::new())
+            .and_then(|b| b.build())
+            .expect("Valid compound expected")
+    }
+
+    fn names(product_sets: &[Vec<Compound>]) -> Vec<Vec<String>> {
+        product_sets
+            .iter()
+            .map(|products| {
+                products
+                    .iter()
+                    .map(|p| p.to_smiles().expect("SMILES expected"))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reaction_parsing() {
+        let reaction = Reaction::parse("[C:1]=[C:2]>>[C:1][C:2]Br")
+            .expect("Valid reaction");
+        assert_eq!(reaction.to_string(), "[C:1]=[C:2]>>[C:1][C:2]Br");
+        assert_eq!(reaction.products().atom_count(), 3);
+        assert!(matches!(
+            Reaction::parse("C=C"),
+            Err(CompoundError::Parsing(_))
+        ));
+        assert!(matches!(
+            Reaction::parse("[C:1][C:1]>>[C:1]"),
+            Err(CompoundError::Reaction(_))
+        ));
+        assert!(matches!(
+            Reaction::parse("[C:1]>>[C:1]*"),
+            Err(CompoundError::Reaction(_))
+        ));
+    }
+
+    #[test]
+    fn reaction_swaps_hydrogens() {
+        let bromination =
+            Reaction::parse("[C;!H0:1]>>[C:1]Br").expect("Valid reaction");
+        let propane = build(|b| b.linear_chain(3));
+        let products = bromination.apply(&[propane]).expect("Products");
+        assert_eq!(names(&products), vec![vec!["C(Br)CC"], vec!["CC(C)Br"]]);
+        // Explicit hydrogens in, explicit hydrogens out
+        let expected = build(|b| b.linear_chain(3)?.brominate(1));
+        assert_eq!(products[0][0].atom_count(), expected.atom_count());
+        assert!(products[0][0].is_isomorphic(&expected));
+
+        let any_carbon = Reaction::parse("[CX4:1]>>[C:1]Br").expect("Valid");
+        let dichloropropane = build(|b| {
+            b.linear_chain(3)?
+                .substitute(2, Substituent::Chloro)?
+                .substitute(2, Substituent::Chloro)
+        });
+        // The central carbon has no hydrogen left to give up
+        let products = any_carbon.apply(&[dichloropropane]).expect("Products");
+        assert_eq!(products.len(), 1);
+        assert_eq!(products[0][0].molecular_formula(), "C3H5BrCl2");
+    }
+
+    #[test]
+    fn reaction_adds_and_splits() {
+        let addition = Reaction::parse("[C:1]=[C:2]>>[C:1][C:2]Br")
+            .expect("Valid reaction");
+        let propene = build(|b| b.linear_chain(3)?.double_bond(1, 2));
+        let products = addition.apply(&[propene]).expect("Products");
+        assert_eq!(names(&products), vec![vec!["CC(C)Br"], vec!["C(Br)CC"]]);
+
+        let sn2 = Reaction::parse("[C:1][Br:2].[O-:3]>>[C:1][O+0:3].[Br-:2]")
+            .expect("Valid reaction");
+        let bromoethane = build(|b| b.linear_chain(2)?.brominate(1));
+        let mut hydroxide =
+            Compound::from_edges(vec![Atom::new_unchecked(8).ion(-1)], &[]);
+        hydroxide.implicit_hydrogens.insert(0, 1);
+        let product_sets =
+            sn2.apply(&[bromoethane, hydroxide]).expect("Products");
+        assert_eq!(product_sets.len(), 1);
+        let [ethanol, bromide] = &product_sets[0][..] else {
+            panic!("Two products expected");
+        };
+        assert!(ethanol.is_isomorphic(&build(|b| {
+            b.linear_chain(2)?.substitute(1, Substituent::Hydroxy)
+        })));
+        assert_eq!(bromide.charge(), -1);
+        assert_eq!(bromide.molecular_formula(), "Br");
+        // Nothing to react with
+        assert!(sn2
+            .apply(&[build(|b| b.linear_chain(2)?.brominate(1))])
+            .expect("Products")
+            .is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::matter::compound::bond::bond_key;
+
+/// Maps old atom indices to new ones once some atoms are removed; the
+/// remaining atoms keep their relative order.
+pub(crate) struct Reindex(Vec<Option<usize>>);
+
+impl Reindex {
+    pub(crate) fn removing(len: usize, removed: &BTreeSet<usize>) -> Self {
+        let mut next = 0;
+        Self(
+            (0..len)
+                .map(|i| {
+                    if removed.contains(&i) {
+                        None
+                    } else {
+                        next += 1;
+                        Some(next - 1)
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    pub(crate) fn get(&self, i: usize) -> Option<usize> {
+        self.0.get(i).copied().flatten()
+    }
+
+    /// Keeps the items (indexed by atom) of the remaining atoms
+    pub(crate) fn items<T>(&self, items: Vec<T>) -> Vec<T> {
+        items
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| self.get(*i).is_some())
+            .map(|(_, item)| item)
+            .collect()
+    }
+
+    pub(crate) fn indices(&self, indices: &[usize]) -> Vec<usize> {
+        indices.iter().filter_map(|&i| self.get(i)).collect()
+    }
+
+    pub(crate) fn side_chains(
+        &self,
+        side_chains: &HashMap<usize, BTreeSet<usize>>,
+    ) -> HashMap<usize, BTreeSet<usize>> {
+        side_chains
+            .iter()
+            .filter_map(|(&i, side_chain)| {
+                let side_chain: BTreeSet<usize> =
+                    side_chain.iter().filter_map(|&j| self.get(j)).collect();
+                Some((self.get(i)?, side_chain)).filter(|(_, c)| !c.is_empty())
+            })
+            .collect()
+    }
+
+    /// Remaps a map keyed by atom index
+    pub(crate) fn atom_map<V: Clone>(
+        &self,
+        map: &HashMap<usize, V>,
+    ) -> HashMap<usize, V> {
+        map.iter()
+            .filter_map(|(&i, v)| Some((self.get(i)?, v.clone())))
+            .collect()
+    }
+
+    /// Remaps a set of bonds, dropping bonds to removed atoms
+    pub(crate) fn bonds(
+        &self,
+        bonds: &BTreeSet<(usize, usize)>,
+    ) -> BTreeSet<(usize, usize)> {
+        bonds
+            .iter()
+            .filter_map(|&(a, b)| Some(bond_key(self.get(a)?, self.get(b)?)))
+            .collect()
+    }
+
+    /// Remaps a map keyed by bond, dropping bonds to removed atoms
+    pub(crate) fn bond_map<V: Clone>(
+        &self,
+        map: &HashMap<(usize, usize), V>,
+    ) -> HashMap<(usize, usize), V> {
+        map.iter()
+            .filter_map(|(&(a, b), v)| {
+                Some((bond_key(self.get(a)?, self.get(b)?), v.clone()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reindex_shifts_remaining_atoms() {
+        let reindex = Reindex::removing(5, &BTreeSet::from([1, 3]));
+        assert_eq!(reindex.get(0), Some(0));
+        assert_eq!(reindex.get(1), None);
+        assert_eq!(reindex.get(2), Some(1));
+        assert_eq!(reindex.get(4), Some(2));
+        assert_eq!(
+            reindex.items(vec!['a', 'b', 'c', 'd', 'e']),
+            vec!['a', 'c', 'e']
+        );
+        let side_chains = HashMap::from([
+            (0, BTreeSet::from([1])),
+            (2, BTreeSet::from([3, 4])),
+        ]);
+        assert_eq!(
+            reindex.side_chains(&side_chains),
+            HashMap::from([(1, BTreeSet::from([2]))])
+        );
+    }
+}
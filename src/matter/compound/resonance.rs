@@ -0,0 +1,376 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::matter::compound::{
+    arrow::Arrow,
+    bond::{bond_key, BondOrder},
+    lewis::{octet_target, LewisStructure, Score},
+    Compound, CompoundError, CompoundResult,
+};
+
+/// Stops runaway enumeration on large conjugated systems: a Compound with
+/// more contributors than this is reported as an error rather than cut short.
+const MAX_CONTRIBUTORS: usize = 64;
+
+#[derive(Clone, Debug)]
+pub struct ResonanceContributor {
+    pub compound: Compound,
+    pub lewis: LewisStructure,
+    /// Index of the contributor this one was derived from
+    pub parent: Option<usize>,
+    /// Arrows turning the parent contributor into this one
+    pub arrows: Vec<Arrow>,
+}
+
+/// Bond orders and charges identify a contributor.
+type StateKey = (Vec<((usize, usize), BondOrder)>, Vec<i8>);
+
+fn state_key(compound: &Compound) -> StateKey {
+    (
+        compound
+            .bond_orders()
+            .into_iter()
+            .filter(|(_, order)| *order != BondOrder::Single)
+            .collect(),
+        compound.atoms().iter().map(|a| a.charge()).collect(),
+    )
+}
+
+/// Whether atom `i` lacks the octet its element wants (e.g. a carbocation).
+fn is_electron_deficient(
+    compound: &Compound,
+    lewis: &LewisStructure,
+    i: usize,
+) -> bool {
+    octet_target(compound.atoms()[i].get_element())
+        .is_some_and(|target| lewis.electrons_around(i) < target)
+}
+
+/// Arrow sets leading to the neighbouring contributors of a structure.
+fn candidate_moves(
+    compound: &Compound,
+    lewis: &LewisStructure,
+) -> Vec<Vec<Arrow>> {
+    let adjacency = compound.adjacency();
+    let orders = compound.bond_orders();
+    let order = |a: usize, b: usize| orders[&bond_key(a, b)].as_u8();
+    let mut moves = Vec::new();
+
+    for (a, neighbors) in adjacency.iter().enumerate() {
+        if lewis.lone_pairs[a] == 0 {
+            continue;
+        }
+        for &b in neighbors.iter().filter(|&&b| order(a, b) < 3) {
+            // Lone pair donated into an empty orbital
+            if is_electron_deficient(compound, lewis, b) {
+                moves.push(vec![Arrow::lone_pair_to_bond(a, b)]);
+            }
+            // Lone pair pushes a neighbouring π bond onto the far atom
+            for &c in adjacency[b].iter() {
+                if c != a && order(b, c) >= 2 {
+                    moves.push(vec![
+                        Arrow::lone_pair_to_bond(a, b),
+                        Arrow::bond_to_atom(b, c),
+                    ]);
+                }
+            }
+        }
+    }
+
+    for (&(x, y), bond_order) in orders.iter() {
+        if *bond_order == BondOrder::Single {
+            continue;
+        }
+        for (a, b) in [(x, y), (y, x)] {
+            // π bond shifts towards an adjacent empty orbital
+            for &c in adjacency[b].iter() {
+                if c != a
+                    && order(b, c) < 3
+                    && is_electron_deficient(compound, lewis, c)
+                {
+                    moves.push(vec![Arrow::bond_to_bond(a, b, c)]);
+                }
+            }
+        }
+        // π bonds circulating around an alternating ring (benzene)
+        let mut path = vec![x, y];
+        alternating_cycles(&adjacency, &order, &mut path, &mut moves);
+    }
+    moves
+}
+
+/// Extends `path` (which ends on a π bond) by alternating single and double
+/// bonds, recording the arrows for every path that closes into a ring.
+fn alternating_cycles(
+    adjacency: &[Vec<usize>],
+    order: &impl Fn(usize, usize) -> u8,
+    path: &mut Vec<usize>,
+    moves: &mut Vec<Vec<Arrow>>,
+) {
+    let last = *path.last().unwrap();
+    for &next in adjacency[last].iter() {
+        if order(last, next) != 1 {
+            continue;
+        }
+        if next == path[0] && path.len() >= 4 {
+            let arrows = path
+                .chunks(2)
+                .enumerate()
+                .map(|(k, pair)| {
+                    let after = path[(2 * k + 2) % path.len()];
+                    Arrow::bond_to_bond(pair[0], pair[1], after)
+                })
+                .collect();
+            moves.push(arrows);
+            continue;
+        }
+        if path.contains(&next) {
+            continue;
+        }
+        for &far in adjacency[next].iter() {
+            if order(next, far) == 2 && !path.contains(&far) {
+                path.push(next);
+                path.push(far);
+                alternating_cycles(adjacency, order, path, moves);
+                path.truncate(path.len() - 2);
+            }
+        }
+    }
+}
+
+impl Compound {
+    /// Enumerates the resonance contributors reachable by moving π bonds and
+    /// lone pairs from the Compound's Lewis structure.
+    ///
+    /// Contributors are ranked by the octet rule, then formal charges, with
+    /// the major contributor first. Each one records the arrows converting
+    /// its parent contributor into it. Conjugated systems with more than
+    /// 64 contributors are rejected instead of enumerated partially.
+    pub fn resonance_structures(
+        &self,
+    ) -> CompoundResult<Vec<ResonanceContributor>> {
+        self.resonance_structures_up_to(MAX_CONTRIBUTORS)
+    }
+
+    fn resonance_structures_up_to(
+        &self,
+        limit: usize,
+    ) -> CompoundResult<Vec<ResonanceContributor>> {
+        let lewis = self.lewis_structure()?;
+        let start = self.with_lewis_structure(&lewis);
+        let mut seen = HashSet::from([state_key(&start)]);
+        let mut contributors = vec![ResonanceContributor {
+            compound: start,
+            lewis,
+            parent: None,
+            arrows: Vec::new(),
+        }];
+        let mut queue = VecDeque::from([0]);
+        while let Some(idx) = queue.pop_front() {
+            let current = &contributors[idx];
+            let mut found = Vec::new();
+            for arrows in candidate_moves(&current.compound, &current.lewis) {
+                let Ok(next) = current.compound.push_electrons(&arrows) else {
+                    continue;
+                };
+                if seen.insert(state_key(&next)) {
+                    let lewis = LewisStructure::from_compound(&next)?;
+                    found.push(ResonanceContributor {
+                        compound: next,
+                        lewis,
+                        parent: Some(idx),
+                        arrows,
+                    });
+                }
+            }
+            for contributor in found {
+                if contributors.len() >= limit {
+                    return Err(CompoundError::Lewis(format!(
+                        "More than {limit} resonance contributors"
+                    )));
+                }
+                queue.push_back(contributors.len());
+                contributors.push(contributor);
+            }
+        }
+
+        let mut ranked: Vec<usize> = (0..contributors.len()).collect();
+        ranked.sort_by(|&i, &j| {
+            let lhs =
+                Score::of(&contributors[i].lewis, &contributors[i].compound);
+            let rhs =
+                Score::of(&contributors[j].lewis, &contributors[j].compound);
+            lhs.partial_cmp(&rhs).expect("Scores are totally ordered")
+        });
+        let mut position = vec![0; ranked.len()];
+        for (new, &old) in ranked.iter().enumerate() {
+            position[old] = new;
+        }
+        let mut sorted: Vec<Option<ResonanceContributor>> =
+            contributors.into_iter().map(Some).collect();
+        Ok(ranked
+            .iter()
+            .map(|&old| {
+                let mut contributor =
+                    sorted[old].take().expect("Each contributor moves once");
+                contributor.parent = contributor.parent.map(|p| position[p]);
+                contributor
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matter::atom::Atom;
+
+    use super::*;
+
+    fn oxygen() -> Atom {
+        Atom::new_unchecked(8)
+    }
+
+    /// Builds the compound after hanging `count` hydrogens off each listed
+    /// `(parent, count)`.
+    fn with_hydrogens(
+        mut atoms: Vec<Atom>,
+        mut edges: Vec<(usize, usize, BondOrder)>,
+        hydrogens: &[(usize, usize)],
+    ) -> Compound {
+        for &(parent, count) in hydrogens {
+            for _ in 0..count {
+                edges.push((parent, atoms.len(), BondOrder::Single));
+                atoms.push(Atom::hydrogen());
+            }
+        }
+        Compound::from_edges(atoms, &edges)
+    }
+
+    fn single(a: usize, b: usize) -> (usize, usize, BondOrder) {
+        (a, b, BondOrder::Single)
+    }
+
+    #[test]
+    fn resonance_acetate() {
+        let acetate = with_hydrogens(
+            vec![Atom::carbon(), Atom::carbon(), oxygen(), oxygen().ion(-1)],
+            vec![single(0, 1), single(1, 2), single(1, 3)],
+            &[(0, 3)],
+        );
+        let contributors = acetate
+            .resonance_structures()
+            .expect("Contributors expected");
+        assert_eq!(contributors.len(), 2);
+        let second = &contributors[1];
+        assert_eq!(second.parent, Some(0));
+        assert_eq!(second.arrows.len(), 2);
+        for contributor in contributors.iter() {
+            let charges: Vec<i8> =
+                contributor.lewis.formal_charges[2..4].to_vec();
+            assert!(charges == vec![0, -1] || charges == vec![-1, 0]);
+        }
+    }
+
+    #[test]
+    fn resonance_allylic_cation() {
+        let allyl = with_hydrogens(
+            vec![Atom::carbon(), Atom::carbon(), Atom::carbon().ion(1)],
+            vec![single(0, 1), single(1, 2)],
+            &[(0, 2), (1, 1), (2, 2)],
+        );
+        let contributors =
+            allyl.resonance_structures().expect("Contributors expected");
+        assert_eq!(contributors.len(), 2);
+        assert_eq!(contributors[1].arrows.len(), 1);
+        let cations: Vec<usize> = contributors
+            .iter()
+            .map(|c| {
+                c.lewis.formal_charges.iter().position(|&q| q == 1).unwrap()
+            })
+            .collect();
+        assert!(cations.contains(&0) && cations.contains(&2));
+    }
+
+    #[test]
+    fn resonance_enolate_prefers_oxyanion() {
+        let enolate = with_hydrogens(
+            vec![Atom::carbon(), Atom::carbon(), oxygen().ion(-1)],
+            vec![single(0, 1), single(1, 2)],
+            &[(0, 2), (1, 1)],
+        );
+        let contributors = enolate
+            .resonance_structures()
+            .expect("Contributors expected");
+        assert_eq!(contributors.len(), 2);
+        assert_eq!(contributors[0].lewis.formal_charges[2], -1);
+        assert_eq!(contributors[1].lewis.formal_charges[0], -1);
+        assert_eq!(
+            contributors[1].compound.bond_order(1, 2),
+            Some(BondOrder::Double)
+        );
+    }
+
+    #[test]
+    fn resonance_nitro_group() {
+        let nitromethane = with_hydrogens(
+            vec![Atom::carbon(), Atom::nitrogen(), oxygen(), oxygen()],
+            vec![single(0, 1), single(1, 2), single(1, 3)],
+            &[(0, 3)],
+        );
+        let contributors = nitromethane
+            .resonance_structures()
+            .expect("Contributors expected");
+        assert_eq!(contributors.len(), 2);
+        for contributor in contributors.iter() {
+            assert_eq!(contributor.lewis.formal_charges[1], 1);
+        }
+    }
+
+    #[test]
+    fn resonance_benzene_kekule_structures() {
+        let mut edges: Vec<_> = (0..5).map(|i| single(i, i + 1)).collect();
+        edges.push(single(0, 5));
+        let benzene = with_hydrogens(
+            vec![Atom::carbon(); 6],
+            edges,
+            &(0..6).map(|i| (i, 1)).collect::<Vec<_>>(),
+        );
+        let contributors = benzene
+            .resonance_structures()
+            .expect("Contributors expected");
+        assert_eq!(contributors.len(), 2);
+        assert_eq!(contributors[1].arrows.len(), 3);
+        for contributor in contributors.iter() {
+            assert_eq!(contributor.lewis.formal_charge_magnitude(), 0);
+        }
+    }
+
+    #[test]
+    fn resonance_too_many_contributors_is_an_error() {
+        let mut edges: Vec<_> = (0..5).map(|i| single(i, i + 1)).collect();
+        edges.push(single(0, 5));
+        let benzene = with_hydrogens(
+            vec![Atom::carbon(); 6],
+            edges,
+            &(0..6).map(|i| (i, 1)).collect::<Vec<_>>(),
+        );
+        assert!(matches!(
+            benzene.resonance_structures_up_to(1),
+            Err(CompoundError::Lewis(_))
+        ));
+        assert_eq!(benzene.resonance_structures_up_to(2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn resonance_alkane_has_single_contributor() {
+        let ethane = with_hydrogens(
+            vec![Atom::carbon(), Atom::carbon()],
+            vec![single(0, 1)],
+            &[(0, 3), (1, 3)],
+        );
+        let contributors = ethane
+            .resonance_structures()
+            .expect("Contributors expected");
+        assert_eq!(contributors.len(), 1);
+        assert!(contributors[0].arrows.is_empty());
+    }
+}
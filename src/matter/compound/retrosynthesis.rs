@@ -0,0 +1,496 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeSet, BinaryHeap},
+};
+
+use crate::matter::compound::{
+    bond::BondOrder,
+    halogenation::Halogen,
+    named_reaction::{predict_products, NamedReaction, Reagent},
+    reaction::Reaction,
+    substituent::AlkylGroup,
+    Compound, CompoundResult,
+};
+
+/// Precursors expanded before the search gives up, however many routes
+/// it found
+const MAX_EXPANSIONS: usize = 500;
+
+/// One forward reaction of a route
+#[derive(Clone, Debug)]
+pub struct SynthesisStep {
+    pub reaction: NamedReaction,
+    pub reagents: Vec<Reagent>,
+    pub reactant: Compound,
+    pub product: Compound,
+}
+
+/// Synthesis of a target from a starting material, steps in the order
+/// they're run
+#[derive(Clone, Debug)]
+pub struct Route {
+    pub starting_material: Compound,
+    pub steps: Vec<SynthesisStep>,
+}
+
+impl Route {
+    /// Compounds made and used up along the way
+    pub fn intermediates(&self) -> Vec<&Compound> {
+        self.steps
+            .iter()
+            .skip(1)
+            .map(|step| &step.reactant)
+            .collect()
+    }
+}
+
+/// Whether the compound counts as a starting material: an alkane of up to
+/// four carbons, or an alkene with a single C=C (not a diene, nor a Kekulé
+/// benzene)
+pub fn is_starting_material(compound: &Compound) -> bool {
+    let mut carbons = 0;
+    for atom in compound.atoms() {
+        match atom.get_element_num() {
+            1 if atom.charge() == 0 => {}
+            6 if atom.charge() == 0 => carbons += 1,
+            _ => return false,
+        }
+    }
+    if !compound
+        .aromatic_atoms()
+        .is_ok_and(|aromatic| aromatic.is_empty())
+    {
+        return false;
+    }
+    let double_bonds = compound
+        .bond_orders
+        .values()
+        .filter(|&&order| order == BondOrder::Double)
+        .count();
+    match compound.bond_orders.len() {
+        0 => (1..=4).contains(&carbons),
+        1 => double_bonds == 1,
+        _ => false,
+    }
+}
+
+/// Reaction SMARTS turning a target into its precursor, with the reagents
+/// taking the precursor forward again
+fn disconnections() -> Vec<(String, Vec<Reagent>)> {
+    let mut disconnections = Vec::new();
+    for halogen in [Halogen::Chlorine, Halogen::Bromine] {
+        let x = halogen.atom().to_string();
+        disconnections.extend([
+            (
+                format!("[C:1][C:2]{}>>[C:1]=[C:2]", x),
+                vec![Reagent::HydrogenHalide(halogen)],
+            ),
+            (
+                format!("{x}[C:1][C:2]{x}>>[C:1]=[C:2]", x = x),
+                vec![Reagent::Halogen(halogen)],
+            ),
+            (
+                format!("[C:1]{}>>[C:1]", x),
+                vec![Reagent::Halogen(halogen), Reagent::Light],
+            ),
+        ]);
+    }
+    let alcohol = "[OX2H1]";
+    disconnections.extend([
+        (
+            "[C:1][C:2]Br>>[C:1]=[C:2]".into(),
+            vec![Reagent::HydrogenHalide(Halogen::Bromine), Reagent::Peroxide],
+        ),
+        (
+            format!("[C:1][C:2]{}>>[C:1]=[C:2]", alcohol),
+            vec![Reagent::AqueousAcid],
+        ),
+        (
+            format!("[C:1][C:2]{}>>[C:1]=[C:2]", alcohol),
+            vec![Reagent::Borane, Reagent::BasicPeroxide],
+        ),
+        (
+            format!("[C:1]{}>>[C:1]Br", alcohol),
+            vec![Reagent::Hydroxide],
+        ),
+        (format!("[C:1]{}>>[C:1]Br", alcohol), vec![Reagent::Water]),
+        ("[C:1]C#N>>[C:1]Br".into(), vec![Reagent::Cyanide]),
+        (
+            "[C:1]O[CH3]>>[C:1]Br".into(),
+            vec![Reagent::Alkoxide(AlkylGroup::Methyl)],
+        ),
+        (
+            "[C:1]O[CH2][CH3]>>[C:1]Br".into(),
+            vec![Reagent::Alkoxide(AlkylGroup::Ethyl)],
+        ),
+        (
+            format!("[C:1]([CH3]){}>>[C:1]=O", alcohol),
+            vec![Reagent::Grignard(AlkylGroup::Methyl)],
+        ),
+        (
+            format!("[C:1]([CH2][CH3]){}>>[C:1]=O", alcohol),
+            vec![Reagent::Grignard(AlkylGroup::Ethyl)],
+        ),
+        (
+            "[CX3;!$(C(=O)[O,N]):1]=[O:2]>>[C:1][O:2]".into(),
+            vec![Reagent::Pcc],
+        ),
+        (
+            "[CX3:1](=[O:2])[OX2H1]>>[C:1][O:2]".into(),
+            vec![Reagent::ChromicAcid],
+        ),
+    ]);
+    disconnections
+}
+
+/// Partial route, from a precursor still to be made to the target
+#[derive(Clone, Debug)]
+struct Node {
+    compound: Compound,
+    /// Forward steps from `compound` to the target
+    steps: Vec<SynthesisStep>,
+    /// Constitutions on the way, so routes don't go round in circles
+    seen: BTreeSet<String>,
+}
+
+impl Node {
+    /// Estimated total length of a route through this node: the steps so
+    /// far plus at least one more unless it's a starting material.
+    fn cost(&self) -> usize {
+        self.steps.len() + usize::from(!is_starting_material(&self.compound))
+    }
+}
+
+/// Nodes cheapest first, then those found first
+struct Queued(Reverse<(usize, usize)>, Node);
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+/// Precursors of the compound one step back, each with the step taking it
+/// to the compound. A step is only kept when the reaction library predicts
+/// the compound as a major product, so regiochemistry is respected;
+/// stereochemistry isn't planned for.
+fn precursors(compound: &Compound) -> CompoundResult<Vec<SynthesisStep>> {
+    let key = compound.canonical_key();
+    let mut steps = Vec::new();
+    let mut found = BTreeSet::new();
+    for (template, reagents) in disconnections() {
+        let retro = Reaction::parse(&template)?;
+        for products in retro.apply(std::slice::from_ref(compound))? {
+            let [precursor] = &products[..] else {
+                continue;
+            };
+            let precursor = precursor.with_implicit_hydrogens();
+            if !found
+                .insert((precursor.canonical_key(), format!("{:?}", reagents)))
+            {
+                continue;
+            }
+            let forward =
+                predict_products(std::slice::from_ref(&precursor), &reagents)?;
+            let made = forward.into_iter().find(|prediction| {
+                prediction.major
+                    && prediction
+                        .products
+                        .iter()
+                        .any(|product| product.canonical_key() == key)
+            });
+            if let Some(prediction) = made {
+                steps.push(SynthesisStep {
+                    reaction: prediction.reaction,
+                    reagents: reagents.clone(),
+                    reactant: precursor,
+                    product: compound.clone(),
+                });
+            }
+        }
+    }
+    Ok(steps)
+}
+
+/// Routes found by [`plan_synthesis`]
+#[derive(Clone, Debug)]
+pub struct SynthesisPlan {
+    pub routes: Vec<Route>,
+    /// Whether the search stopped after expanding its maximum number of
+    /// precursors with some left to try, so shorter or more routes may
+    /// exist
+    pub truncated: bool,
+}
+
+/// Routes to the target from starting materials (see
+/// [`is_starting_material`]) of at most `max_steps` steps, found by a
+/// best-first search backwards through the reaction library. Routes are
+/// ranked by their number of steps, then by the size of their starting
+/// material; at most `limit` are returned. A target which is itself a
+/// starting material gives a route without steps.
+pub fn plan_synthesis(
+    target: &Compound,
+    max_steps: usize,
+    limit: usize,
+) -> CompoundResult<SynthesisPlan> {
+    search(target, max_steps, limit, MAX_EXPANSIONS)
+}
+
+fn search(
+    target: &Compound,
+    max_steps: usize,
+    limit: usize,
+    max_expansions: usize,
+) -> CompoundResult<SynthesisPlan> {
+    let target = target.with_implicit_hydrogens();
+    let start = Node {
+        seen: BTreeSet::from([target.canonical_key()]),
+        compound: target,
+        steps: Vec::new(),
+    };
+    let mut queue =
+        BinaryHeap::from([Queued(Reverse((start.cost(), 0)), start)]);
+    let mut pushed = 1;
+    let mut expansions = 0;
+    let mut routes = Vec::new();
+    let mut truncated = false;
+    while let Some(Queued(_, node)) = queue.pop() {
+        if is_starting_material(&node.compound) {
+            routes.push(Route {
+                starting_material: node.compound,
+                steps: node.steps,
+            });
+            if routes.len() == limit {
+                break;
+            }
+            continue;
+        }
+        if node.steps.len() == max_steps {
+            continue;
+        }
+        if expansions == max_expansions {
+            truncated = true;
+            continue;
+        }
+        expansions += 1;
+        for step in precursors(&node.compound)? {
+            let key = step.reactant.canonical_key();
+            if node.seen.contains(&key) {
+                continue;
+            }
+            let mut next = Node {
+                compound: step.reactant.clone(),
+                steps: vec![step],
+                seen: node.seen.clone(),
+            };
+            next.steps.extend(node.steps.iter().cloned());
+            next.seen.insert(key);
+            queue.push(Queued(Reverse((next.cost(), pushed)), next));
+            pushed += 1;
+        }
+    }
+    routes.sort_by_key(|route| {
+        (route.steps.len(), route.starting_material.atom_count())
+    });
+    Ok(SynthesisPlan { routes, truncated })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::{
+        atom::Atom,
+        compound::builder::{BuildStep, CompoundBuilder},
+    };
+
+    fn build(step: BuildStep) -> Compound {
+        step(&mut CompoundBuilder::new())
+            .and_then(|b| b.build())
+            .expect("Valid compound expected")
+    }
+
+    /// Compound from heavy atoms (by atomic number) and bonds
+    fn graph(atoms: &[u8], bonds: &[(usize, usize, u8)]) -> Compound {
+        let atoms = atoms.iter().map(|&z| Atom::new_unchecked(z)).collect();
+        let bonds: Vec<(usize, usize, BondOrder)> = bonds
+            .iter()
+            .map(|&(a, b, order)| {
+                (a, b, BondOrder::from_u8(order).expect("Bond order"))
+            })
+            .collect();
+        CompoundBuilder::new()
+            .graph(atoms, &bonds)
+            .and_then(|b| b.build())
+            .expect("Valid compound expected")
+    }
+
+    /// Starting material, then the reagents of each step
+    fn summary(route: &Route) -> (String, Vec<Vec<Reagent>>) {
+        (
+            route.starting_material.iupac_name().expect("Name expected"),
+            route.steps.iter().map(|s| s.reagents.clone()).collect(),
+        )
+    }
+
+    #[test]
+    fn retrosynthesis_starting_materials() {
+        assert!(is_starting_material(&build(|b| b.linear_chain(4))));
+        assert!(!is_starting_material(&build(|b| b.linear_chain(5))));
+        assert!(is_starting_material(&build(|b| {
+            b.linear_chain(6)?.double_bond(1, 2)
+        })));
+        assert!(!is_starting_material(&build(|b| {
+            b.linear_chain(2)?.brominate(1)
+        })));
+        assert!(!is_starting_material(&build(|b| {
+            b.linear_chain(3)?.triple_bond(1, 2)
+        })));
+        // Neither Kekulé benzene nor a diene is an alkene
+        assert!(!is_starting_material(&build(|b| b.benzene())));
+        assert!(!is_starting_material(&build(|b| {
+            b.linear_chain(4)?.double_bond(1, 2)?.double_bond(3, 4)
+        })));
+    }
+
+    #[test]
+    fn retrosynthesis_one_step() {
+        use Reagent::*;
+        let routes = |step: BuildStep| {
+            plan_synthesis(&build(step), 1, 10)
+                .expect("Routes expected")
+                .routes
+                .iter()
+                .map(summary)
+                .collect::<Vec<_>>()
+        };
+        let bromine = Reagent::Halogen(super::Halogen::Bromine);
+        let hbr = HydrogenHalide(super::Halogen::Bromine);
+        assert_eq!(
+            routes(|b| b.linear_chain(3)?.brominate(2)),
+            vec![
+                ("propene".into(), vec![vec![hbr]]),
+                ("propane".into(), vec![vec![bromine, Light]]),
+            ]
+        );
+        // Radical bromination of propane gives 2-bromopropane
+        assert_eq!(
+            routes(|b| b.linear_chain(3)?.brominate(1)),
+            vec![("propene".into(), vec![vec![hbr, Peroxide]])]
+        );
+        // No alkene leads to neopentyl bromide, and neopentane is too big
+        assert!(routes(|b| {
+            b.linear_chain(3)?
+                .brominate(1)?
+                .add_alkyl(2, AlkylGroup::Methyl)?
+                .add_alkyl(2, AlkylGroup::Methyl)
+        })
+        .is_empty());
+        assert_eq!(
+            routes(|b| b.linear_chain(2)),
+            vec![("ethane".into(), vec![])]
+        );
+    }
+
+    #[test]
+    fn retrosynthesis_multi_step() {
+        use Reagent::*;
+        // Butan-2-one, by oxidising butan-2-ol made from an alkene
+        let butanone = graph(
+            &[6, 6, 6, 6, 8],
+            &[(0, 1, 1), (1, 2, 1), (2, 3, 1), (1, 4, 2)],
+        );
+        assert!(plan_synthesis(&butanone, 1, 10)
+            .expect("Routes expected")
+            .routes
+            .is_empty());
+        let plan = plan_synthesis(&butanone, 2, 10).expect("Routes expected");
+        assert!(!plan.truncated);
+        let routes = plan.routes;
+        assert_eq!(
+            summary(&routes[0]),
+            ("but-1-ene".into(), vec![vec![AqueousAcid], vec![Pcc]])
+        );
+        let butanol = graph(
+            &[6, 6, 6, 6, 8],
+            &[(0, 1, 1), (1, 2, 1), (2, 3, 1), (1, 4, 1)],
+        );
+        let intermediates = routes[0].intermediates();
+        assert_eq!(intermediates.len(), 1);
+        assert!(intermediates[0].is_isomorphic(&butanol));
+        assert_eq!(
+            routes[0]
+                .steps
+                .iter()
+                .map(|step| step.reaction)
+                .collect::<Vec<_>>(),
+            vec![
+                NamedReaction::AcidCatalysedHydration,
+                NamedReaction::PccOxidation
+            ]
+        );
+        assert!(routes.iter().all(|route| route.steps.len() == 2));
+
+        // Propanenitrile, through bromoethane
+        let nitrile = graph(&[6, 6, 6, 7], &[(0, 1, 1), (1, 2, 1), (2, 3, 3)]);
+        let routes = plan_synthesis(&nitrile, 3, 10)
+            .expect("Routes expected")
+            .routes;
+        let mut summaries: Vec<_> = routes.iter().map(summary).collect();
+        summaries.sort_by_key(|(start, _)| start.clone());
+        assert_eq!(
+            summaries,
+            vec![
+                (
+                    "ethane".into(),
+                    vec![
+                        vec![Reagent::Halogen(super::Halogen::Bromine), Light],
+                        vec![Cyanide]
+                    ]
+                ),
+                (
+                    "ethene".into(),
+                    vec![
+                        vec![HydrogenHalide(super::Halogen::Bromine)],
+                        vec![Cyanide]
+                    ]
+                ),
+                (
+                    "ethene".into(),
+                    vec![
+                        vec![HydrogenHalide(super::Halogen::Bromine), Peroxide],
+                        vec![Cyanide]
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn retrosynthesis_reports_truncation() {
+        // Butan-2-one needs a second expansion to reach an alkene
+        let butanone = graph(
+            &[6, 6, 6, 6, 8],
+            &[(0, 1, 1), (1, 2, 1), (2, 3, 1), (1, 4, 2)],
+        );
+        let plan = search(&butanone, 2, 10, 1).expect("Routes expected");
+        assert!(plan.truncated);
+        assert!(plan.routes.is_empty());
+        // Running out of steps isn't a truncated search
+        let plan = search(&butanone, 1, 10, 1).expect("Routes expected");
+        assert!(!plan.truncated);
+    }
+}
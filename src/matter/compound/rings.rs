@@ -0,0 +1,558 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::matter::compound::{bond::bond_key, Compound};
+
+/// How two rings of the SSSR meet
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum RingJunction {
+    /// Sharing a single atom
+    Spiro,
+    /// Sharing a single bond (ortho-fused)
+    Fused,
+    /// Sharing more than two atoms, bridged across the rings
+    Bridged,
+}
+
+/// Rings connected through shared atoms
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RingSystem {
+    /// Indices into [`RingInfo::sssr`]
+    pub rings: Vec<usize>,
+    pub atoms: BTreeSet<usize>,
+    /// Pairs of rings (indices into [`RingInfo::sssr`]) meeting at shared
+    /// atoms
+    pub junctions: Vec<(usize, usize, RingJunction)>,
+}
+
+impl RingSystem {
+    /// A lone ring sharing no atom with any other
+    pub fn is_isolated(&self) -> bool {
+        self.rings.len() == 1
+    }
+
+    pub fn has_junction(&self, junction: RingJunction) -> bool {
+        self.junctions.iter().any(|&(_, _, j)| j == junction)
+    }
+}
+
+/// Ring perception results for a Compound. Rings are listed in order
+/// around them, starting from their lowest atom index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RingInfo {
+    /// Smallest set of smallest rings: a minimum cycle basis, smallest
+    /// rings first
+    pub sssr: Vec<Vec<usize>>,
+    /// Every ring belonging to some minimum cycle basis; unlike the SSSR,
+    /// this doesn't depend on which of several equal rings gets picked.
+    pub relevant_cycles: Vec<Vec<usize>>,
+    /// Indices into `sssr` of the rings each atom belongs to
+    atom_rings: Vec<Vec<usize>>,
+    /// Indices into `sssr` of the rings each bond belongs to
+    bond_rings: BTreeMap<(usize, usize), Vec<usize>>,
+}
+
+impl RingInfo {
+    /// Number of rings, i.e. the cyclomatic number of the compound
+    pub fn ring_count(&self) -> usize {
+        self.sssr.len()
+    }
+
+    /// Sizes of the SSSR rings
+    pub fn ring_sizes(&self) -> Vec<usize> {
+        self.sssr.iter().map(|ring| ring.len()).collect()
+    }
+
+    /// Indices into `sssr` of the rings atom `i` belongs to
+    pub fn atom_rings(&self, i: usize) -> &[usize] {
+        self.atom_rings.get(i).map_or(&[], |rings| rings)
+    }
+
+    /// Indices into `sssr` of the rings the bond between `a` and `b`
+    /// belongs to
+    pub fn bond_rings(&self, a: usize, b: usize) -> &[usize] {
+        self.bond_rings
+            .get(&bond_key(a, b))
+            .map_or(&[], |rings| rings)
+    }
+
+    pub fn is_ring_atom(&self, i: usize) -> bool {
+        !self.atom_rings(i).is_empty()
+    }
+
+    pub fn is_ring_bond(&self, a: usize, b: usize) -> bool {
+        !self.bond_rings(a, b).is_empty()
+    }
+
+    /// Size of the smallest ring holding atom `i`, if any
+    pub fn smallest_ring_size(&self, i: usize) -> Option<usize> {
+        self.relevant_cycles
+            .iter()
+            .filter(|ring| ring.contains(&i))
+            .map(|ring| ring.len())
+            .min()
+    }
+
+    /// Groups the SSSR into ring systems, classifying how their rings meet.
+    pub fn ring_systems(&self) -> Vec<RingSystem> {
+        let sets: Vec<BTreeSet<usize>> = self
+            .sssr
+            .iter()
+            .map(|ring| ring.iter().copied().collect())
+            .collect();
+        let mut system_of: Vec<usize> = (0..sets.len()).collect();
+        let mut junctions = Vec::new();
+        for a in 0..sets.len() {
+            for b in a + 1..sets.len() {
+                let shared: Vec<usize> =
+                    sets[a].intersection(&sets[b]).copied().collect();
+                let junction = match shared[..] {
+                    [] => continue,
+                    [_] => RingJunction::Spiro,
+                    [x, y] if self.bond_rings(x, y).len() > 1 => {
+                        RingJunction::Fused
+                    }
+                    _ => RingJunction::Bridged,
+                };
+                junctions.push((a, b, junction));
+                let (from, to) = (system_of[b], system_of[a]);
+                for system in system_of.iter_mut() {
+                    if *system == from {
+                        *system = to;
+                    }
+                }
+            }
+        }
+        let mut systems: BTreeMap<usize, RingSystem> = BTreeMap::new();
+        for (ring, &system) in system_of.iter().enumerate() {
+            let entry = systems.entry(system).or_insert_with(|| RingSystem {
+                rings: Vec::new(),
+                atoms: BTreeSet::new(),
+                junctions: Vec::new(),
+            });
+            entry.rings.push(ring);
+            entry.atoms.extend(sets[ring].iter().copied());
+        }
+        for (a, b, junction) in junctions {
+            if let Some(system) = systems.get_mut(&system_of[a]) {
+                system.junctions.push((a, b, junction));
+            }
+        }
+        systems.into_values().collect()
+    }
+}
+
+impl Compound {
+    /// Perceives the rings of the compound: the SSSR, the relevant cycles
+    /// and the ring membership of every atom and bond.
+    pub fn rings(&self) -> RingInfo {
+        let adjacency = self.adjacency();
+        let edges: BTreeMap<(usize, usize), usize> = self
+            .bonds()
+            .into_iter()
+            .enumerate()
+            .map(|(k, bond)| (bond, k))
+            .collect();
+        let space = || CycleSpace::new(edges.len());
+
+        let mut prototypes = prototypes(&adjacency);
+        prototypes.sort_by(|a, b| {
+            (a.ring.len(), &a.ring).cmp(&(b.ring.len(), &b.ring))
+        });
+        let mut basis = space();
+        let sssr: Vec<Vec<usize>> = prototypes
+            .iter()
+            .filter(|prototype| {
+                basis.insert(cycle_vector(&prototype.ring, &edges))
+            })
+            .map(|prototype| prototype.ring.clone())
+            .collect();
+
+        // Relevant cycles aren't sums of strictly smaller cycles. Only
+        // prototypes need testing: every ring in a prototype's family is
+        // relevant along with it.
+        let mut shorter = space();
+        let mut paths: BTreeMap<usize, RootedPaths> = BTreeMap::new();
+        let mut relevant_cycles = BTreeSet::new();
+        for group in prototypes.chunk_by(|a, b| a.ring.len() == b.ring.len()) {
+            for prototype in group {
+                if shorter.spans(&cycle_vector(&prototype.ring, &edges)) {
+                    continue;
+                }
+                let paths = paths.entry(prototype.root).or_insert_with(|| {
+                    RootedPaths::new(&adjacency, prototype.root)
+                });
+                relevant_cycles.extend(prototype.family(paths));
+            }
+            for prototype in group {
+                shorter.insert(cycle_vector(&prototype.ring, &edges));
+            }
+        }
+        let mut relevant_cycles: Vec<Vec<usize>> =
+            relevant_cycles.into_iter().collect();
+        relevant_cycles.sort_by_key(|ring| ring.len());
+
+        let mut atom_rings = vec![Vec::new(); self.atom_count()];
+        let mut bond_rings: BTreeMap<(usize, usize), Vec<usize>> =
+            BTreeMap::new();
+        for (k, ring) in sssr.iter().enumerate() {
+            for (m, &i) in ring.iter().enumerate() {
+                atom_rings[i].push(k);
+                let next = ring[(m + 1) % ring.len()];
+                bond_rings.entry(bond_key(i, next)).or_default().push(k);
+            }
+        }
+        RingInfo {
+            sssr,
+            relevant_cycles,
+            atom_rings,
+            bond_rings,
+        }
+    }
+}
+
+/// Cycles over GF(2), as sets of bonds, kept in row echelon form
+struct CycleSpace {
+    /// Pivot bond and row; each row is zero at the pivots before it.
+    rows: Vec<(usize, Vec<u64>)>,
+    width: usize,
+}
+
+impl CycleSpace {
+    fn new(bonds: usize) -> Self {
+        Self {
+            rows: Vec::new(),
+            width: bonds.div_ceil(64),
+        }
+    }
+
+    fn reduce(&self, mut v: Vec<u64>) -> Vec<u64> {
+        for (pivot, row) in self.rows.iter() {
+            if v[pivot / 64] >> (pivot % 64) & 1 == 1 {
+                v.iter_mut().zip(row.iter()).for_each(|(x, y)| *x ^= y);
+            }
+        }
+        v
+    }
+
+    /// Whether the cycle is a sum of the cycles already in the space
+    fn spans(&self, v: &[u64]) -> bool {
+        self.reduce(v.to_vec()).iter().all(|&x| x == 0)
+    }
+
+    /// Adds the cycle unless the space already spans it; returns whether
+    /// it was added.
+    fn insert(&mut self, v: Vec<u64>) -> bool {
+        debug_assert_eq!(v.len(), self.width);
+        let v = self.reduce(v);
+        let pivot = v
+            .iter()
+            .enumerate()
+            .find(|(_, &x)| x != 0)
+            .map(|(k, x)| 64 * k + x.trailing_zeros() as usize);
+        match pivot {
+            Some(pivot) => {
+                self.rows.push((pivot, v));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// The bonds of a ring as a GF(2) vector over the bond numbering `edges`
+fn cycle_vector(
+    ring: &[usize],
+    edges: &BTreeMap<(usize, usize), usize>,
+) -> Vec<u64> {
+    let mut v = vec![0; edges.len().div_ceil(64)];
+    for k in 0..ring.len() {
+        let e = edges[&bond_key(ring[k], ring[(k + 1) % ring.len()])];
+        v[e / 64] |= 1 << (e % 64);
+    }
+    v
+}
+
+/// Rotates a ring to start at its lowest atom, heading towards its lower
+/// neighbour, so equal rings compare equal.
+fn canonical_ring(mut ring: Vec<usize>) -> Vec<usize> {
+    let start = (0..ring.len()).min_by_key(|&k| ring[k]).unwrap_or(0);
+    ring.rotate_left(start);
+    if ring.len() > 2 && ring[ring.len() - 1] < ring[1] {
+        ring[1..].reverse();
+    }
+    ring
+}
+
+/// Shortest paths from `root` passing only through lower atoms, as in
+/// Vismara's construction of the relevant cycles
+struct RootedPaths {
+    root: usize,
+    distance: Vec<usize>,
+    /// Neighbours one step closer to the root along such paths; empty for
+    /// atoms they don't reach
+    predecessors: Vec<Vec<usize>>,
+}
+
+impl RootedPaths {
+    fn new(adjacency: &[Vec<usize>], root: usize) -> Self {
+        let mut distance = vec![usize::MAX; adjacency.len()];
+        distance[root] = 0;
+        let mut order = Vec::new();
+        let mut queue = VecDeque::from([root]);
+        while let Some(i) = queue.pop_front() {
+            order.push(i);
+            for &j in adjacency[i].iter() {
+                if distance[j] == usize::MAX {
+                    distance[j] = distance[i] + 1;
+                    queue.push_back(j);
+                }
+            }
+        }
+        let mut paths = Self {
+            root,
+            distance,
+            predecessors: vec![Vec::new(); adjacency.len()],
+        };
+        for &i in order.iter().filter(|&&i| i < root) {
+            paths.predecessors[i] = adjacency[i]
+                .iter()
+                .copied()
+                .filter(|&j| {
+                    paths.distance[j] + 1 == paths.distance[i]
+                        && paths.reaches(j)
+                })
+                .collect();
+        }
+        paths
+    }
+
+    fn reaches(&self, i: usize) -> bool {
+        i == self.root || !self.predecessors[i].is_empty()
+    }
+
+    /// One shortest path from the root to atom `i`
+    fn path(&self, mut i: usize) -> Vec<usize> {
+        let mut path = vec![i];
+        while i != self.root {
+            i = self.predecessors[i][0];
+            path.push(i);
+        }
+        path.reverse();
+        path
+    }
+
+    /// Every shortest path from the root to atom `i`
+    fn all_paths(&self, i: usize) -> Vec<Vec<usize>> {
+        if i == self.root {
+            return vec![vec![i]];
+        }
+        self.predecessors[i]
+            .iter()
+            .flat_map(|&j| self.all_paths(j))
+            .map(|mut path| {
+                path.push(i);
+                path
+            })
+            .collect()
+    }
+}
+
+/// Joins two paths from the same root into a ring through `middle`,
+/// provided they only meet at the root
+fn close_ring(
+    to_a: &[usize],
+    middle: Option<usize>,
+    to_b: &[usize],
+) -> Option<Vec<usize>> {
+    if to_a[1..].iter().any(|i| to_b[1..].contains(i)) {
+        return None;
+    }
+    let mut ring = to_a.to_vec();
+    ring.extend(middle);
+    ring.extend(to_b[1..].iter().rev());
+    Some(canonical_ring(ring))
+}
+
+/// A candidate ring of Vismara's: shortest paths from its highest atom,
+/// the root, to two ends which are bonded (odd rings) or share a
+/// neighbour (even rings)
+struct Prototype {
+    ring: Vec<usize>,
+    root: usize,
+    ends: (usize, usize),
+    middle: Option<usize>,
+}
+
+impl Prototype {
+    /// Rings of the same size found by taking any other shortest paths to
+    /// the ends
+    fn family(&self, paths: &RootedPaths) -> Vec<Vec<usize>> {
+        let to_b = paths.all_paths(self.ends.1);
+        paths
+            .all_paths(self.ends.0)
+            .iter()
+            .flat_map(|to_a| {
+                to_b.iter()
+                    .filter_map(|to_b| close_ring(to_a, self.middle, to_b))
+            })
+            .collect()
+    }
+}
+
+/// Vismara's prototypes, a Horton-style candidate set small enough to
+/// enumerate which includes a minimum cycle basis and, through their
+/// families, every relevant cycle.
+fn prototypes(adjacency: &[Vec<usize>]) -> Vec<Prototype> {
+    let mut prototypes = Vec::new();
+    for root in 0..adjacency.len() {
+        let paths = RootedPaths::new(adjacency, root);
+        for y in (0..root).filter(|&y| paths.reaches(y)) {
+            let mut closer = Vec::new();
+            for &z in adjacency[y].iter().filter(|&&z| paths.reaches(z)) {
+                if paths.distance[z] + 1 == paths.distance[y] {
+                    closer.push(z);
+                } else if paths.distance[z] == paths.distance[y] && z < y {
+                    let ring = close_ring(&paths.path(y), None, &paths.path(z));
+                    prototypes.extend(ring.map(|ring| Prototype {
+                        ring,
+                        root,
+                        ends: (y, z),
+                        middle: None,
+                    }));
+                }
+            }
+            for (k, &p) in closer.iter().enumerate() {
+                for &q in closer[k + 1..].iter() {
+                    let ring =
+                        close_ring(&paths.path(p), Some(y), &paths.path(q));
+                    prototypes.extend(ring.map(|ring| Prototype {
+                        ring,
+                        root,
+                        ends: (p, q),
+                        middle: Some(y),
+                    }));
+                }
+            }
+        }
+    }
+    prototypes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::{
+        atom::Atom,
+        compound::{
+            bond::BondOrder,
+            builder::{BuildStep, CompoundBuilder},
+            substituent::AlkylGroup,
+        },
+    };
+
+    fn build(steps: &[BuildStep]) -> Compound {
+        let mut builder = CompoundBuilder::new();
+        for step in steps {
+            step(&mut builder).expect("Valid step expected");
+        }
+        builder.build().expect("Valid compound expected")
+    }
+
+    #[test]
+    fn rings_of_simple_compounds() {
+        let hexane = build(&[|b| b.linear_chain(6)]);
+        assert_eq!(hexane.rings().ring_count(), 0);
+        assert!(hexane.rings().ring_systems().is_empty());
+
+        let methylcyclohexane =
+            build(&[|b| b.ring(6), |b| b.add_alkyl(1, AlkylGroup::Methyl)]);
+        let rings = methylcyclohexane.rings();
+        assert_eq!(rings.sssr, vec![vec![0, 1, 2, 3, 4, 5]]);
+        assert_eq!(rings.relevant_cycles, rings.sssr);
+        assert!(rings.is_ring_atom(3));
+        assert!(rings.is_ring_bond(5, 0));
+        let methyl = methylcyclohexane.neighbors(0).into_iter().find(|&j| {
+            methylcyclohexane.atoms()[j].get_element_num() == 6 && j >= 6
+        });
+        let methyl = methyl.expect("Methyl carbon");
+        assert!(!rings.is_ring_atom(methyl));
+        assert!(!rings.is_ring_bond(0, methyl));
+        assert_eq!(rings.smallest_ring_size(2), Some(6));
+        let systems = rings.ring_systems();
+        assert_eq!(systems.len(), 1);
+        assert!(systems[0].is_isolated());
+    }
+
+    #[test]
+    fn rings_junctions() {
+        let cases: [(&[BuildStep], Vec<usize>, RingJunction); 3] = [
+            // Decalin
+            (
+                &[|b| b.ring(10), |b| b.ring_closure(1, 6)],
+                vec![6, 6],
+                RingJunction::Fused,
+            ),
+            // Spiro[4.4]nonane
+            (
+                &[
+                    |b| b.linear_chain(9),
+                    |b| b.ring_closure(1, 5)?.ring_closure(5, 9),
+                ],
+                vec![5, 5],
+                RingJunction::Spiro,
+            ),
+            // Norbornane
+            (
+                &[
+                    |b| b.linear_chain(7),
+                    |b| b.ring_closure(1, 6)?.ring_closure(3, 7),
+                ],
+                vec![5, 5],
+                RingJunction::Bridged,
+            ),
+        ];
+        for (steps, sizes, junction) in cases {
+            let rings = build(steps).rings();
+            assert_eq!(rings.ring_sizes(), sizes);
+            let systems = rings.ring_systems();
+            assert_eq!(systems.len(), 1);
+            assert_eq!(systems[0].junctions, vec![(0, 1, junction)]);
+        }
+    }
+
+    #[test]
+    fn rings_relevant_cycles() {
+        // Bicyclo[2.2.2]octane: two rings make the SSSR, but all three six
+        // membered rings are relevant.
+        let compound = build(&[
+            |b| b.linear_chain(8),
+            |b| b.ring_closure(1, 6)?.ring_closure(3, 8),
+        ]);
+        assert_eq!(compound.molecular_formula(), "C8H14");
+        let rings = compound.rings();
+        assert_eq!(rings.ring_sizes(), vec![6, 6]);
+        assert_eq!(rings.relevant_cycles.len(), 3);
+        assert_eq!(rings.atom_rings(5).len(), 2);
+        assert!(rings.ring_systems()[0].has_junction(RingJunction::Bridged));
+    }
+
+    #[test]
+    fn rings_relevant_cycles_of_a_prism() {
+        // Two 24-membered rings joined rung by rung: the squares and both
+        // large rings are relevant, of the far more cycles no longer than
+        // 24 atoms
+        let bonds: BTreeMap<(usize, usize), BondOrder> = (0..24)
+            .flat_map(|i| {
+                let next = (i + 1) % 24;
+                [(i, next), (i + 24, next + 24), (i, i + 24)]
+            })
+            .map(|(a, b)| (bond_key(a, b), BondOrder::Single))
+            .collect();
+        let prism = Compound::from_bonds(vec![Atom::carbon(); 48], &bonds);
+        let rings = prism.rings();
+        assert_eq!(rings.ring_count(), 25);
+        assert_eq!(rings.relevant_cycles.len(), 26);
+        assert_eq!(rings.relevant_cycles[25].len(), 24);
+        assert_eq!(rings.smallest_ring_size(30), Some(4));
+    }
+}
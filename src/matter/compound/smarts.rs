@@ -0,0 +1,835 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    str::FromStr,
+};
+
+use crate::matter::{
+    compound::{
+        bond::{bond_key, BondOrder},
+        rings::RingInfo,
+        substructure::{AtomMapping, MatchGraph, Vf2},
+        Compound, CompoundError, CompoundResult,
+    },
+    element::Element,
+};
+
+/// Primitives combined with `!` (not), `&` or juxtaposition (high
+/// precedence and), `,` (or) and `;` (low precedence and)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr<T> {
+    Primitive(T),
+    Not(Box<Expr<T>>),
+    And(Vec<Expr<T>>),
+    Or(Vec<Expr<T>>),
+}
+
+impl<T> Expr<T> {
+    fn and(mut parts: Vec<Self>) -> Self {
+        match parts.len() {
+            1 => parts.remove(0),
+            _ => Self::And(parts),
+        }
+    }
+
+    fn or(mut parts: Vec<Self>) -> Self {
+        match parts.len() {
+            1 => parts.remove(0),
+            _ => Self::Or(parts),
+        }
+    }
+
+    fn eval(&self, primitive: &impl Fn(&T) -> bool) -> bool {
+        match self {
+            Self::Primitive(p) => primitive(p),
+            Self::Not(expr) => !expr.eval(primitive),
+            Self::And(parts) => parts.iter().all(|e| e.eval(primitive)),
+            Self::Or(parts) => parts.iter().any(|e| e.eval(primitive)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AtomPrimitive {
+    /// `*`
+    Any,
+    /// `a` (true) or `A` (false)
+    Aromatic(bool),
+    /// `#n`, aromatic or not
+    AtomicNumber(u8),
+    /// Element symbol, lowercase when aromatic: `C`, `c`, `Cl`
+    Symbol { number: u8, aromatic: bool },
+    /// Leading mass number: `[2H]`, `[13C]`
+    Isotope(u16),
+    /// `Dn`: bonds to atoms other than plain hydrogens
+    Degree(u8),
+    /// `Hn`: hydrogens in total, implicit or not
+    Hydrogens(u8),
+    /// `Xn`: connections in total, hydrogens included
+    Connectivity(u8),
+    /// `+n` or `-n`
+    Charge(i8),
+    /// `Rn`: number of SSSR rings holding the atom; bare `R` means any
+    RingCount(Option<u8>),
+    /// `rn`: size of the smallest ring holding the atom; bare `r` means
+    /// any
+    RingSize(Option<u8>),
+    /// `$(...)`: the atom is the first atom of a match of the pattern
+    Recursive(Box<Smarts>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BondPrimitive {
+    /// `~`
+    Any,
+    /// `-`
+    Single,
+    /// `=`
+    Double,
+    /// `#`
+    Triple,
+    /// `:`
+    Aromatic,
+    /// `@`: the bond is in a ring
+    Ring,
+}
+
+pub type AtomExpr = Expr<AtomPrimitive>;
+pub type BondExpr = Expr<BondPrimitive>;
+
+/// Bond written without a symbol: single or aromatic
+fn implicit_bond() -> BondExpr {
+    Expr::Or(vec![
+        Expr::Primitive(BondPrimitive::Single),
+        Expr::Primitive(BondPrimitive::Aromatic),
+    ])
+}
+
+/// A parsed SMARTS query.
+///
+/// Atoms are numbered in the order they're written. Atom map numbers
+/// (`[C:1]`) are kept for reaction templates, and dots may separate
+/// fragments, which match independently of each other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Smarts {
+    source: String,
+    atoms: Vec<AtomExpr>,
+    maps: Vec<Option<u16>>,
+    bonds: BTreeMap<(usize, usize), BondExpr>,
+}
+
+impl Smarts {
+    pub fn parse(pattern: &str) -> CompoundResult<Self> {
+        Parser::new(pattern).smarts()
+    }
+
+    pub fn atom_count(&self) -> usize {
+        self.atoms.len()
+    }
+
+    pub fn atoms(&self) -> &[AtomExpr] {
+        &self.atoms
+    }
+
+    /// Atom map number of atom `i`, if written
+    pub fn map_number(&self, i: usize) -> Option<u16> {
+        self.maps.get(i).copied().flatten()
+    }
+
+    pub fn bonds(&self) -> &BTreeMap<(usize, usize), BondExpr> {
+        &self.bonds
+    }
+
+    fn adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![Vec::new(); self.atoms.len()];
+        for &(a, b) in self.bonds.keys() {
+            adjacency[a].push(b);
+            adjacency[b].push(a);
+        }
+        adjacency
+    }
+}
+
+impl FromStr for Smarts {
+    type Err = CompoundError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Smarts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            chars: source.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn error<T>(&self, message: &str) -> CompoundResult<T> {
+        Err(CompoundError::Parsing(format!(
+            "SMARTS `{}` at {}: {}",
+            self.source, self.pos, message
+        )))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn number(&mut self) -> Option<u16> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    fn count(&mut self, default: u8) -> CompoundResult<u8> {
+        Ok(self.optional_count()?.unwrap_or(default))
+    }
+
+    fn optional_count(&mut self) -> CompoundResult<Option<u8>> {
+        let digits = self.peek().is_some_and(|c| c.is_ascii_digit());
+        match self.number().map(u8::try_from) {
+            None if !digits => Ok(None),
+            Some(Ok(n)) => Ok(Some(n)),
+            _ => self.error("Count out of range"),
+        }
+    }
+
+    fn smarts(&mut self) -> CompoundResult<Smarts> {
+        let mut smarts = Smarts {
+            source: self.source.to_string(),
+            atoms: Vec::new(),
+            maps: Vec::new(),
+            bonds: BTreeMap::new(),
+        };
+        let mut prev: Option<usize> = None;
+        let mut branches: Vec<usize> = Vec::new();
+        let mut bond: Option<BondExpr> = None;
+        let mut open_rings: HashMap<u16, (usize, Option<BondExpr>)> =
+            HashMap::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '(' => {
+                    let Some(atom) = prev else {
+                        return self.error("Branch without an atom");
+                    };
+                    self.pos += 1;
+                    branches.push(atom);
+                }
+                ')' => {
+                    let Some(atom) = branches.pop() else {
+                        return self.error("Unopened branch");
+                    };
+                    if bond.is_some() {
+                        return self.error("Bond without an atom");
+                    }
+                    self.pos += 1;
+                    prev = Some(atom);
+                }
+                '.' => {
+                    if bond.is_some() || !branches.is_empty() {
+                        return self.error("Misplaced dot");
+                    }
+                    self.pos += 1;
+                    prev = None;
+                }
+                '-' | '=' | '#' | ':' | '~' | '@' | '!' if prev.is_some() => {
+                    if bond.is_some() {
+                        return self.error("Two bonds in a row");
+                    }
+                    bond = Some(self.expr(Self::bond_primitive, |p| {
+                        p.peek().is_some_and(|c| "-=#:~@!".contains(c))
+                    })?);
+                }
+                '%' | '0'..='9' => {
+                    let Some(atom) = prev else {
+                        return self.error("Ring closure without an atom");
+                    };
+                    let label = match self.eat('%') {
+                        true => match self.number() {
+                            Some(label) => label,
+                            None => return self.error("Expected ring label"),
+                        },
+                        false => {
+                            self.pos += 1;
+                            c as u16 - '0' as u16
+                        }
+                    };
+                    match open_rings.remove(&label) {
+                        Some((other, opening)) => {
+                            let expr = bond
+                                .take()
+                                .or(opening)
+                                .unwrap_or_else(implicit_bond);
+                            if other == atom
+                                || smarts
+                                    .bonds
+                                    .insert(bond_key(other, atom), expr)
+                                    .is_some()
+                            {
+                                return self
+                                    .error("Ring closure doubles a bond");
+                            }
+                        }
+                        None => {
+                            open_rings.insert(label, (atom, bond.take()));
+                        }
+                    }
+                }
+                _ => {
+                    let (atom, map) = self.atom()?;
+                    let idx = smarts.atoms.len();
+                    smarts.atoms.push(atom);
+                    smarts.maps.push(map);
+                    if let Some(p) = prev {
+                        let expr = bond.take().unwrap_or_else(implicit_bond);
+                        smarts.bonds.insert((p, idx), expr);
+                    }
+                    prev = Some(idx);
+                }
+            }
+        }
+        if smarts.atoms.is_empty() {
+            self.error("Empty pattern")
+        } else if bond.is_some() {
+            self.error("Bond without an atom")
+        } else if !branches.is_empty() {
+            self.error("Unclosed branch")
+        } else if !open_rings.is_empty() {
+            self.error("Unclosed ring")
+        } else {
+            Ok(smarts)
+        }
+    }
+
+    /// Expression of primitives read by `primitive`, where `continues`
+    /// tells whether another primitive follows without an explicit `&`
+    fn expr<T>(
+        &mut self,
+        primitive: fn(&mut Self) -> CompoundResult<T>,
+        continues: fn(&Self) -> bool,
+    ) -> CompoundResult<Expr<T>> {
+        let mut low = Vec::new();
+        loop {
+            let mut or = Vec::new();
+            loop {
+                let mut high = vec![self.unary(primitive)?];
+                while self.eat('&') || continues(self) {
+                    high.push(self.unary(primitive)?);
+                }
+                or.push(Expr::and(high));
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            low.push(Expr::or(or));
+            if !self.eat(';') {
+                break;
+            }
+        }
+        Ok(Expr::and(low))
+    }
+
+    fn unary<T>(
+        &mut self,
+        primitive: fn(&mut Self) -> CompoundResult<T>,
+    ) -> CompoundResult<Expr<T>> {
+        if self.eat('!') {
+            Ok(Expr::Not(Box::new(self.unary(primitive)?)))
+        } else {
+            Ok(Expr::Primitive(primitive(self)?))
+        }
+    }
+
+    fn bond_primitive(&mut self) -> CompoundResult<BondPrimitive> {
+        let primitive = match self.peek() {
+            Some('~') => BondPrimitive::Any,
+            Some('-') => BondPrimitive::Single,
+            Some('=') => BondPrimitive::Double,
+            Some('#') => BondPrimitive::Triple,
+            Some(':') => BondPrimitive::Aromatic,
+            Some('@') => BondPrimitive::Ring,
+            _ => return self.error("Expected a bond"),
+        };
+        self.pos += 1;
+        Ok(primitive)
+    }
+
+    /// Atom along with its map number
+    fn atom(&mut self) -> CompoundResult<(AtomExpr, Option<u16>)> {
+        if !self.eat('[') {
+            return Ok((Expr::Primitive(self.organic_atom()?), None));
+        }
+        let expr = match self.bracket_hydrogen() {
+            Some(expr) => expr,
+            None => self.expr(Self::atom_primitive, |p| {
+                p.peek().is_some_and(|c| !"];,&:".contains(c))
+            })?,
+        };
+        let map = match self.eat(':') {
+            true => match self.number() {
+                Some(map) => Some(map),
+                None => return self.error("Expected atom map number"),
+            },
+            false => None,
+        };
+        if !self.eat(']') {
+            return self.error("Expected `]`");
+        }
+        Ok((expr, map))
+    }
+
+    /// `[H]`, `[2H]` and `[H+]` are hydrogen atoms rather than a hydrogen
+    /// count
+    fn bracket_hydrogen(&mut self) -> Option<AtomExpr> {
+        let start = self.pos;
+        let mass = self.number();
+        if mass.is_none() && self.pos > start {
+            // Out of range: left for the isotope primitive to report
+            self.pos = start;
+            return None;
+        }
+        if self.peek() == Some('H')
+            && self.peek_at(1).is_some_and(|c| "]+-:".contains(c))
+        {
+            self.pos += 1;
+            let mut parts =
+                vec![Expr::Primitive(AtomPrimitive::AtomicNumber(1))];
+            if let Some(mass) = mass {
+                parts.push(Expr::Primitive(AtomPrimitive::Isotope(mass)));
+            }
+            if let Some(c) = self.peek().filter(|c| "+-".contains(*c)) {
+                match self.charge(c) {
+                    Ok(charge) => parts
+                        .push(Expr::Primitive(AtomPrimitive::Charge(charge))),
+                    Err(_) => {
+                        self.pos = start;
+                        return None;
+                    }
+                }
+            }
+            return Some(Expr::and(parts));
+        }
+        self.pos = start;
+        None
+    }
+
+    /// Atoms written outside brackets: the organic subset, `*`, `a`, `A`
+    fn organic_atom(&mut self) -> CompoundResult<AtomPrimitive> {
+        let primitive = match (self.peek(), self.peek_at(1)) {
+            (Some('C'), Some('l')) | (Some('B'), Some('r')) => {
+                let symbol: String =
+                    self.chars[self.pos..self.pos + 2].iter().collect();
+                self.pos += 1;
+                self.symbol(&symbol, false)?
+            }
+            (Some('*'), _) => AtomPrimitive::Any,
+            (Some('a'), _) => AtomPrimitive::Aromatic(true),
+            (Some('A'), _) => AtomPrimitive::Aromatic(false),
+            (Some(c @ ('B' | 'C' | 'N' | 'O' | 'P' | 'S' | 'F' | 'I')), _) => {
+                self.symbol(&c.to_string(), false)?
+            }
+            (Some(c @ ('b' | 'c' | 'n' | 'o' | 'p' | 's')), _) => {
+                self.symbol(&c.to_ascii_uppercase().to_string(), true)?
+            }
+            _ => return self.error("Expected an atom"),
+        };
+        self.pos += 1;
+        Ok(primitive)
+    }
+
+    fn symbol(
+        &self,
+        symbol: &str,
+        aromatic: bool,
+    ) -> CompoundResult<AtomPrimitive> {
+        match Element::from_str(symbol) {
+            Some(element) => Ok(AtomPrimitive::Symbol {
+                number: element.number,
+                aromatic,
+            }),
+            None => self.error(&format!("Unknown element {}", symbol)),
+        }
+    }
+
+    fn charge(&mut self, sign: char) -> CompoundResult<i8> {
+        let mut magnitude = 0;
+        while self.eat(sign) {
+            magnitude += 1;
+        }
+        if magnitude == 1 {
+            magnitude = self.count(1)? as i8;
+        }
+        Ok(if sign == '-' { -magnitude } else { magnitude })
+    }
+
+    fn atom_primitive(&mut self) -> CompoundResult<AtomPrimitive> {
+        let Some(c) = self.peek() else {
+            return self.error("Expected `]`");
+        };
+        // Two-letter symbols win over a one-letter symbol and a primitive
+        if c.is_ascii_uppercase() {
+            if let Some(next) =
+                self.peek_at(1).filter(|n| n.is_ascii_lowercase())
+            {
+                let symbol = format!("{}{}", c, next);
+                if Element::from_str(&symbol).is_some() {
+                    self.pos += 2;
+                    return self.symbol(&symbol, false);
+                }
+            }
+        }
+        if let Some(next) = self.peek_at(1) {
+            let symbol = format!("{}{}", c.to_ascii_uppercase(), next);
+            if matches!((c, next), ('s', 'e') | ('a', 's')) {
+                self.pos += 2;
+                return self.symbol(&symbol, true);
+            }
+        }
+        if c.is_ascii_digit() {
+            return match self.number() {
+                Some(mass) => Ok(AtomPrimitive::Isotope(mass)),
+                None => self.error("Isotope out of range"),
+            };
+        }
+        self.pos += 1;
+        let primitive = match c {
+            '*' => AtomPrimitive::Any,
+            'a' => AtomPrimitive::Aromatic(true),
+            'A' => AtomPrimitive::Aromatic(false),
+            '#' => match self.number() {
+                Some(n) if n > 0 && n <= u8::MAX as u16 => {
+                    AtomPrimitive::AtomicNumber(n as u8)
+                }
+                _ => return self.error("Expected an atomic number"),
+            },
+            'D' => AtomPrimitive::Degree(self.count(1)?),
+            'H' => AtomPrimitive::Hydrogens(self.count(1)?),
+            'X' => AtomPrimitive::Connectivity(self.count(1)?),
+            'R' => AtomPrimitive::RingCount(self.optional_count()?),
+            'r' => AtomPrimitive::RingSize(self.optional_count()?),
+            '+' | '-' => {
+                self.pos -= 1;
+                AtomPrimitive::Charge(self.charge(c)?)
+            }
+            '$' => AtomPrimitive::Recursive(Box::new(self.recursive()?)),
+            'b' | 'c' | 'n' | 'o' | 'p' | 's' => {
+                self.symbol(&c.to_ascii_uppercase().to_string(), true)?
+            }
+            _ if c.is_ascii_uppercase() => {
+                self.symbol(&c.to_string(), false)?
+            }
+            '@' => return self.error("Chirality isn't supported"),
+            _ => {
+                self.pos -= 1;
+                return self.error(&format!("Unknown primitive `{}`", c));
+            }
+        };
+        Ok(primitive)
+    }
+
+    /// `$(...)`, after the `$`
+    fn recursive(&mut self) -> CompoundResult<Smarts> {
+        if !self.eat('(') {
+            return self.error("Expected `(` after `$`");
+        }
+        let start = self.pos;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.peek() {
+                Some('(') => depth += 1,
+                Some(')') => depth -= 1,
+                Some(_) => {}
+                None => return self.error("Unclosed recursive SMARTS"),
+            }
+            self.pos += 1;
+        }
+        let inner: String = self.chars[start..self.pos - 1].iter().collect();
+        Smarts::parse(&inner)
+    }
+}
+
+/// A compound prepared for SMARTS queries
+struct Target {
+    graph: MatchGraph,
+    aromatic: BTreeSet<usize>,
+    rings: RingInfo,
+}
+
+impl Target {
+    fn of(compound: &Compound) -> CompoundResult<Self> {
+        let graph = MatchGraph::of(compound);
+        Ok(Self {
+            aromatic: graph.compound.aromatic_atoms()?,
+            rings: graph.compound.rings(),
+            graph,
+        })
+    }
+
+    fn primitive(&self, primitive: &AtomPrimitive, t: usize) -> bool {
+        let compound = &self.graph.compound;
+        let atom = &compound.atoms()[t];
+        let degree = self.graph.adjacency[t].len();
+        match primitive {
+            AtomPrimitive::Any => true,
+            AtomPrimitive::Aromatic(aromatic) => {
+                self.aromatic.contains(&t) == *aromatic
+            }
+            AtomPrimitive::AtomicNumber(number) => {
+                atom.get_element_num() == *number
+            }
+            AtomPrimitive::Symbol { number, aromatic } => {
+                atom.get_element_num() == *number
+                    && self.aromatic.contains(&t) == *aromatic
+            }
+            AtomPrimitive::Isotope(mass) => {
+                atom.get_element_num() as u16 + atom.neutrons as u16 == *mass
+            }
+            AtomPrimitive::Degree(n) => degree == *n as usize,
+            AtomPrimitive::Hydrogens(n) => compound.hydrogen_count(t) == *n,
+            AtomPrimitive::Connectivity(n) => {
+                degree + compound.implicit_hydrogens(t) as usize == *n as usize
+            }
+            AtomPrimitive::Charge(charge) => atom.charge() == *charge,
+            AtomPrimitive::RingCount(None) => self.rings.is_ring_atom(t),
+            AtomPrimitive::RingCount(Some(n)) => {
+                self.rings.atom_rings(t).len() == *n as usize
+            }
+            AtomPrimitive::RingSize(None) => self.rings.is_ring_atom(t),
+            AtomPrimitive::RingSize(Some(n)) => {
+                self.rings.smallest_ring_size(t) == Some(*n as usize)
+            }
+            AtomPrimitive::Recursive(smarts) => {
+                !self.mappings(smarts, Some(t), 1).is_empty()
+            }
+        }
+    }
+
+    fn bond_primitive(
+        &self,
+        primitive: BondPrimitive,
+        (s, t): (usize, usize),
+    ) -> bool {
+        let order = self.graph.order(s, t);
+        match primitive {
+            BondPrimitive::Any => true,
+            BondPrimitive::Single => order == BondOrder::Single,
+            BondPrimitive::Double => order == BondOrder::Double,
+            BondPrimitive::Triple => order == BondOrder::Triple,
+            BondPrimitive::Aromatic => order == BondOrder::Aromatic,
+            BondPrimitive::Ring => self.rings.is_ring_bond(s, t),
+        }
+    }
+
+    /// Up to `limit` matches of `smarts`, with its first atom on `root`
+    /// when given, indexed by view atom
+    fn mappings(
+        &self,
+        smarts: &Smarts,
+        root: Option<usize>,
+        limit: usize,
+    ) -> Vec<Vec<usize>> {
+        let adjacency = smarts.adjacency();
+        let atom_ok = |p: usize, t: usize| {
+            (p != 0 || root.is_none_or(|root| root == t))
+                && smarts.atoms[p]
+                    .eval(&|primitive| self.primitive(primitive, t))
+        };
+        let bond_ok = |(p, q): (usize, usize), target: (usize, usize)| {
+            smarts.bonds[&bond_key(p, q)]
+                .eval(&|&primitive| self.bond_primitive(primitive, target))
+        };
+        Vf2::new(&adjacency, &self.graph.adjacency, &atom_ok, &bond_ok, false)
+            .mappings(limit)
+    }
+}
+
+impl Compound {
+    /// Every match of a SMARTS query, as a mapping from the query's atoms
+    /// to the compound's. Symmetric matches are all listed.
+    ///
+    /// Plain hydrogens are counted by `H` and `X` rather than matched as
+    /// atoms; only isotopic and charged hydrogens can match `[#1]`.
+    pub fn smarts_matches(
+        &self,
+        smarts: &Smarts,
+    ) -> CompoundResult<Vec<AtomMapping>> {
+        let target = Target::of(self)?;
+        Ok(target
+            .mappings(smarts, None, usize::MAX)
+            .into_iter()
+            .map(|mapping| {
+                mapping
+                    .into_iter()
+                    .enumerate()
+                    .map(|(p, t)| (p, target.graph.atoms[t]))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Whether a SMARTS query matches somewhere in the compound
+    pub fn has_smarts_match(&self, smarts: &Smarts) -> CompoundResult<bool> {
+        Ok(!Target::of(self)?.mappings(smarts, None, 1).is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::{
+        atom::Atom,
+        compound::{
+            builder::{BuildStep, CompoundBuilder},
+            substituent::{AlkylGroup, Substituent},
+        },
+    };
+
+    fn build(step: BuildStep) -> Compound {
+        step(&mut CompoundBuilder::new())
+            .and_then(|b| b.build())
+            .expect("Valid compound expected")
+    }
+
+    fn count(compound: &Compound, pattern: &str) -> usize {
+        let smarts = Smarts::parse(pattern).expect("Valid SMARTS expected");
+        compound.smarts_matches(&smarts).expect("Matches").len()
+    }
+
+    #[test]
+    fn smarts_parsing() {
+        let smarts = Smarts::parse("[C:1](=[O:2])[OH]").expect("Valid SMARTS");
+        assert_eq!(smarts.atom_count(), 3);
+        assert_eq!(smarts.map_number(1), Some(2));
+        assert_eq!(smarts.map_number(2), None);
+        assert_eq!(
+            smarts.bonds()[&(0, 1)],
+            Expr::Primitive(BondPrimitive::Double)
+        );
+        assert_eq!(smarts.to_string(), "[C:1](=[O:2])[OH]");
+        let ring = Smarts::parse("C1CC=1").expect("Valid SMARTS");
+        assert_eq!(
+            ring.bonds()[&(0, 2)],
+            Expr::Primitive(BondPrimitive::Double)
+        );
+        assert_eq!(
+            Smarts::parse("[Cl,Br;!R]").expect("Valid SMARTS").atoms()[0],
+            Expr::And(vec![
+                Expr::Or(vec![
+                    Expr::Primitive(AtomPrimitive::Symbol {
+                        number: 17,
+                        aromatic: false
+                    }),
+                    Expr::Primitive(AtomPrimitive::Symbol {
+                        number: 35,
+                        aromatic: false
+                    }),
+                ]),
+                Expr::Not(Box::new(Expr::Primitive(AtomPrimitive::RingCount(
+                    None
+                )))),
+            ])
+        );
+        for invalid in [
+            "", "C(", "C)", "C1CC", "[C", "C=", "[C@H]", "[Q]", "$(C",
+            "[99999C]", "[99999H]", "[R300]", "[r256]", "[D99999]",
+        ] {
+            assert!(
+                matches!(
+                    Smarts::parse(invalid),
+                    Err(CompoundError::Parsing(_))
+                ),
+                "{} should be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn smarts_atom_primitives() {
+        let ethanol =
+            build(|b| b.linear_chain(2)?.substitute(2, Substituent::Hydroxy));
+        let ether = build(|b| {
+            b.linear_chain(3)?.replace_atom(2, Atom::new_unchecked(8))
+        });
+        assert_eq!(count(&ethanol, "[OX2H]"), 1);
+        assert_eq!(count(&ether, "[OX2H]"), 0);
+        assert_eq!(count(&ether, "[OD2]([#6])[#6]"), 2);
+        assert_eq!(count(&ethanol, "[CH3]"), 1);
+        assert_eq!(count(&ethanol, "[CH2][OH1]"), 1);
+
+        let isobutane =
+            build(|b| b.linear_chain(3)?.add_alkyl(2, AlkylGroup::Methyl));
+        assert_eq!(count(&isobutane, "[CD3]"), 1);
+        assert_eq!(count(&isobutane, "[CX4H1]"), 1);
+
+        let toluene = build(|b| b.benzene()?.add_alkyl(1, AlkylGroup::Methyl));
+        assert_eq!(count(&toluene, "c1ccccc1"), 12);
+        assert_eq!(count(&toluene, "C1CCCCC1"), 0);
+        assert_eq!(count(&toluene, "[a;r6]"), 6);
+        assert_eq!(count(&toluene, "[CR0]-c"), 1);
+        assert_eq!(count(&toluene, "[R1]"), 6);
+
+        let methylamine = build(|b| {
+            b.linear_chain(2)?.replace_atom(2, Atom::new_unchecked(7))
+        });
+        assert_eq!(count(&methylamine, "[NH2]"), 1);
+        assert_eq!(count(&methylamine, "[N+]"), 0);
+    }
+
+    #[test]
+    fn smarts_logic_and_recursion() {
+        let bromopropanol = build(|b| {
+            b.linear_chain(3)?
+                .substitute(1, Substituent::Hydroxy)?
+                .brominate(3)
+        });
+        assert_eq!(count(&bromopropanol, "[Br,O]"), 2);
+        assert_eq!(count(&bromopropanol, "[!#6]"), 2);
+        assert_eq!(count(&bromopropanol, "[C;!$(C[OH])]"), 2);
+        assert_eq!(count(&bromopropanol, "[$([CH2][OH]),$(CBr)]"), 2);
+        assert_eq!(count(&bromopropanol, "[$(C(CO)CBr)]"), 1);
+        assert_eq!(count(&bromopropanol, "O.Br"), 1);
+
+        let propene = build(|b| b.linear_chain(3)?.double_bond(1, 2));
+        assert_eq!(count(&propene, "C=C"), 2);
+        assert_eq!(count(&propene, "C=,#C"), 2);
+        assert_eq!(count(&propene, "C!=C"), 2);
+        assert_eq!(count(&propene, "C~C~C"), 2);
+        let cyclohexene = build(|b| b.ring(6)?.double_bond(1, 2));
+        assert_eq!(count(&cyclohexene, "C=@C"), 2);
+        assert_eq!(count(&propene, "C@C"), 0);
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::matter::compound::{
+    bond::{bond_key, BondOrder},
+    Compound, CompoundResult,
+};
+
+/// Elements which may be written without brackets when they carry no
+/// charge, no isotope label and their default number of hydrogens
+const ORGANIC_SUBSET: [&str; 10] =
+    ["B", "C", "N", "O", "P", "S", "F", "Cl", "Br", "I"];
+
+impl Compound {
+    /// SMILES string of the compound, with hydrogens left implicit and
+    /// aromatic atoms written in lowercase.
+    ///
+    /// The string isn't canonical: atoms are visited from the lowest index,
+    /// following lower indices first.
+    pub fn to_smiles(&self) -> CompoundResult<String> {
+        let compound = self.with_implicit_hydrogens();
+        SmilesWriter::new(&compound)?.write()
+    }
+}
+
+/// Depth-first walk of a compound which writes its SMILES string
+struct SmilesWriter<'a> {
+    compound: &'a Compound,
+    orders: BTreeMap<(usize, usize), BondOrder>,
+    aromatic_atoms: BTreeSet<usize>,
+    /// Spanning tree children of every atom, smallest branch first
+    children: Vec<Vec<usize>>,
+    /// Bonds left out of the spanning tree, written as ring closure digits
+    closures: Vec<Vec<usize>>,
+    /// Ring closure digits currently open, by bond
+    digits: HashMap<(usize, usize), usize>,
+    /// `/` or `\\` on single bonds around configured double bonds, by bond
+    /// in the order its atoms are written
+    directions: HashMap<(usize, usize), char>,
+}
+
+impl<'a> SmilesWriter<'a> {
+    fn new(compound: &'a Compound) -> CompoundResult<Self> {
+        let n = compound.atom_count();
+        let mut writer = Self {
+            compound,
+            orders: compound.aromatic_bond_orders()?,
+            aromatic_atoms: compound.aromatic_atoms()?,
+            children: vec![Vec::new(); n],
+            closures: vec![Vec::new(); n],
+            digits: HashMap::new(),
+            directions: HashMap::new(),
+        };
+        let adjacency = compound.adjacency();
+        let mut visited = vec![false; n];
+        let mut sizes = vec![1; n];
+        for root in 0..n {
+            if !visited[root] {
+                writer.span(root, None, &adjacency, &mut visited, &mut sizes);
+            }
+        }
+        for children in writer.children.iter_mut() {
+            children.sort_by_key(|&child| (sizes[child], child));
+        }
+        writer.mark_double_bonds();
+        Ok(writer)
+    }
+
+    /// Picks `/` or `\\` for the spanning tree bonds around every configured
+    /// double bond. Written from atom `p` to atom `q`, `/` puts `q` above
+    /// `p`.
+    fn mark_double_bonds(&mut self) {
+        for ((a, b), [x, y]) in self.compound.double_bond_configurations() {
+            // Substituents above their double bond atom: the cis pair
+            let mut marks = Vec::new();
+            for (end, partner, cis) in [(a, b, x), (b, a, y)] {
+                for s in self.compound.double_bond_substituents(end, partner) {
+                    let above = s == cis;
+                    if self.children[end].contains(&s) {
+                        marks.push(((end, s), above));
+                    } else if self.children[s].contains(&end) {
+                        marks.push(((s, end), !above));
+                    }
+                }
+            }
+            // Agree with a conjugated double bond already marked, otherwise
+            // start with `/`
+            let flip = marks
+                .iter()
+                .find_map(|(bond, slash)| {
+                    Some((*self.directions.get(bond)? == '/') != *slash)
+                })
+                .unwrap_or_else(|| marks.first().is_some_and(|(_, s)| !s));
+            for (bond, slash) in marks {
+                let mark = if slash != flip { '/' } else { '\\' };
+                self.directions.entry(bond).or_insert(mark);
+            }
+        }
+    }
+
+    /// Builds the spanning tree below atom `i`, recording the size of every
+    /// branch.
+    fn span(
+        &mut self,
+        i: usize,
+        parent: Option<usize>,
+        adjacency: &[Vec<usize>],
+        visited: &mut [bool],
+        sizes: &mut [usize],
+    ) {
+        visited[i] = true;
+        let mut neighbors = adjacency[i].clone();
+        neighbors.sort();
+        for j in neighbors {
+            if Some(j) == parent {
+                continue;
+            } else if !visited[j] {
+                self.children[i].push(j);
+                self.span(j, Some(i), adjacency, visited, sizes);
+                sizes[i] += sizes[j];
+            } else if !self.closures[i].contains(&j) {
+                self.closures[i].push(j);
+                self.closures[j].push(i);
+            }
+        }
+    }
+
+    fn write(mut self) -> CompoundResult<String> {
+        let mut written = vec![false; self.compound.atom_count()];
+        let mut components = Vec::new();
+        for root in 0..self.compound.atom_count() {
+            if !written[root] {
+                let mut s = String::new();
+                self.write_atom(root, &mut s, &mut written);
+                components.push(s);
+            }
+        }
+        Ok(components.join("."))
+    }
+
+    /// Writes atom `i`, its ring closures and its branches.
+    fn write_atom(&mut self, i: usize, s: &mut String, written: &mut [bool]) {
+        written[i] = true;
+        s.push_str(&self.atom_symbol(i));
+        for j in self.closures[i].clone() {
+            let key = bond_key(i, j);
+            if let Some(digit) = self.digits.remove(&key) {
+                s.push_str(&closure_digit(digit));
+            } else {
+                let digit = (1..)
+                    .find(|d| !self.digits.values().any(|v| v == d))
+                    .expect("Digits are unbounded");
+                self.digits.insert(key, digit);
+                s.push_str(&self.bond_symbol(i, j));
+                s.push_str(&closure_digit(digit));
+            }
+        }
+        let children = self.children[i].clone();
+        for (k, &j) in children.iter().enumerate() {
+            let branch = k + 1 < children.len();
+            if branch {
+                s.push('(');
+            }
+            s.push_str(&self.bond_symbol(i, j));
+            self.write_atom(j, s, written);
+            if branch {
+                s.push(')');
+            }
+        }
+    }
+
+    /// Bond symbol written between atoms `a` and `b`; single and aromatic
+    /// bonds go without one, except single bonds joining aromatic atoms.
+    fn bond_symbol(&self, a: usize, b: usize) -> String {
+        if let Some(&direction) = self.directions.get(&(a, b)) {
+            return direction.to_string();
+        }
+        match self.orders[&bond_key(a, b)] {
+            BondOrder::Single
+                if self.aromatic_atoms.contains(&a)
+                    && self.aromatic_atoms.contains(&b) =>
+            {
+                "-".into()
+            }
+            BondOrder::Single | BondOrder::Aromatic => String::new(),
+            BondOrder::Double => "=".into(),
+            BondOrder::Triple => "#".into(),
+        }
+    }
+
+    /// Atom `i` as written in SMILES, in brackets unless its hydrogens
+    /// follow from its bonds.
+    fn atom_symbol(&self, i: usize) -> String {
+        let atom = &self.compound.atoms()[i];
+        let element = atom.get_element();
+        let aromatic = self.aromatic_atoms.contains(&i);
+        let symbol = if aromatic {
+            element.as_str().to_lowercase()
+        } else {
+            element.as_str().to_string()
+        };
+        let hydrogens = self.compound.implicit_hydrogens(i);
+        if ORGANIC_SUBSET.contains(&element.as_str())
+            && atom.charge() == 0
+            && atom.neutrons == 0
+            && hydrogens == self.default_hydrogens(i)
+        {
+            return symbol;
+        }
+        let mut bracket = String::from("[");
+        if atom.neutrons > 0 {
+            bracket.push_str(&(element.number + atom.neutrons).to_string());
+        }
+        bracket.push_str(&symbol);
+        match hydrogens {
+            0 => (),
+            1 => bracket.push('H'),
+            h => bracket.push_str(&format!("H{}", h)),
+        }
+        match atom.charge() {
+            0 => (),
+            1 => bracket.push('+'),
+            -1 => bracket.push('-'),
+            c if c > 0 => bracket.push_str(&format!("+{}", c)),
+            c => bracket.push_str(&format!("-{}", -c)),
+        }
+        bracket.push(']');
+        bracket
+    }
+
+    /// Hydrogens a SMILES reader gives an unbracketed atom `i`: up to its
+    /// lowest standard valence which covers its bonds. Aromatic bonds count
+    /// as one each, plus one for the atom's share of the π system.
+    fn default_hydrogens(&self, i: usize) -> u8 {
+        let mut bonded: u8 = self
+            .orders
+            .iter()
+            .filter(|(&(a, b), _)| a == i || b == i)
+            .map(|(_, order)| order.as_u8())
+            .sum();
+        if self.aromatic_atoms.contains(&i) {
+            bonded += 1;
+        }
+        self.compound.atoms()[i]
+            .get_element()
+            .standard_valences()
+            .iter()
+            .find(|&&valence| valence >= bonded)
+            .map_or(0, |valence| valence - bonded)
+    }
+}
+
+fn closure_digit(digit: usize) -> String {
+    if digit < 10 {
+        digit.to_string()
+    } else {
+        format!("%{}", digit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::{
+        atom::Atom,
+        compound::{
+            builder::{BuildStep, CompoundBuilder},
+            substituent::{AlkylGroup, Substituent},
+        },
+    };
+
+    fn build(steps: &[BuildStep]) -> Compound {
+        let mut builder = CompoundBuilder::new();
+        for step in steps {
+            step(&mut builder).expect("Valid step expected");
+        }
+        builder.build().expect("Valid compound expected")
+    }
+
+    #[test]
+    fn smiles_chains() {
+        let cases: [(&[BuildStep], &str); 6] = [
+            (
+                &[
+                    |b| b.linear_chain(2),
+                    |b| b.substitute(2, Substituent::Hydroxy),
+                ],
+                "CCO",
+            ),
+            (&[|b| b.linear_chain(3), |b| b.double_bond(1, 2)], "C=CC"),
+            (&[|b| b.linear_chain(3), |b| b.triple_bond(1, 2)], "C#CC"),
+            (
+                &[
+                    |b| b.linear_chain(1),
+                    |b| b.substitute(1, Substituent::Nitro),
+                ],
+                "C[N+](=O)[O-]",
+            ),
+            (
+                &[
+                    |b| b.linear_chain(2),
+                    |b| b.replace_atom(2, Atom::nitrogen().ion(1)),
+                ],
+                "C[NH3+]",
+            ),
+            (
+                &[
+                    |b| b.linear_chain(3),
+                    |b| b.add_alkyl(2, AlkylGroup::Methyl),
+                ],
+                "CC(C)C",
+            ),
+        ];
+        for (steps, expected) in cases {
+            assert_eq!(build(steps).to_smiles().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn smiles_rings() {
+        let cases: [(&[BuildStep], &str); 7] = [
+            (&[|b| b.ring(6)], "C1CCCCC1"),
+            (&[|b| b.benzene()], "c1ccccc1"),
+            (
+                &[|b| b.benzene(), |b| b.add_alkyl(1, AlkylGroup::Methyl)],
+                "c1(C)ccccc1",
+            ),
+            (
+                &[|b| b.benzene(), |b| b.replace_atom(1, Atom::nitrogen())],
+                "n1ccccc1",
+            ),
+            (
+                &[
+                    |b| b.ring(5),
+                    |b| b.double_bond(2, 3),
+                    |b| b.double_bond(4, 5),
+                    |b| b.replace_atom(1, Atom::nitrogen()),
+                ],
+                "[nH]1cccc1",
+            ),
+            (
+                &[
+                    |b| b.ring(5),
+                    |b| b.double_bond(2, 3),
+                    |b| b.double_bond(4, 5),
+                    |b| b.replace_atom(1, Atom::carbon().ion(-1)),
+                ],
+                "[cH-]1cccc1",
+            ),
+            (
+                &[
+                    |b| b.ring(4),
+                    |b| b.double_bond(1, 2),
+                    |b| b.double_bond(3, 4),
+                ],
+                "C1=CC=C1",
+            ),
+        ];
+        for (steps, expected) in cases {
+            assert_eq!(build(steps).to_smiles().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn smiles_double_bond_stereo() {
+        use crate::matter::compound::stereo::EzDescriptor::{E, Z};
+        let cases: [(BuildStep, &str); 4] = [
+            (
+                |b| b.linear_chain(4)?.stereo_double_bond(2, 3, E),
+                "C/C=C/C",
+            ),
+            (
+                |b| b.linear_chain(4)?.stereo_double_bond(2, 3, Z),
+                "C/C=C\\C",
+            ),
+            (
+                |b| {
+                    b.linear_chain(6)?
+                        .stereo_double_bond(2, 3, E)?
+                        .stereo_double_bond(4, 5, Z)
+                },
+                "C/C=C/C=C\\C",
+            ),
+            (
+                |b| {
+                    b.linear_chain(4)?
+                        .stereo_double_bond(2, 3, Z)?
+                        .substitute(2, Substituent::Chloro)
+                },
+                "C/C(/Cl)=C/C",
+            ),
+        ];
+        for (step, expected) in cases {
+            assert_eq!(build(&[step]).to_smiles().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn smiles_isotopes_and_fragments() {
+        let compound = Compound::from_edges(
+            vec![
+                Atom::carbon(),
+                Atom::deuterium(),
+                Atom::hydrogen(),
+                Atom::hydrogen(),
+                Atom::hydrogen(),
+                Atom::chlorine().ion(-1),
+            ],
+            &[
+                (0, 1, BondOrder::Single),
+                (0, 2, BondOrder::Single),
+                (0, 3, BondOrder::Single),
+                (0, 4, BondOrder::Single),
+            ],
+        );
+        assert_eq!(compound.to_smiles().unwrap(), "C[2H].[Cl-]");
+    }
+}
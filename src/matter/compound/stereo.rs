@@ -0,0 +1,730 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use crate::matter::compound::{
+    bond::{bond_key, BondOrder},
+    Compound, CompoundError, CompoundResult,
+};
+
+/// Cahn–Ingold–Prelog descriptor of a tetrahedral stereocenter
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Hash,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    strum_macros::Display,
+)]
+pub enum CipDescriptor {
+    /// Priorities decrease clockwise, lowest priority pointing away
+    R,
+    /// Priorities decrease anticlockwise, lowest priority pointing away
+    S,
+}
+
+impl CipDescriptor {
+    pub fn inverted(&self) -> Self {
+        match self {
+            Self::R => Self::S,
+            Self::S => Self::R,
+        }
+    }
+}
+
+/// Configuration of a stereogenic double bond by CIP priority
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Hash,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    strum_macros::Display,
+)]
+pub enum EzDescriptor {
+    /// Highest priorities on the same side (zusammen)
+    Z,
+    /// Highest priorities on opposite sides (entgegen)
+    E,
+}
+
+impl EzDescriptor {
+    pub fn inverted(&self) -> Self {
+        match self {
+            Self::Z => Self::E,
+            Self::E => Self::Z,
+        }
+    }
+}
+
+/// Atom of the hierarchical digraph explored from a stereocenter by the
+/// CIP rules
+#[derive(Clone, Debug)]
+struct CipNode {
+    /// Atom index; `None` for implicit hydrogens and phantom atoms
+    atom: Option<usize>,
+    /// Atomic number (0 for phantom atoms), then neutrons
+    label: (u8, u8),
+    /// Atoms from the stereocenter down to this one
+    path: Vec<usize>,
+    /// Duplicate atoms stand for multiple bonds and ring closures, and
+    /// carry nothing but phantom atoms.
+    duplicate: bool,
+}
+
+impl CipNode {
+    fn phantom() -> Self {
+        Self {
+            atom: None,
+            label: (0, 0),
+            path: Vec::new(),
+            duplicate: true,
+        }
+    }
+
+    fn hydrogen() -> Self {
+        Self {
+            label: (1, 0),
+            ..Self::phantom()
+        }
+    }
+}
+
+/// What a pass over the hierarchical digraph compares atoms by
+#[derive(Clone, Copy)]
+enum CipRule {
+    /// Rule 1a
+    AtomicNumber,
+    /// Rule 1b, only consulted once every sphere ties by atomic number
+    Mass,
+}
+
+impl CipRule {
+    fn key(&self, node: &CipNode) -> u16 {
+        let (number, neutrons) = node.label;
+        match self {
+            Self::AtomicNumber => number as u16,
+            Self::Mass => number as u16 + neutrons as u16,
+        }
+    }
+}
+
+/// Ranks the branches around a stereocenter (rules 1a and 1b: atomic
+/// number, then mass)
+struct Cip<'a> {
+    compound: &'a Compound,
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl<'a> Cip<'a> {
+    fn new(compound: &'a Compound) -> Self {
+        Self {
+            compound,
+            adjacency: compound.adjacency(),
+        }
+    }
+
+    fn node(&self, i: usize, path: Vec<usize>, duplicate: bool) -> CipNode {
+        let atom = &self.compound.atoms[i];
+        CipNode {
+            atom: Some(i),
+            label: (atom.get_element_num(), atom.neutrons),
+            path,
+            duplicate,
+        }
+    }
+
+    fn order(&self, a: usize, b: usize) -> u8 {
+        self.compound
+            .bond_order(a, b)
+            .map_or(1, |order| order.as_u8())
+    }
+
+    /// Branches around atom `center` by the neighbour they start from, an
+    /// implicit hydrogen written as `center`
+    fn branches(&self, center: usize) -> Vec<(usize, CipNode)> {
+        let mut branches: Vec<(usize, CipNode)> = self.adjacency[center]
+            .iter()
+            .map(|&j| (j, self.node(j, vec![center, j], false)))
+            .collect();
+        for _ in 0..self.compound.implicit_hydrogens(center) {
+            branches.push((center, CipNode::hydrogen()));
+        }
+        branches
+    }
+
+    /// Neighbour of `center` starting its highest ranked branch among
+    /// `around`; `None` when the top two tie.
+    fn highest(&self, center: usize, around: &[usize]) -> Option<usize> {
+        let mut ranked: Vec<(usize, CipNode)> = self
+            .branches(center)
+            .into_iter()
+            .filter(|(j, _)| around.contains(j))
+            .collect();
+        ranked.sort_by(|(_, a), (_, b)| self.compare(b, a));
+        match &ranked[..] {
+            [(top, _)] => Some(*top),
+            [(top, a), (_, b), ..] if self.compare(a, b).is_gt() => Some(*top),
+            _ => None,
+        }
+    }
+
+    /// Atoms one sphere further from the stereocenter, highest priority
+    /// first. Every atom but hydrogen is padded to three substituents with
+    /// phantom atoms.
+    fn substituents(&self, node: &CipNode) -> Vec<CipNode> {
+        if node.label.0 <= 1 {
+            return Vec::new();
+        }
+        let mut substituents = Vec::new();
+        if let (Some(i), false) = (node.atom, node.duplicate) {
+            let parent = node.path[node.path.len() - 2];
+            for &j in self.adjacency[i].iter().filter(|&&j| j != parent) {
+                if node.path.contains(&j) {
+                    substituents.push(self.node(j, Vec::new(), true));
+                } else {
+                    let mut path = node.path.clone();
+                    path.push(j);
+                    substituents.push(self.node(j, path, false));
+                }
+                for _ in 1..self.order(i, j) {
+                    substituents.push(self.node(j, Vec::new(), true));
+                }
+            }
+            for _ in 1..self.order(i, parent) {
+                substituents.push(self.node(parent, Vec::new(), true));
+            }
+            for _ in 0..self.compound.implicit_hydrogens(i) {
+                substituents.push(CipNode::hydrogen());
+            }
+        }
+        while substituents.len() < 3 {
+            substituents.push(CipNode::phantom());
+        }
+        substituents.sort_by(|a, b| self.compare(b, a));
+        substituents
+    }
+
+    /// Compares two branches by atomic number across the whole digraph,
+    /// and only when that ties all the way out by mass.
+    fn compare(&self, a: &CipNode, b: &CipNode) -> Ordering {
+        self.compare_by(CipRule::AtomicNumber, a, b)
+            .then_with(|| self.compare_by(CipRule::Mass, a, b))
+    }
+
+    /// Compares two branches sphere by sphere under one rule, exploring
+    /// each sphere in order of precedence; the first difference decides.
+    fn compare_by(&self, rule: CipRule, a: &CipNode, b: &CipNode) -> Ordering {
+        let (mut sphere_a, mut sphere_b) = (vec![a.clone()], vec![b.clone()]);
+        while !sphere_a.is_empty() || !sphere_b.is_empty() {
+            let keys = |sphere: &[CipNode]| -> Vec<u16> {
+                sphere.iter().map(|node| rule.key(node)).collect()
+            };
+            match keys(&sphere_a).cmp(&keys(&sphere_b)) {
+                Ordering::Equal => (),
+                order => return order,
+            }
+            let next = |sphere: &[CipNode]| -> Vec<CipNode> {
+                sphere
+                    .iter()
+                    .flat_map(|node| self.substituents(node))
+                    .collect()
+            };
+            (sphere_a, sphere_b) = (next(&sphere_a), next(&sphere_b));
+        }
+        Ordering::Equal
+    }
+}
+
+/// Whether `order` is an even permutation of `reference`
+fn is_even_permutation(reference: &[usize; 4], order: &[usize; 4]) -> bool {
+    let mut positions = order.map(|x| {
+        reference
+            .iter()
+            .position(|&r| r == x)
+            .expect("Both orders hold the same atoms")
+    });
+    let mut swaps = 0;
+    for k in 0..positions.len() {
+        while positions[k] != k {
+            let target = positions[k];
+            positions.swap(k, target);
+            swaps += 1;
+        }
+    }
+    swaps % 2 == 0
+}
+
+impl Compound {
+    /// Neighbours of an atom forming four single bonds, one of which may be
+    /// to an implicit hydrogen (written as the atom's own index).
+    fn tetrahedral_neighbors(&self, i: usize) -> Option<[usize; 4]> {
+        let mut around = self.neighbors(i);
+        let implicit = self.implicit_hydrogens(i) as usize;
+        if around.len() + implicit != 4 || implicit > 1 {
+            return None;
+        }
+        if around
+            .iter()
+            .any(|&j| self.bond_order(i, j) != Some(BondOrder::Single))
+        {
+            return None;
+        }
+        if implicit == 1 {
+            around.push(i);
+        }
+        around.try_into().ok()
+    }
+
+    /// Neighbours of stereocenter `i`, highest CIP priority first (an
+    /// implicit hydrogen written as `i`). `None` when atom `i` isn't a
+    /// stereocenter.
+    pub fn cip_priorities(&self, i: usize) -> Option<[usize; 4]> {
+        self.tetrahedral_neighbors(i)?;
+        let cip = Cip::new(self);
+        let mut ranked = cip.branches(i);
+        ranked.sort_by(|(_, a), (_, b)| cip.compare(b, a));
+        if ranked
+            .windows(2)
+            .any(|pair| cip.compare(&pair[0].1, &pair[1].1).is_eq())
+        {
+            return None;
+        }
+        let ranked: Vec<usize> = ranked.into_iter().map(|(j, _)| j).collect();
+        ranked.try_into().ok()
+    }
+
+    /// Atoms bonded to four different groups, whether or not their
+    /// configuration is recorded
+    pub fn stereocenters(&self) -> Vec<usize> {
+        (0..self.atom_count())
+            .filter(|&i| self.cip_priorities(i).is_some())
+            .collect()
+    }
+
+    /// Recorded arrangement around atom `i`: seen from the first neighbour,
+    /// the other three turn anticlockwise (as `@` in SMILES). The atom's
+    /// own index stands for its implicit hydrogen.
+    pub fn chirality(&self, i: usize) -> Option<[usize; 4]> {
+        self.chirality.get(&i).copied()
+    }
+
+    /// Records the arrangement around atom `i` (see [`Self::chirality`]).
+    pub fn set_chirality(
+        &mut self,
+        i: usize,
+        around: [usize; 4],
+    ) -> CompoundResult<()> {
+        let mut expected = self.tetrahedral_neighbors(i).ok_or_else(|| {
+            CompoundError::Stereo(format!("Atom {} isn't tetrahedral", i))
+        })?;
+        let mut given = around;
+        expected.sort();
+        given.sort();
+        if expected != given {
+            return Err(CompoundError::Stereo(format!(
+                "{:?} aren't the neighbours of atom {}",
+                around, i
+            )));
+        }
+        self.chirality.insert(i, around);
+        Ok(())
+    }
+
+    /// CIP descriptor of stereocenter `i`; `None` when atom `i` isn't a
+    /// stereocenter or has no recorded arrangement.
+    pub fn cip_descriptor(&self, i: usize) -> Option<CipDescriptor> {
+        let around = self.chirality(i)?;
+        let [a, b, c, d] = self.cip_priorities(i)?;
+        // Seen from the lowest priority, a → b → c turns anticlockwise,
+        // i.e. clockwise with it pointing away
+        if is_even_permutation(&around, &[d, a, b, c]) {
+            Some(CipDescriptor::R)
+        } else {
+            Some(CipDescriptor::S)
+        }
+    }
+
+    /// CIP descriptor of every stereocenter with a recorded arrangement,
+    /// by atom index
+    pub fn cip_descriptors(&self) -> BTreeMap<usize, CipDescriptor> {
+        self.chirality
+            .keys()
+            .filter_map(|&i| Some((i, self.cip_descriptor(i)?)))
+            .collect()
+    }
+
+    /// Arranges stereocenter `i` to get the given descriptor.
+    pub fn set_cip_descriptor(
+        &mut self,
+        i: usize,
+        descriptor: CipDescriptor,
+    ) -> CompoundResult<()> {
+        let [a, b, c, d] = self.cip_priorities(i).ok_or_else(|| {
+            CompoundError::Stereo(format!("Atom {} isn't a stereocenter", i))
+        })?;
+        let around = match descriptor {
+            CipDescriptor::R => [d, a, b, c],
+            CipDescriptor::S => [d, a, c, b],
+        };
+        self.chirality.insert(i, around);
+        Ok(())
+    }
+
+    /// Atoms bonded to `a` other than `b`, an implicit hydrogen written as
+    /// `a`
+    pub(crate) fn double_bond_substituents(
+        &self,
+        a: usize,
+        b: usize,
+    ) -> Vec<usize> {
+        let mut around: Vec<usize> =
+            self.neighbors(a).into_iter().filter(|&j| j != b).collect();
+        around.extend(std::iter::repeat_n(
+            a,
+            self.implicit_hydrogens(a) as usize,
+        ));
+        around
+    }
+
+    /// Highest priority substituents at either end of the double bond
+    /// between `a` and `b`; `None` unless the bond is stereogenic.
+    ///
+    /// Each end needs two different substituents, or a single one beside
+    /// a lone pair (as on an imine's nitrogen), and the bond can't lie in
+    /// a ring of fewer than eight atoms.
+    fn double_bond_tops(&self, a: usize, b: usize) -> Option<[usize; 2]> {
+        if self.bond_order(a, b) != Some(BondOrder::Double) {
+            return None;
+        }
+        let rings = self.rings();
+        if rings
+            .bond_rings(a, b)
+            .iter()
+            .any(|&ring| rings.sssr[ring].len() < 8)
+        {
+            return None;
+        }
+        let cip = Cip::new(self);
+        let top = |end: usize, other: usize| {
+            let around = self.double_bond_substituents(end, other);
+            let single = around.iter().all(|&j| {
+                j == end || self.bond_order(end, j) == Some(BondOrder::Single)
+            });
+            match around.len() {
+                1 if single && self.atoms[end].get_element_num() != 6 => {
+                    Some(around[0])
+                }
+                2 if single => cip.highest(end, &around),
+                _ => None,
+            }
+        };
+        Some([top(a, b)?, top(b, a)?])
+    }
+
+    /// Double bonds with a configuration, whether or not it's recorded
+    pub fn stereogenic_double_bonds(&self) -> Vec<(usize, usize)> {
+        self.bond_orders()
+            .into_iter()
+            .filter(|&(_, order)| order == BondOrder::Double)
+            .map(|(bond, _)| bond)
+            .filter(|&(a, b)| self.double_bond_tops(a, b).is_some())
+            .collect()
+    }
+
+    /// Recorded configuration of the double bond between `a` and `b`: a
+    /// substituent of `a` and one of `b` lying on the same side.
+    pub fn double_bond_configuration(
+        &self,
+        a: usize,
+        b: usize,
+    ) -> Option<[usize; 2]> {
+        let [x, y] = *self.double_bond_stereo.get(&bond_key(a, b))?;
+        Some(if a < b { [x, y] } else { [y, x] })
+    }
+
+    /// Every recorded double bond configuration, by bond
+    pub fn double_bond_configurations(
+        &self,
+    ) -> BTreeMap<(usize, usize), [usize; 2]> {
+        self.double_bond_stereo
+            .iter()
+            .map(|(&bond, &cis)| (bond, cis))
+            .collect()
+    }
+
+    /// Records that substituents `cis[0]` of `a` and `cis[1]` of `b` lie on
+    /// the same side of the double bond between them.
+    pub fn set_double_bond_configuration(
+        &mut self,
+        a: usize,
+        b: usize,
+        cis: [usize; 2],
+    ) -> CompoundResult<()> {
+        if self.bond_order(a, b) != Some(BondOrder::Double) {
+            return Err(CompoundError::Stereo(format!(
+                "Atoms {} and {} aren't double bonded",
+                a, b
+            )));
+        }
+        if !self.double_bond_substituents(a, b).contains(&cis[0])
+            || !self.double_bond_substituents(b, a).contains(&cis[1])
+        {
+            return Err(CompoundError::Stereo(format!(
+                "{:?} aren't substituents of the double bond {}={}",
+                cis, a, b
+            )));
+        }
+        let cis = if a < b { cis } else { [cis[1], cis[0]] };
+        self.double_bond_stereo.insert(bond_key(a, b), cis);
+        Ok(())
+    }
+
+    /// E/Z descriptor of the double bond between `a` and `b`; `None` when
+    /// it isn't stereogenic or has no recorded configuration.
+    pub fn ez_descriptor(&self, a: usize, b: usize) -> Option<EzDescriptor> {
+        let [x, y] = self.double_bond_configuration(a, b)?;
+        let [top_a, top_b] = self.double_bond_tops(a, b)?;
+        if (x == top_a) == (y == top_b) {
+            Some(EzDescriptor::Z)
+        } else {
+            Some(EzDescriptor::E)
+        }
+    }
+
+    /// E/Z descriptor of every double bond with a recorded configuration,
+    /// by bond
+    pub fn ez_descriptors(&self) -> BTreeMap<(usize, usize), EzDescriptor> {
+        self.double_bond_stereo
+            .keys()
+            .filter_map(|&(a, b)| Some(((a, b), self.ez_descriptor(a, b)?)))
+            .collect()
+    }
+
+    /// Configures the double bond between `a` and `b` to get the given
+    /// descriptor.
+    pub fn set_ez_descriptor(
+        &mut self,
+        a: usize,
+        b: usize,
+        descriptor: EzDescriptor,
+    ) -> CompoundResult<()> {
+        let [top_a, top_b] = self.double_bond_tops(a, b).ok_or_else(|| {
+            CompoundError::Stereo(format!(
+                "The bond between atoms {} and {} isn't a stereogenic double \
+                 bond",
+                a, b
+            ))
+        })?;
+        let other = |end: usize, partner: usize, top: usize| {
+            self.double_bond_substituents(end, partner)
+                .into_iter()
+                .find(|&j| j != top)
+        };
+        let cis = match descriptor {
+            EzDescriptor::Z => [top_a, top_b],
+            EzDescriptor::E => match (other(a, b, top_a), other(b, a, top_b)) {
+                (_, Some(y)) => [top_a, y],
+                (Some(x), None) => [x, top_b],
+                (None, None) => {
+                    return Err(CompoundError::Stereo(format!(
+                        "Atoms {} and {} have a substituent each, which \
+                         can't be placed apart",
+                        a, b
+                    )))
+                }
+            },
+        };
+        self.set_double_bond_configuration(a, b, cis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::{
+        atom::Atom,
+        compound::{
+            builder::{BuildStep, CompoundBuilder},
+            substituent::AlkylGroup,
+        },
+    };
+
+    #[test]
+    fn stereo_detects_stereocenters() {
+        let bromobutane = CompoundBuilder::new()
+            .linear_chain(4)
+            .and_then(|b| b.brominate(2))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(bromobutane.stereocenters(), vec![1]);
+        assert_eq!(
+            bromobutane.with_implicit_hydrogens().stereocenters(),
+            vec![1]
+        );
+        let butane = CompoundBuilder::new()
+            .linear_chain(4)
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert!(butane.stereocenters().is_empty());
+
+        // 3-methylhexane: propyl outranks ethyl one sphere further out
+        let compound = CompoundBuilder::new()
+            .linear_chain(6)
+            .and_then(|b| b.add_alkyl(3, AlkylGroup::Methyl))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected")
+            .with_implicit_hydrogens();
+        assert_eq!(compound.stereocenters(), vec![2]);
+        assert_eq!(compound.cip_priorities(2), Some([3, 1, 6, 2]));
+
+        // 3-methylpent-1-ene: the double bond's duplicate carbon puts vinyl
+        // ahead of ethyl
+        let compound = CompoundBuilder::new()
+            .linear_chain(5)
+            .and_then(|b| b.double_bond(1, 2))
+            .and_then(|b| b.add_alkyl(3, AlkylGroup::Methyl))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected")
+            .with_implicit_hydrogens();
+        assert_eq!(compound.cip_priorities(2), Some([1, 3, 5, 2]));
+    }
+
+    #[test]
+    fn stereo_ranks_isotopes() {
+        // CHDBrCl
+        let atoms = vec![
+            Atom::carbon(),
+            Atom::bromine(),
+            Atom::chlorine(),
+            Atom::deuterium(),
+            Atom::hydrogen(),
+        ];
+        let edges: Vec<_> = (1..5).map(|j| (0, j, BondOrder::Single)).collect();
+        let mut compound = Compound::from_edges(atoms, &edges);
+        assert_eq!(compound.cip_priorities(0), Some([1, 2, 3, 4]));
+        compound
+            .set_chirality(0, [4, 1, 2, 3])
+            .expect("Tetrahedral");
+        assert_eq!(compound.cip_descriptor(0), Some(CipDescriptor::R));
+        compound
+            .set_chirality(0, [3, 1, 2, 4])
+            .expect("Tetrahedral");
+        assert_eq!(compound.cip_descriptor(0), Some(CipDescriptor::S));
+        assert!(compound.set_chirality(0, [0, 1, 2, 3]).is_err());
+
+        // Survives folding the plain hydrogen away and back
+        let implicit = compound.with_implicit_hydrogens();
+        assert_eq!(implicit.chirality(0), Some([3, 1, 2, 0]));
+        assert_eq!(implicit.cip_descriptor(0), Some(CipDescriptor::S));
+        let explicit = implicit.with_explicit_hydrogens();
+        assert_eq!(explicit.cip_descriptor(0), Some(CipDescriptor::S));
+
+        // CH2BrCl has two identical hydrogens
+        compound.atoms[3] = Atom::hydrogen();
+        assert_eq!(compound.cip_priorities(0), None);
+        assert_eq!(compound.cip_descriptor(0), None);
+        assert!(compound.set_cip_descriptor(0, CipDescriptor::R).is_err());
+    }
+
+    #[test]
+    fn stereo_ranks_atomic_number_before_isotopes() {
+        // CH3-CH(-CHD-CH3)-CH2-CH2-OH: the deuterium one sphere out loses
+        // to the oxygen further along the other branch
+        let oxygen = Atom::new_unchecked(8);
+        let mut atoms = vec![Atom::carbon(), Atom::hydrogen(), Atom::carbon()];
+        atoms.extend(vec![Atom::hydrogen(); 3]);
+        atoms.extend([Atom::carbon(), Atom::deuterium(), Atom::hydrogen()]);
+        atoms.push(Atom::carbon());
+        atoms.extend(vec![Atom::hydrogen(); 3]);
+        atoms.extend([Atom::carbon(), Atom::hydrogen(), Atom::hydrogen()]);
+        atoms.extend([Atom::carbon(), Atom::hydrogen(), Atom::hydrogen()]);
+        atoms.extend([oxygen, Atom::hydrogen()]);
+        let edges: Vec<_> = [
+            (0, 1),
+            (0, 2),
+            (2, 3),
+            (2, 4),
+            (2, 5),
+            (0, 6),
+            (6, 7),
+            (6, 8),
+            (6, 9),
+            (9, 10),
+            (9, 11),
+            (9, 12),
+            (0, 13),
+            (13, 14),
+            (13, 15),
+            (13, 16),
+            (16, 17),
+            (16, 18),
+            (16, 19),
+            (19, 20),
+        ]
+        .into_iter()
+        .map(|(a, b)| (a, b, BondOrder::Single))
+        .collect();
+        let compound = Compound::from_edges(atoms, &edges);
+        assert_eq!(compound.cip_priorities(0), Some([13, 6, 2, 1]));
+    }
+
+    #[test]
+    fn stereo_double_bonds() {
+        let build = |step: BuildStep| {
+            step(&mut CompoundBuilder::new())
+                .and_then(|b| b.build())
+                .expect("Valid compound expected")
+        };
+        let propene = build(|b| b.linear_chain(3)?.double_bond(1, 2));
+        assert!(propene.stereogenic_double_bonds().is_empty());
+        let cyclohexene = build(|b| b.ring(6)?.double_bond(1, 2));
+        assert!(cyclohexene.stereogenic_double_bonds().is_empty());
+        // An imine's nitrogen has a lone pair opposite its substituent
+        let imine = build(|b| {
+            b.linear_chain(4)?
+                .replace_atom(3, Atom::nitrogen())?
+                .double_bond(2, 3)
+        });
+        assert_eq!(imine.stereogenic_double_bonds(), vec![(1, 2)]);
+
+        let mut butene = build(|b| b.linear_chain(4)?.double_bond(2, 3));
+        butene
+            .set_ez_descriptor(1, 2, EzDescriptor::E)
+            .expect("Stereogenic double bond");
+        assert_eq!(butene.ez_descriptor(2, 1), Some(EzDescriptor::E));
+        let implicit = butene.with_implicit_hydrogens();
+        assert_eq!(implicit.ez_descriptor(1, 2), Some(EzDescriptor::E));
+        let explicit = implicit.with_explicit_hydrogens();
+        assert_eq!(explicit.ez_descriptor(1, 2), Some(EzDescriptor::E));
+        assert_eq!(
+            explicit.ez_descriptors(),
+            BTreeMap::from([((1, 2), EzDescriptor::E)])
+        );
+        assert!(butene.set_ez_descriptor(0, 1, EzDescriptor::Z).is_err());
+    }
+
+    #[test]
+    fn stereo_descriptor_round_trip() {
+        let mut compound = CompoundBuilder::new()
+            .linear_chain(4)
+            .and_then(|b| b.brominate(2))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        for descriptor in [CipDescriptor::R, CipDescriptor::S] {
+            compound
+                .set_cip_descriptor(1, descriptor)
+                .expect("Stereocenter expected");
+            assert_eq!(compound.cip_descriptor(1), Some(descriptor));
+            assert_eq!(
+                compound.cip_descriptors(),
+                BTreeMap::from([(1, descriptor)])
+            );
+        }
+        assert_eq!(CipDescriptor::R.inverted(), CipDescriptor::S);
+    }
+}
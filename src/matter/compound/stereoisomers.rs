@@ -0,0 +1,371 @@
+use std::collections::HashSet;
+
+use crate::matter::compound::{
+    stereo::{CipDescriptor, EzDescriptor},
+    Compound, CompoundError, CompoundResult,
+};
+
+/// How two structures relate to each other
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum StereoRelationship {
+    /// The same compound, however it's drawn
+    Identical,
+    /// Non-superimposable mirror images
+    Enantiomers,
+    /// Same constitution, but stereoisomers which aren't mirror images
+    Diastereomers,
+    /// Same molecular formula, different connectivity
+    ConstitutionalIsomers,
+    /// Different molecular formulas
+    Unrelated,
+}
+
+/// Stereocenters and stereogenic double bonds of a compound
+enum StereoElement {
+    Center(usize),
+    DoubleBond(usize, usize),
+}
+
+impl Compound {
+    fn stereo_elements(&self) -> Vec<StereoElement> {
+        let centers =
+            self.stereocenters().into_iter().map(StereoElement::Center);
+        let bonds = self
+            .stereogenic_double_bonds()
+            .into_iter()
+            .map(|(a, b)| StereoElement::DoubleBond(a, b));
+        centers.chain(bonds).collect()
+    }
+
+    /// Key which is the same for two compounds exactly when they're the
+    /// same stereoisomer: the canonical key, plus the descriptors of the
+    /// stereo elements at their canonical positions. Elements without a
+    /// recorded configuration show as `?`.
+    ///
+    /// Pseudoasymmetric centers (r/s) and ring cis/trans isomerism without
+    /// stereocenters aren't told apart.
+    pub fn stereo_key(&self) -> String {
+        let compound = self.with_implicit_hydrogens();
+        let elements = compound.stereo_elements();
+        let atoms: Vec<usize> = elements
+            .iter()
+            .flat_map(|element| match *element {
+                StereoElement::Center(i) => vec![i],
+                StereoElement::DoubleBond(a, b) => vec![a, b],
+            })
+            .collect();
+        let configuration = compound
+            .canonical_labellings(&atoms)
+            .into_iter()
+            .map(|positions| {
+                let mut positions = positions.into_iter();
+                let mut items: Vec<String> = elements
+                    .iter()
+                    .map(|element| match *element {
+                        StereoElement::Center(i) => format!(
+                            "{}{}",
+                            positions.next().expect("Position of the center"),
+                            compound
+                                .cip_descriptor(i)
+                                .map_or("?".into(), |d| d.to_string())
+                        ),
+                        StereoElement::DoubleBond(a, b) => {
+                            let p = positions.next().expect("Position of a");
+                            let q = positions.next().expect("Position of b");
+                            format!(
+                                "{}={}{}",
+                                p.min(q),
+                                p.max(q),
+                                compound
+                                    .ez_descriptor(a, b)
+                                    .map_or("?".into(), |d| d.to_string())
+                            )
+                        }
+                    })
+                    .collect();
+                items.sort();
+                items.join(",")
+            })
+            .min()
+            .unwrap_or_default();
+        format!("{}|{}", compound.canonical_key(), configuration)
+    }
+
+    /// The compound reflected in a mirror: every stereocenter inverted,
+    /// double bonds left as they are.
+    pub fn mirror_image(&self) -> Self {
+        let mut mirror = self.clone();
+        for around in mirror.chirality.values_mut() {
+            around.swap(2, 3);
+        }
+        mirror
+    }
+
+    /// Whether the compound differs from its mirror image
+    pub fn is_chiral(&self) -> bool {
+        self.stereo_key() != self.mirror_image().stereo_key()
+    }
+
+    /// Whether the compound has stereocenters yet is its own mirror image
+    pub fn is_meso(&self) -> bool {
+        !self.stereocenters().is_empty()
+            && self.cip_descriptors().len() == self.stereocenters().len()
+            && !self.is_chiral()
+    }
+
+    /// Every stereoisomer of the compound, each once: configurations which
+    /// turn out to be the same compound (as the two halves of a meso
+    /// compound) are collapsed.
+    ///
+    /// Stereoisomers are listed from all-R (and Z) onwards, cycling
+    /// through the last stereo elements first.
+    pub fn stereoisomers(&self) -> CompoundResult<Vec<Compound>> {
+        let elements = self.stereo_elements();
+        if elements.len() >= usize::BITS as usize {
+            return Err(CompoundError::Stereo(format!(
+                "{} stereo elements are too many to enumerate",
+                elements.len()
+            )));
+        }
+        let mut seen = HashSet::new();
+        let mut isomers = Vec::new();
+        for mask in 0..1usize << elements.len() {
+            let mut isomer = self.clone();
+            for (k, element) in elements.iter().enumerate() {
+                let flipped = mask >> (elements.len() - 1 - k) & 1 == 1;
+                match *element {
+                    StereoElement::Center(i) => {
+                        let descriptor = match flipped {
+                            false => CipDescriptor::R,
+                            true => CipDescriptor::S,
+                        };
+                        isomer.set_cip_descriptor(i, descriptor)?;
+                    }
+                    StereoElement::DoubleBond(a, b) => {
+                        let descriptor = match flipped {
+                            false => EzDescriptor::Z,
+                            true => EzDescriptor::E,
+                        };
+                        isomer.set_ez_descriptor(a, b, descriptor)?;
+                    }
+                }
+            }
+            if seen.insert(isomer.stereo_key()) {
+                isomers.push(isomer);
+            }
+        }
+        Ok(isomers)
+    }
+
+    /// How this structure relates to `other`: identical, enantiomers,
+    /// diastereomers, constitutional isomers or unrelated.
+    ///
+    /// Telling stereoisomers apart needs every stereo element of both
+    /// structures configured.
+    pub fn relationship(
+        &self,
+        other: &Compound,
+    ) -> CompoundResult<StereoRelationship> {
+        if self.molecular_formula() != other.molecular_formula()
+            || self.charge() != other.charge()
+        {
+            return Ok(StereoRelationship::Unrelated);
+        }
+        if !self.same_constitution(other) {
+            return Ok(StereoRelationship::ConstitutionalIsomers);
+        }
+        for compound in [self, other] {
+            let configured = compound.cip_descriptors().len()
+                + compound.ez_descriptors().len();
+            if configured != compound.stereo_elements().len() {
+                return Err(CompoundError::Stereo(
+                    "Every stereo element needs a configuration".into(),
+                ));
+            }
+        }
+        let key = other.stereo_key();
+        if self.stereo_key() == key {
+            Ok(StereoRelationship::Identical)
+        } else if self.mirror_image().stereo_key() == key {
+            Ok(StereoRelationship::Enantiomers)
+        } else {
+            Ok(StereoRelationship::Diastereomers)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::compound::builder::{BuildStep, CompoundBuilder};
+    use CipDescriptor::{R, S};
+    use StereoRelationship::*;
+
+    fn build(step: BuildStep) -> Compound {
+        step(&mut CompoundBuilder::new())
+            .and_then(|b| b.build())
+            .expect("Valid compound expected")
+    }
+
+    fn names(compound: &Compound) -> Vec<String> {
+        compound
+            .stereoisomers()
+            .expect("Stereoisomers expected")
+            .iter()
+            .map(|isomer| isomer.iupac_name().expect("Name expected"))
+            .collect()
+    }
+
+    #[test]
+    fn stereoisomers_collapse_meso_forms() {
+        let bromobutane = build(|b| b.linear_chain(4)?.brominate(2));
+        assert_eq!(
+            names(&bromobutane),
+            vec!["(R)-2-bromobutane", "(S)-2-bromobutane"]
+        );
+        let dibromobutane =
+            build(|b| b.linear_chain(4)?.brominate(2)?.brominate(3));
+        let isomers = dibromobutane.stereoisomers().expect("Stereoisomers");
+        assert_eq!(
+            names(&dibromobutane),
+            vec![
+                "(2R,3R)-2,3-dibromobutane",
+                "(2R,3S)-2,3-dibromobutane",
+                "(2S,3S)-2,3-dibromobutane"
+            ]
+        );
+        let meso: Vec<bool> = isomers.iter().map(|i| i.is_meso()).collect();
+        assert_eq!(meso, vec![false, true, false]);
+        assert!(isomers[0].is_chiral());
+
+        let dibromopentane =
+            build(|b| b.linear_chain(5)?.brominate(2)?.brominate(3));
+        assert_eq!(names(&dibromopentane).len(), 4);
+        // (2E,4Z) and (2Z,4E) are the same compound
+        let hexadiene =
+            build(|b| b.linear_chain(6)?.double_bond(2, 3)?.double_bond(4, 5));
+        assert_eq!(
+            names(&hexadiene),
+            vec![
+                "(2Z,4Z)-hexa-2,4-diene",
+                "(2Z,4E)-hexa-2,4-diene",
+                "(2E,4E)-hexa-2,4-diene"
+            ]
+        );
+        let butane = build(|b| b.linear_chain(4));
+        assert_eq!(names(&butane), vec!["butane"]);
+    }
+
+    #[test]
+    fn stereoisomer_relationships() {
+        let bromobutane = |atom_num, descriptor| {
+            CompoundBuilder::new()
+                .linear_chain(4)
+                .and_then(|b| b.brominate(atom_num))
+                .and_then(|b| b.stereocenter(atom_num, descriptor))
+                .and_then(|b| b.build())
+                .expect("Valid compound expected")
+        };
+        let relationship = |a: &Compound, b: &Compound| {
+            a.relationship(b).expect("Configured compounds")
+        };
+        // Drawn from either end
+        assert_eq!(
+            relationship(&bromobutane(2, R), &bromobutane(3, R)),
+            Identical
+        );
+        assert_eq!(
+            relationship(&bromobutane(2, R), &bromobutane(2, S)),
+            Enantiomers
+        );
+
+        let dibromobutane = |first, second| {
+            CompoundBuilder::new()
+                .linear_chain(4)
+                .and_then(|b| b.brominate(2))
+                .and_then(|b| b.brominate(3))
+                .and_then(|b| b.stereocenter(2, first))
+                .and_then(|b| b.stereocenter(3, second))
+                .and_then(|b| b.build())
+                .expect("Valid compound expected")
+        };
+        assert_eq!(
+            relationship(&dibromobutane(R, S), &dibromobutane(S, R)),
+            Identical
+        );
+        assert_eq!(
+            relationship(&dibromobutane(R, R), &dibromobutane(S, S)),
+            Enantiomers
+        );
+        assert_eq!(
+            relationship(&dibromobutane(R, R), &dibromobutane(R, S)),
+            Diastereomers
+        );
+        let but_2_ene = |descriptor| {
+            CompoundBuilder::new()
+                .linear_chain(4)
+                .and_then(|b| b.stereo_double_bond(2, 3, descriptor))
+                .and_then(|b| b.build())
+                .expect("Valid compound expected")
+        };
+        assert_eq!(
+            relationship(
+                &but_2_ene(EzDescriptor::E),
+                &but_2_ene(EzDescriptor::Z)
+            ),
+            Diastereomers
+        );
+
+        let primary = build(|b| b.linear_chain(4)?.brominate(1));
+        assert_eq!(
+            relationship(&primary, &bromobutane(2, R)),
+            ConstitutionalIsomers
+        );
+        let propane = build(|b| b.linear_chain(3));
+        assert_eq!(relationship(&primary, &propane), Unrelated);
+        let unconfigured = build(|b| b.linear_chain(4)?.brominate(2));
+        assert!(matches!(
+            unconfigured.relationship(&bromobutane(2, R)),
+            Err(CompoundError::Stereo(_))
+        ));
+    }
+
+    #[test]
+    fn stereoisomers_of_a_symmetric_chain() {
+        let tetrabromohexane = |descriptors: [CipDescriptor; 4]| {
+            let mut builder = CompoundBuilder::new();
+            builder.linear_chain(6).expect("Valid chain expected");
+            for (atom_num, descriptor) in (2..=5).zip(descriptors) {
+                builder
+                    .brominate(atom_num)
+                    .and_then(|b| b.stereocenter(atom_num, descriptor))
+                    .expect("Valid stereocenter expected");
+            }
+            builder.build().expect("Valid compound expected")
+        };
+        // 16 configurations, of which 6 pairs are the same compound drawn
+        // from either end
+        let unconfigured = CompoundBuilder::new()
+            .linear_chain(6)
+            .and_then(|b| b.brominate(2))
+            .and_then(|b| b.brominate(3))
+            .and_then(|b| b.brominate(4))
+            .and_then(|b| b.brominate(5))
+            .and_then(|b| b.build())
+            .expect("Valid compound expected");
+        assert_eq!(names(&unconfigured).len(), 10);
+        // Drawn from the other end, each center keeps its own descriptor
+        let alternating = tetrabromohexane([R, S, R, S]);
+        let relationship = |a: &Compound, b: &Compound| {
+            a.relationship(b).expect("Configured compounds")
+        };
+        assert_eq!(
+            relationship(&alternating, &tetrabromohexane([S, R, S, R])),
+            Identical
+        );
+        assert_ne!(
+            relationship(&alternating, &tetrabromohexane([R, R, S, S])),
+            Identical
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::{fmt, str::FromStr};
+
+use crate::matter::{
+    atom::Atom,
+    compound::{
+        bond::BondOrder,
+        builder::{CompoundBuilderError, CompoundBuilderResult},
+    },
+};
+
+/// Group which can take the place of a hydrogen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Substituent {
+    Fluoro,
+    Chloro,
+    Bromo,
+    Iodo,
+    /// -OH
+    Hydroxy,
+    /// -NH₂
+    Amino,
+    /// -C≡N
+    Cyano,
+    /// -OCH₃
+    Methoxy,
+    /// -N⁺(=O)O⁻
+    Nitro,
+}
+
+impl Substituent {
+    /// Heavy atoms of the group; the first one bonds to the parent chain.
+    /// Hydrogens are left for the builder to fill.
+    pub fn atoms(&self) -> Vec<Atom> {
+        let oxygen = Atom::new_unchecked(8);
+        match self {
+            Self::Fluoro => vec![Atom::new_unchecked(9)],
+            Self::Chloro => vec![Atom::chlorine()],
+            Self::Bromo => vec![Atom::bromine()],
+            Self::Iodo => vec![Atom::new_unchecked(53)],
+            Self::Hydroxy => vec![oxygen],
+            Self::Amino => vec![Atom::nitrogen()],
+            Self::Cyano => vec![Atom::carbon(), Atom::nitrogen()],
+            Self::Methoxy => vec![oxygen, Atom::carbon()],
+            Self::Nitro => {
+                vec![Atom::nitrogen().ion(1), oxygen.clone(), oxygen.ion(-1)]
+            }
+        }
+    }
+
+    /// Bonds within the group as `(parent, child, order)`, indexing
+    /// [`Substituent::atoms`].
+    pub fn bonds(&self) -> Vec<(usize, usize, BondOrder)> {
+        match self {
+            Self::Cyano => vec![(0, 1, BondOrder::Triple)],
+            Self::Methoxy => vec![(0, 1, BondOrder::Single)],
+            Self::Nitro => {
+                vec![(0, 1, BondOrder::Double), (0, 2, BondOrder::Single)]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Alkyl group which can take the place of a hydrogen
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum AlkylGroup {
+    Methyl,
+    Ethyl,
+    Propyl,
+    /// -CH(CH₃)₂
+    Isopropyl,
+    Butyl,
+    /// -CH(CH₃)CH₂CH₃
+    SecButyl,
+    /// -CH₂CH(CH₃)₂
+    Isobutyl,
+    /// -C(CH₃)₃
+    TertButyl,
+    /// Unbranched chain of the given number of carbons
+    NAlkyl(usize),
+}
+
+impl AlkylGroup {
+    pub fn carbon_count(&self) -> usize {
+        match self {
+            Self::Methyl => 1,
+            Self::Ethyl => 2,
+            Self::Propyl | Self::Isopropyl => 3,
+            Self::Butyl | Self::SecButyl | Self::Isobutyl | Self::TertButyl => {
+                4
+            }
+            Self::NAlkyl(count) => *count,
+        }
+    }
+
+    /// Carbons of the group; the first one bonds to the parent chain.
+    pub fn atoms(&self) -> Vec<Atom> {
+        vec![Atom::carbon(); self.carbon_count()]
+    }
+
+    /// Bonds within the group as `(parent, child)`, indexing
+    /// [`AlkylGroup::atoms`].
+    pub fn bonds(&self) -> Vec<(usize, usize)> {
+        match self {
+            Self::Isopropyl => vec![(0, 1), (0, 2)],
+            Self::SecButyl => vec![(0, 1), (0, 2), (2, 3)],
+            Self::Isobutyl => vec![(0, 1), (1, 2), (1, 3)],
+            Self::TertButyl => vec![(0, 1), (0, 2), (0, 3)],
+            _ => (1..self.carbon_count()).map(|i| (i - 1, i)).collect(),
+        }
+    }
+}
+
+/// Position on a benzene ring relative to the substituent on atom 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum ArenePosition {
+    /// Next to it (1,2)
+    Ortho,
+    /// One carbon away (1,3)
+    Meta,
+    /// Across the ring (1,4)
+    Para,
+}
+
+impl ArenePosition {
+    /// Labeled atom number of the position on a benzene ring
+    pub fn atom_num(&self) -> usize {
+        match self {
+            Self::Ortho => 2,
+            Self::Meta => 3,
+            Self::Para => 4,
+        }
+    }
+
+    /// Position of ring atom `atom_num` relative to atom 1, if it's one
+    /// of the three.
+    pub fn from_atom_num(atom_num: usize) -> Option<Self> {
+        match atom_num {
+            2 | 6 => Some(Self::Ortho),
+            3 | 5 => Some(Self::Meta),
+            4 => Some(Self::Para),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Substituent {
+    /// Writes the group as [`Substituent::from_str`] parses it
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Fluoro => "F",
+            Self::Chloro => "Cl",
+            Self::Bromo => "Br",
+            Self::Iodo => "I",
+            Self::Hydroxy => "OH",
+            Self::Amino => "NH2",
+            Self::Cyano => "CN",
+            Self::Methoxy => "OCH3",
+            Self::Nitro => "NO2",
+        })
+    }
+}
+
+impl FromStr for Substituent {
+    type Err = CompoundBuilderError;
+
+    /// Parses the group as it's written on a condensed formula (e.g. `OH`,
+    /// `NO2`).
+    fn from_str(s: &str) -> CompoundBuilderResult<Self> {
+        match s {
+            "F" => Ok(Self::Fluoro),
+            "Cl" => Ok(Self::Chloro),
+            "Br" => Ok(Self::Bromo),
+            "I" => Ok(Self::Iodo),
+            "OH" => Ok(Self::Hydroxy),
+            "NH2" => Ok(Self::Amino),
+            "CN" => Ok(Self::Cyano),
+            "OCH3" => Ok(Self::Methoxy),
+            "NO2" => Ok(Self::Nitro),
+            _ => Err(CompoundBuilderError::UnknownSubstituentError(s.into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substituents_round_trip_through_strings() {
+        let groups = [
+            Substituent::Fluoro,
+            Substituent::Chloro,
+            Substituent::Bromo,
+            Substituent::Iodo,
+            Substituent::Hydroxy,
+            Substituent::Amino,
+            Substituent::Cyano,
+            Substituent::Methoxy,
+            Substituent::Nitro,
+        ];
+        for group in groups {
+            let parsed = group.to_string().parse::<Substituent>();
+            assert_eq!(parsed.ok(), Some(group), "{}", group);
+        }
+    }
+}
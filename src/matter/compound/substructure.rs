@@ -0,0 +1,363 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::matter::compound::{
+    bond::{bond_key, BondOrder},
+    Compound,
+};
+
+/// Pattern atom index to the target atom it lands on
+pub type AtomMapping = BTreeMap<usize, usize>;
+
+/// VF2-style search for every mapping of a pattern graph into a target
+/// graph which keeps the pattern's bonds.
+///
+/// The mapping grows one pattern atom at a time, in an order where every
+/// atom (but the first of each fragment) neighbours one already mapped, so
+/// candidates come from the neighbours of that atom's image. VF2's
+/// look-ahead prunes targets with too few bonds to go round.
+pub(crate) struct Vf2<'a> {
+    pattern: &'a [Vec<usize>],
+    target: &'a [Vec<usize>],
+    /// Whether pattern atom `p` may land on target atom `t`
+    atom_ok: &'a dyn Fn(usize, usize) -> bool,
+    /// Whether pattern bond `(p, q)` may land on target bond `(s, t)`
+    bond_ok: &'a dyn Fn((usize, usize), (usize, usize)) -> bool,
+    /// Full isomorphism: every target atom and bond is covered too
+    exact: bool,
+    /// Pattern atoms in the order they get mapped
+    order: Vec<usize>,
+    /// Neighbour of each pattern atom mapped before it, if any
+    anchor: Vec<Option<usize>>,
+}
+
+impl<'a> Vf2<'a> {
+    pub(crate) fn new(
+        pattern: &'a [Vec<usize>],
+        target: &'a [Vec<usize>],
+        atom_ok: &'a dyn Fn(usize, usize) -> bool,
+        bond_ok: &'a dyn Fn((usize, usize), (usize, usize)) -> bool,
+        exact: bool,
+    ) -> Self {
+        let n = pattern.len();
+        let mut order = Vec::with_capacity(n);
+        let mut anchor = vec![None; n];
+        let mut seen = vec![false; n];
+        // Each fragment from its most connected atom, breadth first
+        let mut starts: Vec<usize> = (0..n).collect();
+        starts.sort_by_key(|&p| std::cmp::Reverse(pattern[p].len()));
+        for start in starts {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut queue = VecDeque::from([start]);
+            while let Some(p) = queue.pop_front() {
+                order.push(p);
+                for &q in pattern[p].iter() {
+                    if !seen[q] {
+                        seen[q] = true;
+                        anchor[q] = Some(p);
+                        queue.push_back(q);
+                    }
+                }
+            }
+        }
+        Self {
+            pattern,
+            target,
+            atom_ok,
+            bond_ok,
+            exact,
+            order,
+            anchor,
+        }
+    }
+
+    /// Up to `limit` mappings, each indexed by pattern atom
+    pub(crate) fn mappings(&self, limit: usize) -> Vec<Vec<usize>> {
+        let mut found = Vec::new();
+        if self.exact && self.pattern.len() != self.target.len() {
+            return found;
+        }
+        let mut mapped = vec![None; self.pattern.len()];
+        let mut used = vec![false; self.target.len()];
+        self.extend(0, &mut mapped, &mut used, &mut found, limit);
+        found
+    }
+
+    fn extend(
+        &self,
+        depth: usize,
+        mapped: &mut [Option<usize>],
+        used: &mut [bool],
+        found: &mut Vec<Vec<usize>>,
+        limit: usize,
+    ) {
+        if found.len() >= limit {
+            return;
+        }
+        let Some(&p) = self.order.get(depth) else {
+            found.push(mapped.iter().map(|t| t.expect("Mapped")).collect());
+            return;
+        };
+        let candidates: Vec<usize> = match self.anchor[p] {
+            Some(q) => {
+                self.target[mapped[q].expect("Anchors come first")].clone()
+            }
+            None => (0..self.target.len()).collect(),
+        };
+        for t in candidates {
+            if used[t] || !self.feasible(p, t, mapped, used) {
+                continue;
+            }
+            mapped[p] = Some(t);
+            used[t] = true;
+            self.extend(depth + 1, mapped, used, found, limit);
+            mapped[p] = None;
+            used[t] = false;
+        }
+    }
+
+    /// VF2's feasibility rules for adding `p → t` to the mapping
+    fn feasible(
+        &self,
+        p: usize,
+        t: usize,
+        mapped: &[Option<usize>],
+        used: &[bool],
+    ) -> bool {
+        let (degree_p, degree_t) =
+            (self.pattern[p].len(), self.target[t].len());
+        if (self.exact && degree_p != degree_t) || degree_p > degree_t {
+            return false;
+        }
+        if !(self.atom_ok)(p, t) {
+            return false;
+        }
+        let mut mapped_neighbors = 0;
+        for &q in self.pattern[p].iter() {
+            let Some(s) = mapped[q] else {
+                continue;
+            };
+            if !self.target[t].contains(&s) || !(self.bond_ok)((p, q), (t, s)) {
+                return false;
+            }
+            mapped_neighbors += 1;
+        }
+        let used_neighbors =
+            self.target[t].iter().filter(|&&s| used[s]).count();
+        if self.exact && used_neighbors != mapped_neighbors {
+            return false;
+        }
+        // Look-ahead: the pattern's unmapped neighbours need somewhere to go
+        degree_p - mapped_neighbors <= degree_t - used_neighbors
+    }
+}
+
+/// Heavy-atom view of a compound which matching runs on: plain hydrogens
+/// are folded into counts, and indices lead back to the compound's atoms.
+pub(crate) struct MatchGraph {
+    pub(crate) compound: Compound,
+    /// Compound atom index of every atom in the view
+    pub(crate) atoms: Vec<usize>,
+    pub(crate) adjacency: Vec<Vec<usize>>,
+    /// Bond orders, aromatic rings as [`BondOrder::Aromatic`]
+    orders: BTreeMap<(usize, usize), BondOrder>,
+}
+
+impl MatchGraph {
+    pub(crate) fn of(compound: &Compound) -> Self {
+        let folded = compound.foldable_hydrogens();
+        let atoms = (0..compound.atom_count())
+            .filter(|i| !folded.contains_key(i))
+            .collect();
+        let compound = compound.with_implicit_hydrogens();
+        let orders = compound
+            .aromatic_bond_orders()
+            .unwrap_or_else(|_| compound.bond_orders());
+        Self {
+            adjacency: compound.adjacency(),
+            compound,
+            atoms,
+            orders,
+        }
+    }
+
+    /// Whether the atoms carry the same element, isotope and charge
+    fn same_atom(&self, i: usize, other: &MatchGraph, j: usize) -> bool {
+        let (a, b) = (&self.compound.atoms[i], &other.compound.atoms[j]);
+        a == b && a.neutrons == b.neutrons && a.charge() == b.charge()
+    }
+
+    pub(crate) fn order(&self, a: usize, b: usize) -> BondOrder {
+        self.orders[&bond_key(a, b)]
+    }
+
+    fn mappings(
+        &self,
+        target: &MatchGraph,
+        exact: bool,
+        limit: usize,
+    ) -> Vec<AtomMapping> {
+        let atom_ok = |p: usize, t: usize| {
+            self.same_atom(p, target, t)
+                && (!exact
+                    || self.compound.implicit_hydrogens(p)
+                        == target.compound.implicit_hydrogens(t))
+        };
+        let bond_ok = |(p, q): (usize, usize), (s, t): (usize, usize)| {
+            self.order(p, q) == target.order(s, t)
+        };
+        Vf2::new(
+            &self.adjacency,
+            &target.adjacency,
+            &atom_ok,
+            &bond_ok,
+            exact,
+        )
+        .mappings(limit)
+        .into_iter()
+        .map(|mapping| {
+            mapping
+                .into_iter()
+                .enumerate()
+                .map(|(p, t)| (self.atoms[p], target.atoms[t]))
+                .collect()
+        })
+        .collect()
+    }
+}
+
+impl Compound {
+    /// Every way `pattern` sits in the compound, as a mapping from the
+    /// pattern's atoms to the compound's.
+    ///
+    /// Atoms match on element, isotope and charge, bonds on their order
+    /// (aromatic rings match whichever Kekulé structure was drawn). Plain
+    /// hydrogens are left open, so benzene is found in toluene; patterns
+    /// which need hydrogens counted are written in SMARTS. Symmetric
+    /// matches are all listed.
+    pub fn matches(&self, pattern: &Compound) -> Vec<AtomMapping> {
+        MatchGraph::of(pattern).mappings(
+            &MatchGraph::of(self),
+            false,
+            usize::MAX,
+        )
+    }
+
+    /// Whether `pattern` is a substructure of the compound
+    pub fn contains(&self, pattern: &Compound) -> bool {
+        !MatchGraph::of(pattern)
+            .mappings(&MatchGraph::of(self), false, 1)
+            .is_empty()
+    }
+
+    /// Mapping of this compound's atoms onto `other`'s when both are the
+    /// same molecular graph, however their atoms are ordered or placed
+    pub fn isomorphism(&self, other: &Compound) -> Option<AtomMapping> {
+        MatchGraph::of(self)
+            .mappings(&MatchGraph::of(other), true, 1)
+            .pop()
+    }
+
+    /// Whether both compounds are the same molecular graph (constitution),
+    /// ignoring atom order and locations
+    pub fn is_isomorphic(&self, other: &Compound) -> bool {
+        self.isomorphism(other).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matter::{
+        atom::Atom,
+        compound::{
+            bond::BondOrder,
+            builder::fixtures::{bare, build},
+            substituent::{AlkylGroup, Substituent},
+        },
+    };
+
+    #[test]
+    fn substructure_contains() {
+        let ethanol =
+            build(|b| b.linear_chain(2)?.substitute(2, Substituent::Hydroxy));
+        let ether = build(|b| {
+            b.linear_chain(3)?.replace_atom(2, Atom::new_unchecked(8))
+        });
+        let c_o = bare(
+            vec![Atom::carbon(), Atom::new_unchecked(8)],
+            &[(0, 1, BondOrder::Single)],
+        );
+        assert!(ethanol.contains(&c_o));
+        assert!(ether.contains(&c_o));
+        let carbonyl = bare(
+            vec![Atom::carbon(), Atom::new_unchecked(8)],
+            &[(0, 1, BondOrder::Double)],
+        );
+        assert!(!ethanol.contains(&carbonyl));
+
+        let benzene = build(|b| b.benzene());
+        let toluene = build(|b| b.benzene()?.add_alkyl(1, AlkylGroup::Methyl));
+        assert!(toluene.contains(&benzene));
+        assert!(!benzene.contains(&toluene));
+        let cyclohexane = build(|b| b.ring(6));
+        assert!(!toluene.contains(&cyclohexane));
+    }
+
+    #[test]
+    fn substructure_mappings() {
+        let propane = build(|b| b.linear_chain(3));
+        let c_c = bare(
+            vec![Atom::carbon(), Atom::carbon()],
+            &[(0, 1, BondOrder::Single)],
+        );
+        let mappings = propane.matches(&c_c);
+        assert_eq!(mappings.len(), 4);
+        assert!(mappings.contains(&AtomMapping::from([(0, 1), (1, 2)])));
+        // Twelve symmetries of the hexagon
+        let benzene = build(|b| b.benzene());
+        assert_eq!(benzene.matches(&benzene).len(), 12);
+        let bromide = build(|b| b.linear_chain(3)?.brominate(2));
+        let c_br = bare(
+            vec![Atom::bromine(), Atom::carbon()],
+            &[(0, 1, BondOrder::Single)],
+        );
+        let bromine = bromide
+            .atoms()
+            .iter()
+            .position(|atom| *atom == Atom::bromine())
+            .expect("Bromine");
+        assert_eq!(
+            bromide.matches(&c_br),
+            vec![AtomMapping::from([(0, bromine), (1, 1)])]
+        );
+    }
+
+    #[test]
+    fn substructure_isomorphism() {
+        let from_start = build(|b| b.linear_chain(4)?.brominate(2));
+        let from_end = build(|b| b.linear_chain(4)?.brominate(3));
+        let primary = build(|b| b.linear_chain(4)?.brominate(1));
+        assert!(from_start.is_isomorphic(&from_end));
+        assert!(from_start.is_isomorphic(&from_end.with_implicit_hydrogens()));
+        assert!(!from_start.is_isomorphic(&primary));
+        assert_ne!(from_start, from_end);
+        let mapping = from_start.isomorphism(&from_end).expect("Isomorphic");
+        assert_eq!(mapping[&1], 2);
+
+        // The other Kekulé structure of benzene
+        let kekule = build(|b| {
+            b.ring(6)?
+                .double_bond(2, 3)?
+                .double_bond(4, 5)?
+                .double_bond(6, 1)
+        });
+        assert!(kekule.is_isomorphic(&build(|b| b.benzene())));
+        let butane = build(|b| b.linear_chain(4));
+        let isobutane =
+            build(|b| b.linear_chain(3)?.add_alkyl(2, AlkylGroup::Methyl));
+        assert!(!butane.is_isomorphic(&isobutane));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::matter::{atom::Atom, compound::deserializer::Chain};
 
-trait Compound {
+pub trait Compound {
     fn get_chain() -> Chain<Atom>;
     fn get_backbone() -> Chain<Atom>;
 }
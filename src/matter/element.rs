@@ -1,11 +1,10 @@
-use crate::constants::{ELEMENTS, ELEMENTS_VEC};
+use crate::constants::{ELECTRONEGATIVITIES, ELEMENTS, ELEMENTS_VEC};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Element {
     pub number: u8,
 }
 
-#[allow(dead_code)]
 impl Element {
     pub fn new(number: u8) -> Option<Self> {
         if number != 0 && number as usize <= ELEMENTS_VEC.len() {
@@ -15,6 +14,7 @@ impl Element {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input_str: &str) -> Option<Self> {
         if !ELEMENTS.contains(input_str) {
             None
@@ -30,11 +30,13 @@ impl Element {
     }
 
     pub fn new_unchecked(number: u8) -> Self {
-        Element::new(number).expect(&format!(
-            "Invalid element number: {}. `Element::new_unchecked` expects a \
-                valid atomic number.",
-            number
-        ))
+        Element::new(number).unwrap_or_else(|| {
+            panic!(
+                "Invalid element number: {}. `Element::new_unchecked` expects \
+                    a valid atomic number.",
+                number
+            )
+        })
     }
 
     pub fn hydrogen() -> Self {
@@ -48,6 +50,94 @@ impl Element {
     pub fn as_str(&self) -> &str {
         ELEMENTS_VEC.get(self.number as usize - 1).unwrap()
     }
+
+    /// Row of the periodic table (1-7)
+    pub fn period(&self) -> u8 {
+        match self.number {
+            1..=2 => 1,
+            3..=10 => 2,
+            11..=18 => 3,
+            19..=36 => 4,
+            37..=54 => 5,
+            55..=86 => 6,
+            _ => 7,
+        }
+    }
+
+    /// IUPAC group (1-18). Lanthanides and actinides are placed in group 3.
+    pub fn group(&self) -> u8 {
+        let n = self.number;
+        match self.period() {
+            1 => {
+                if n == 1 {
+                    1
+                } else {
+                    18
+                }
+            }
+            2 | 3 => {
+                let pos = n - if n <= 10 { 3 } else { 11 };
+                if pos < 2 {
+                    pos + 1
+                } else {
+                    pos + 11
+                }
+            }
+            4 | 5 => n - if n <= 36 { 19 } else { 37 } + 1,
+            _ => {
+                let pos = n - if n <= 86 { 55 } else { 87 };
+                match pos {
+                    0 | 1 => pos + 1,
+                    2..=16 => 3,
+                    _ => pos - 13,
+                }
+            }
+        }
+    }
+
+    /// Electrons available for bonding (main group elements).
+    pub fn valence_electrons(&self) -> u8 {
+        match (self.number, self.group()) {
+            (2, _) => 2,
+            (_, g @ 13..=18) => g - 10,
+            (_, g) => g,
+        }
+    }
+
+    /// Pauling electronegativity (0.0 when untabulated)
+    pub fn electronegativity(&self) -> f64 {
+        ELECTRONEGATIVITIES[self.number as usize - 1]
+    }
+
+    /// Common numbers of bonds formed by the neutral element, lowest first.
+    /// Empty when the element has no standard covalent valence.
+    pub fn standard_valences(&self) -> &'static [u8] {
+        match self.number {
+            1 | 3 | 9 | 11 | 19 => &[1],
+            4 | 8 | 12 => &[2],
+            5 | 7 | 13 => &[3],
+            6 | 14 => &[4],
+            15 | 33 => &[3, 5],
+            16 | 34 => &[2, 4, 6],
+            17 | 35 | 53 => &[1],
+            _ => &[],
+        }
+    }
+
+    /// Whether d orbitals allow more than eight electrons around the atom.
+    pub fn can_expand_octet(&self) -> bool {
+        self.period() >= 3
+    }
+
+    /// Maximum number of electrons the valence shell holds in a Lewis
+    /// structure.
+    pub fn valence_shell_capacity(&self) -> u8 {
+        match self.period() {
+            1 => 2,
+            2 => 8,
+            _ => 12,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -70,4 +160,47 @@ mod tests {
         assert_eq!(Element::new_unchecked(3).as_str(), "Li");
         assert_eq!(Element::new_unchecked(118).as_str(), "Og");
     }
+
+    #[test]
+    fn element_periodic_position() {
+        let cases = [
+            (1, 1, 1),
+            (2, 1, 18),
+            (6, 2, 14),
+            (8, 2, 16),
+            (12, 3, 2),
+            (17, 3, 17),
+            (26, 4, 8),
+            (35, 4, 17),
+            (53, 5, 17),
+            (57, 6, 3),
+            (72, 6, 4),
+            (86, 6, 18),
+            (118, 7, 18),
+        ];
+        for (number, period, group) in cases {
+            let element = Element::new_unchecked(number);
+            assert_eq!(element.period(), period, "period of {}", number);
+            assert_eq!(element.group(), group, "group of {}", number);
+        }
+    }
+
+    #[test]
+    fn element_valence_electrons() {
+        assert_eq!(Element::hydrogen().valence_electrons(), 1);
+        assert_eq!(Element::carbon().valence_electrons(), 4);
+        assert_eq!(Element::new_unchecked(2).valence_electrons(), 2);
+        assert_eq!(Element::new_unchecked(7).valence_electrons(), 5);
+        assert_eq!(Element::new_unchecked(16).valence_electrons(), 6);
+        assert_eq!(Element::new_unchecked(35).valence_electrons(), 7);
+    }
+
+    #[test]
+    fn element_standard_valences() {
+        assert_eq!(Element::carbon().standard_valences(), &[4]);
+        assert_eq!(Element::new_unchecked(7).standard_valences(), &[3]);
+        assert_eq!(Element::new_unchecked(16).standard_valences(), &[2, 4, 6]);
+        assert_eq!(Element::new_unchecked(15).standard_valences(), &[3, 5]);
+        assert!(Element::new_unchecked(2).standard_valences().is_empty());
+    }
 }
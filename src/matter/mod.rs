@@ -1,4 +1,6 @@
 pub mod atom;
 pub mod compound;
 pub mod element;
+// Name kept from the original prototype
+#[allow(clippy::module_inception)]
 pub mod matter;
@@ -6,15 +6,13 @@ use std::{
 use itertools::Itertools;
 use lazy_static::lazy_static;
 
-use crate::matter::atom::{self, Atom};
-
 #[derive(Debug, Clone)]
 pub struct Configuration {
     pub vec: Vec<Subshell>,
     pub electrons: u32,
 }
 
-/// TODO: Work on exceptions
+// TODO: Work on exceptions
 
 impl Configuration {
     /// Assuming neutral, unionized
@@ -180,9 +178,7 @@ impl Subshell {
 
     fn fill_by(&mut self, amount: u32) {
         let space = self.get_space();
-        if space == 0 {
-            return;
-        } else if space >= amount {
+        if space >= amount {
             self.current += amount;
         } else {
             self.current += space;